@@ -0,0 +1,153 @@
+//! Verification of KYC attestations signed by the registered attester.
+//!
+//! The attester's signature is checked by the Ed25519 program in the instruction that precedes
+//! the deposit. The Staker reads that instruction from the instructions sysvar and verifies that
+//! it covers exactly one signature, made by the attester over the expected message.
+
+use crate::error::ErrorCode;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        ed25519_program,
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+};
+
+const PUBKEY_SIZE: usize = 32;
+const SIGNATURE_SIZE: usize = 64;
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_SIZE: usize = 14;
+
+/// Ed25519 program value for offsets that point into the Ed25519 instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Verifies that the instruction preceding the current one is an Ed25519 program instruction
+/// verifying `attester`'s signature of `message`.
+pub fn verify_attestation(
+    instructions_sysvar: &AccountInfo,
+    attester: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let ed25519_index = current_index
+        .checked_sub(1)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    let ed25519_instruction =
+        load_instruction_at_checked(ed25519_index as usize, instructions_sysvar)?;
+    require_keys_eq!(
+        ed25519_instruction.program_id,
+        ed25519_program::ID,
+        ErrorCode::InvalidAttestation
+    );
+
+    let (signer, signed_message) = parse_ed25519_instruction(&ed25519_instruction.data)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    require!(
+        signer == attester.as_ref() && signed_message == message,
+        ErrorCode::InvalidAttestation
+    );
+    Ok(())
+}
+
+/// Returns the public key and message of an Ed25519 program instruction verifying a single
+/// signature whose data is all stored in the instruction itself.
+fn parse_ed25519_instruction(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    if data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SIZE || data[0] != 1 {
+        return None;
+    }
+
+    let offsets = &data[SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SIZE];
+    let read_u16 = |index: usize| u16::from_le_bytes([offsets[2 * index], offsets[2 * index + 1]]);
+    let signature_offset = read_u16(0) as usize;
+    let signature_instruction_index = read_u16(1);
+    let public_key_offset = read_u16(2) as usize;
+    let public_key_instruction_index = read_u16(3);
+    let message_offset = read_u16(4) as usize;
+    let message_size = read_u16(5) as usize;
+    let message_instruction_index = read_u16(6);
+
+    // the signature, public key and message must be read from this instruction, so that they are
+    // the ones the Ed25519 program verified rather than data of another instruction
+    if signature_instruction_index != CURRENT_INSTRUCTION
+        || public_key_instruction_index != CURRENT_INSTRUCTION
+        || message_instruction_index != CURRENT_INSTRUCTION
+    {
+        return None;
+    }
+
+    data.get(signature_offset..signature_offset.checked_add(SIGNATURE_SIZE)?)?;
+    let public_key = data.get(public_key_offset..public_key_offset.checked_add(PUBKEY_SIZE)?)?;
+    let message = data.get(message_offset..message_offset.checked_add(message_size)?)?;
+    Some((public_key, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds Ed25519 instruction data in the layout produced by the Solana SDK.
+    fn ed25519_instruction_data(
+        public_key: &[u8],
+        message: &[u8],
+        instruction_index: u16,
+    ) -> Vec<u8> {
+        let public_key_offset = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SIZE;
+        let signature_offset = public_key_offset + PUBKEY_SIZE;
+        let message_offset = signature_offset + SIGNATURE_SIZE;
+
+        let mut data = vec![1, 0];
+        for value in [
+            signature_offset as u16,
+            instruction_index,
+            public_key_offset as u16,
+            instruction_index,
+            message_offset as u16,
+            message.len() as u16,
+            instruction_index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(public_key);
+        data.extend_from_slice(&[7; SIGNATURE_SIZE]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn parses_signer_and_message() {
+        let attester = Pubkey::new_unique();
+        let data = ed25519_instruction_data(attester.as_ref(), b"attestation", CURRENT_INSTRUCTION);
+
+        let (signer, message) = parse_ed25519_instruction(&data).unwrap();
+        assert_eq!(signer, attester.as_ref());
+        assert_eq!(message, b"attestation");
+    }
+
+    #[test]
+    fn rejects_data_read_from_other_instructions() {
+        let attester = Pubkey::new_unique();
+        let data = ed25519_instruction_data(attester.as_ref(), b"attestation", 0);
+
+        assert!(parse_ed25519_instruction(&data).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        let attester = Pubkey::new_unique();
+        let data = ed25519_instruction_data(attester.as_ref(), b"attestation", CURRENT_INSTRUCTION);
+
+        // no signatures
+        let mut no_signatures = data.clone();
+        no_signatures[0] = 0;
+        assert!(parse_ed25519_instruction(&no_signatures).is_none());
+
+        // multiple signatures
+        let mut multiple_signatures = data.clone();
+        multiple_signatures[0] = 2;
+        assert!(parse_ed25519_instruction(&multiple_signatures).is_none());
+
+        // truncated message
+        assert!(parse_ed25519_instruction(&data[..data.len() - 1]).is_none());
+        assert!(parse_ed25519_instruction(&data[..8]).is_none());
+    }
+}
@@ -8,4 +8,37 @@ pub const STAKE_POOL_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
 
 pub const ANCHOR_DISCRIMINATOR: usize = 8;
 
+pub const ACCESS_VERSION: u8 = 1; // layout version of the Access account
+pub const ACCESS_RESERVED_SPACE: usize = 128; // bytes reserved in the Access account for new settings
+
 pub const ONE_SOL: u64 = 1_000_000_000; // 1 SOL in lamports
+
+pub const UNSTAKE_DELAY_EPOCHS: u64 = 1; // epochs between requesting and claiming an unstake
+
+#[cfg(not(feature = "localnet"))]
+pub const MIN_TIMELOCK_DELAY: i64 = 24 * 60 * 60; // 1 day in seconds
+#[cfg(feature = "localnet")]
+pub const MIN_TIMELOCK_DELAY: i64 = 0; // no minimum for local tests, which execute actions right away
+pub const MAX_TIMELOCK_DELAY: i64 = 30 * 24 * 60 * 60; // 30 days in seconds
+
+pub const MAX_GUARDIANS: usize = 10; // maximum number of guardian keys
+
+// Pause flags
+pub const PAUSE_DEPOSITS: u8 = 1 << 0; // SOL and stake deposits
+pub const PAUSE_VALIDATOR_DEPOSITS: u8 = 1 << 1; // deposits to a specific validator
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 2; // SOL withdrawals and unstake tickets
+pub const PAUSE_REBALANCING: u8 = 1 << 3; // stake manager stake changes and unstake ticket funding
+pub const PAUSE_VALIDATOR_CHANGES: u8 = 1 << 4; // adding and removing validators
+pub const PAUSE_ALL: u8 = PAUSE_DEPOSITS
+    | PAUSE_VALIDATOR_DEPOSITS
+    | PAUSE_WITHDRAWALS
+    | PAUSE_REBALANCING
+    | PAUSE_VALIDATOR_CHANGES;
+
+// Agent permission bits
+pub const AGENT_CAN_WHITELIST: u8 = 1 << 0; // whitelist users and manage whitelist expiries and the Merkle root
+pub const AGENT_CAN_BLACKLIST: u8 = 1 << 1; // blacklist users
+pub const AGENT_CAN_CLEAR: u8 = 1 << 2; // clear the status of users
+pub const AGENT_CAN_MANAGE_AGENTS: u8 = 1 << 3; // add and remove agents, only grantable by the owner
+pub const AGENT_ALL_PERMISSIONS: u8 =
+    AGENT_CAN_WHITELIST | AGENT_CAN_BLACKLIST | AGENT_CAN_CLEAR | AGENT_CAN_MANAGE_AGENTS;
@@ -22,4 +22,104 @@ pub enum ErrorCode {
     AlreadyCleared,
     #[msg("User is not whitelisted")]
     UserNotWhitelisted,
+    #[msg("Invalid stake pool")]
+    InvalidStakePool,
+    #[msg("Invalid pool mint")]
+    InvalidPoolMint,
+    #[msg("Invalid reserve stake account")]
+    InvalidReserveStake,
+    #[msg("Invalid validator list")]
+    InvalidValidatorList,
+    #[msg("Invalid fee token account")]
+    InvalidFeeTokenAccount,
+    #[msg("Invalid withdraw authority")]
+    InvalidWithdrawAuthority,
+    #[msg("Transient stake account does not match the transient stake seed")]
+    InvalidTransientStakeAccount,
+    #[msg("Ephemeral stake account does not match the ephemeral stake seed")]
+    InvalidEphemeralStakeAccount,
+    #[msg("Pool tokens received are below the minimum requested")]
+    SlippageExceeded,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Stake account is not delegated")]
+    StakeAccountNotDelegated,
+    #[msg("Validator is not part of the stake pool")]
+    ValidatorNotInPool,
+    #[msg("User is blacklisted")]
+    UserBlacklisted,
+    #[msg("Unstake ticket is not claimable yet")]
+    UnstakeTicketNotClaimable,
+    #[msg("Funding exceeds the SOL owed to outstanding unstake tickets")]
+    UnstakeFundingExceeded,
+    #[msg("SOL owed to the unstake ticket is not funded yet")]
+    UnstakeTicketNotFunded,
+    #[msg("Withdrawal would use the SOL funded for unstake tickets")]
+    ReserveFundedForUnstakeTickets,
+    #[msg("Deposit exceeds the deposit cap")]
+    DepositCapExceeded,
+    #[msg("Deposit exceeds the user's deposit limit")]
+    UserDepositLimitExceeded,
+    #[msg("User's whitelisting has expired")]
+    WhitelistExpired,
+    #[msg("Whitelist expiry must be in the future")]
+    InvalidWhitelistExpiry,
+    #[msg("Number of user status accounts does not match the number of users")]
+    UserStatusAccountsMismatch,
+    #[msg("User status account does not match the user")]
+    InvalidUserStatusAccount,
+    #[msg("Invalid whitelist proof")]
+    InvalidWhitelistProof,
+    #[msg("Attestation is not signed by the registered attester")]
+    InvalidAttestation,
+    #[msg("Attestation has expired")]
+    AttestationExpired,
+    #[msg("Agent does not have the permission for this action")]
+    MissingAgentPermission,
+    #[msg("Invalid agent permissions")]
+    InvalidAgentPermissions,
+    #[msg("Maximum number of agents reached")]
+    MaxAgentsReached,
+    #[msg("User status must be cleared before it can be closed")]
+    UserStatusNotCleared,
+    #[msg("Invalid rent recipient")]
+    InvalidRentRecipient,
+    #[msg("Pool tokens must be minted to a pool token account owned by the depositor")]
+    InvalidPoolTokenAccount,
+    #[msg("Timelock delay must be between the minimum and the maximum delay")]
+    InvalidTimelockDelay,
+    #[msg("Pending action does not match the instruction")]
+    PendingActionMismatch,
+    #[msg("Pending action cannot be executed yet")]
+    TimelockNotElapsed,
+    #[msg("Guardian already exists")]
+    GuardianAlreadyExists,
+    #[msg("Guardian does not exist")]
+    GuardianNotFound,
+    #[msg("Maximum number of guardians reached")]
+    MaxGuardiansReached,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+    #[msg("Pending ownership has expired")]
+    PendingOwnershipExpired,
+    #[msg("Pending owner deadline must be in the future")]
+    InvalidPendingOwnerDeadline,
+    #[msg("Access account is already at the latest version")]
+    AccessAlreadyMigrated,
+    #[msg("Access account layout is not recognised")]
+    UnknownAccessLayout,
+    #[msg("User status account is already at the latest layout")]
+    UserStatusAlreadyMigrated,
+    #[msg("User status account layout is not recognised")]
+    UnknownUserStatusLayout,
+    #[msg("Blacklisting or clearing a user requires a reason and a case reference")]
+    MissingStatusChangeReason,
+    #[msg("Agent account is already at the latest layout")]
+    AgentAlreadyMigrated,
+    #[msg("Agent account layout is not recognised")]
+    UnknownAgentLayout,
+    #[msg("User status with deposit limits or deposit history cannot be closed")]
+    UserStatusHasDepositHistory,
+    #[msg("User status was cleared by an agent and cannot be restored by an attestation")]
+    UserStatusCleared,
 }
@@ -1,4 +1,8 @@
-use crate::{state::*, ANCHOR_DISCRIMINATOR};
+use crate::{
+    error::ErrorCode, instructions::setters::record_stake_pool, migration, state::*,
+    ACCESS_VERSION, AGENT_ALL_PERMISSIONS, ANCHOR_DISCRIMINATOR, MIN_TIMELOCK_DELAY,
+    STAKE_POOL_PROGRAM_ID,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::Token2022;
 
@@ -43,6 +47,10 @@ pub struct InitializeStaker<'info> {
     #[account()]
     pub stake_manager_info: AccountInfo<'info>,
 
+    /// CHECK: The stake pool account, deserialized in the instruction
+    #[account(owner = STAKE_POOL_PROGRAM_ID @ ErrorCode::InvalidStakePool)]
+    pub stake_pool: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
@@ -50,13 +58,66 @@ pub struct InitializeStaker<'info> {
 /// Processes the `InitializeStaker` instruction
 pub fn process_initialize_staker(ctx: Context<InitializeStaker>) -> Result<()> {
     let access_control = &mut ctx.accounts.access;
+    access_control.version = ACCESS_VERSION;
     access_control.owner = ctx.accounts.owner_info.key();
-    access_control.is_paused = false;
+    access_control.paused_flags = 0;
     access_control.stake_manager = ctx.accounts.stake_manager_info.key();
+    access_control.active_agents = 1;
+    access_control.timelock_delay = MIN_TIMELOCK_DELAY;
+    record_stake_pool(access_control, &ctx.accounts.stake_pool)?;
+
+    let owner_agent = &mut ctx.accounts.owner_agent_account;
+    owner_agent.permissions = AGENT_ALL_PERMISSIONS;
+    owner_agent.created_by = ctx.accounts.owner_info.key();
 
     emit_cpi!(StakerInitialized {
         owner: ctx.accounts.owner_info.key(),
         stake_manager: ctx.accounts.stake_manager_info.key(),
+        stake_pool: ctx.accounts.stake_pool.key(),
     });
     Ok(())
 }
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct MigrateAccess<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: The access account, deserialized in the instruction as its layout may be outdated
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `MigrateAccess` instruction
+pub fn process_migrate_access(ctx: Context<MigrateAccess>) -> Result<()> {
+    let access_info = &ctx.accounts.access;
+    let (old_version, access) = migration::migrate_access_data(&access_info.try_borrow_data()?)?;
+    require_keys_eq!(
+        access.owner,
+        ctx.accounts.owner.key(),
+        ErrorCode::NotAuthorized
+    );
+
+    // grow the account to the space of the latest layout
+    migration::grow_account(
+        access_info,
+        &ctx.accounts.owner,
+        &ctx.accounts.system_program,
+        ANCHOR_DISCRIMINATOR + Access::INIT_SPACE,
+    )?;
+    access.try_serialize(&mut &mut access_info.try_borrow_mut_data()?[..])?;
+
+    emit_cpi! {AccessMigrated {
+        old_version,
+        new_version: ACCESS_VERSION,
+    }};
+    Ok(())
+}
@@ -1,11 +1,15 @@
 pub mod initialize;
 pub mod setters;
 pub mod staking;
+pub mod timelock;
 pub mod validators;
 pub mod whitelist;
+pub mod withdrawals;
 
 pub use initialize::*;
 pub use setters::*;
 pub use staking::*;
+pub use timelock::*;
 pub use validators::*;
 pub use whitelist::*;
+pub use withdrawals::*;
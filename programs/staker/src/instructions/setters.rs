@@ -1,17 +1,16 @@
 use crate::error::ErrorCode;
-use crate::{state::*, ANCHOR_DISCRIMINATOR};
+use crate::instructions::timelock::require_action_executable;
+use crate::{state::*, ANCHOR_DISCRIMINATOR, MAX_GUARDIANS, PAUSE_ALL, STAKE_POOL_PROGRAM_ID};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
 #[event_cpi]
 pub struct Pause<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    pub signer: Signer<'info>,
 
     #[account(
         mut,
-        has_one = owner @ ErrorCode::NotAuthorized,
-        constraint = !access.is_paused @ ErrorCode::ContractPaused,
+        constraint = access.is_owner_or_guardian(signer.key) @ ErrorCode::NotAuthorized,
         seeds = [b"access"],
         bump
     )]
@@ -19,10 +18,20 @@ pub struct Pause<'info> {
 }
 
 /// Processes the `Pause` instruction
-pub fn process_pause(ctx: Context<Pause>) -> Result<()> {
+pub fn process_pause(ctx: Context<Pause>, flags: u8) -> Result<()> {
+    require_pause_flags_valid(flags)?;
     let access = &mut ctx.accounts.access;
-    access.is_paused = true;
-    emit_cpi! {StakerPaused {}};
+    // fail if every operation in the flags is already paused
+    require!(
+        access.paused_flags & flags != flags,
+        ErrorCode::ContractPaused
+    );
+
+    access.paused_flags |= flags;
+    emit_cpi! {StakerPaused {
+        flags,
+        paused_flags: access.paused_flags,
+    }};
     Ok(())
 }
 
@@ -35,18 +44,36 @@ pub struct Unpause<'info> {
     #[account(
         mut,
         has_one = owner @ ErrorCode::NotAuthorized,
-        constraint = access.is_paused @ ErrorCode::NotPaused,
         seeds = [b"access"],
         bump
     )]
     pub access: Account<'info, Access>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
 }
 
 /// Processes the `Unpause` instruction
-pub fn process_unpause(ctx: Context<Unpause>) -> Result<()> {
+pub fn process_unpause(ctx: Context<Unpause>, flags: u8) -> Result<()> {
+    let id = require_action_executable(
+        &ctx.accounts.pending_action,
+        &TimelockedAction::Unpause { flags },
+    )?;
+    require_pause_flags_valid(flags)?;
     let access = &mut ctx.accounts.access;
-    access.is_paused = false;
-    emit_cpi! {StakerUnpaused {}};
+    require!(access.is_paused(flags), ErrorCode::NotPaused);
+
+    access.paused_flags &= !flags;
+    emit_cpi! {StakerUnpaused {
+        flags,
+        paused_flags: access.paused_flags,
+    }};
+    emit_cpi! {ActionExecuted { id }};
     Ok(())
 }
 
@@ -63,18 +90,73 @@ pub struct SetStakerPendingOwner<'info> {
         bump
     )]
     pub access: Account<'info, Access>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
 }
 
 /// Processes the `SetStakerPendingOwner` instruction
 pub fn process_set_pending_owner(
     ctx: Context<SetStakerPendingOwner>,
     pending_owner: Pubkey,
+    deadline: Option<u64>,
 ) -> Result<()> {
+    let id = require_action_executable(
+        &ctx.accounts.pending_action,
+        &TimelockedAction::SetPendingOwner {
+            pending_owner,
+            deadline,
+        },
+    )?;
+    if let Some(deadline) = deadline {
+        require_gt!(
+            deadline,
+            Clock::get()?.slot,
+            ErrorCode::InvalidPendingOwnerDeadline
+        );
+    }
+
     let access_account = &mut ctx.accounts.access;
     access_account.pending_owner = Some(pending_owner);
+    access_account.pending_owner_deadline = deadline;
     emit_cpi! {SetPendingOwner {
         current_owner: access_account.owner,
         pending_owner,
+        deadline,
+    }};
+    emit_cpi! {ActionExecuted { id }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct CancelPendingOwner<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        constraint = access.pending_owner.is_some() @ ErrorCode::PendingOwnerNotSet,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `CancelPendingOwner` instruction
+pub fn process_cancel_pending_owner(ctx: Context<CancelPendingOwner>) -> Result<()> {
+    let access_account = &mut ctx.accounts.access;
+    let pending_owner = access_account.pending_owner.take().unwrap();
+    access_account.pending_owner_deadline = None;
+    emit_cpi! {PendingOwnerCancelled {
+        current_owner: access_account.owner,
+        pending_owner,
     }};
     Ok(())
 }
@@ -98,9 +180,18 @@ pub struct StakerClaimOwnership<'info> {
 /// Processes the `ClaimOwnership` instruction
 pub fn process_claim_ownership(ctx: Context<StakerClaimOwnership>) -> Result<()> {
     let access_account = &mut ctx.accounts.access;
+    if let Some(deadline) = access_account.pending_owner_deadline {
+        require_gte!(
+            deadline,
+            Clock::get()?.slot,
+            ErrorCode::PendingOwnershipExpired
+        );
+    }
+
     let old_owner = access_account.owner;
     access_account.owner = access_account.pending_owner.unwrap();
     access_account.pending_owner = None;
+    access_account.pending_owner_deadline = None;
     emit_cpi! {ClaimedOwnership {
         old_owner,
         new_owner: access_account.owner,
@@ -122,6 +213,14 @@ pub struct SetStakeManager<'info> {
     )]
     pub access: Account<'info, Access>,
 
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
     #[account(
         init,
         payer = owner,
@@ -152,11 +251,267 @@ pub struct SetStakeManager<'info> {
 
 /// Processes the `SetStakeManager` instruction
 pub fn process_set_stake_manager(ctx: Context<SetStakeManager>) -> Result<()> {
+    let id = require_action_executable(
+        &ctx.accounts.pending_action,
+        &TimelockedAction::SetStakeManager {
+            new_stake_manager: ctx.accounts.new_stake_manager.key(),
+        },
+    )?;
+
     let access = &mut ctx.accounts.access;
     access.stake_manager = ctx.accounts.new_stake_manager.key();
     emit_cpi! {StakeManagerSet {
         old_stake_manager: ctx.accounts.old_stake_manager.key(),
         new_stake_manager: ctx.accounts.new_stake_manager.key(),
     }};
+    emit_cpi! {ActionExecuted { id }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct SetStakePool<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
+    /// CHECK: The stake pool account, deserialized in the instruction
+    #[account(owner = STAKE_POOL_PROGRAM_ID @ ErrorCode::InvalidStakePool)]
+    pub stake_pool: AccountInfo<'info>,
+}
+
+/// Processes the `SetStakePool` instruction
+pub fn process_set_stake_pool(ctx: Context<SetStakePool>) -> Result<()> {
+    let stake_pool_address = ctx.accounts.stake_pool.key();
+    let id = require_action_executable(
+        &ctx.accounts.pending_action,
+        &TimelockedAction::SetStakePool {
+            stake_pool: stake_pool_address,
+        },
+    )?;
+    let access = &mut ctx.accounts.access;
+    let old_stake_pool = access.stake_pool;
+    record_stake_pool(access, &ctx.accounts.stake_pool)?;
+
+    emit_cpi! {StakePoolSet {
+        old_stake_pool,
+        new_stake_pool: stake_pool_address,
+    }};
+    emit_cpi! {ActionExecuted { id }};
+    Ok(())
+}
+
+/// Records the stake pool and the pool accounts derived from it in the access account
+pub fn record_stake_pool(access: &mut Access, stake_pool_info: &AccountInfo) -> Result<()> {
+    let stake_pool = StakePool::from_account_info(stake_pool_info)?;
+    access.stake_pool = stake_pool_info.key();
+    access.pool_mint = stake_pool.pool_mint;
+    access.reserve_stake = stake_pool.reserve_stake;
+    access.validator_list = stake_pool.validator_list;
+    access.manager_fee_account = stake_pool.manager_fee_account;
+    access.withdraw_authority = stake_pool.withdraw_authority(&stake_pool_info.key())?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct SetDepositCaps<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `SetDepositCaps` instruction
+pub fn process_set_deposit_caps(
+    ctx: Context<SetDepositCaps>,
+    max_total_lamports: Option<u64>,
+    max_epoch_deposit_lamports: Option<u64>,
+) -> Result<()> {
+    let access = &mut ctx.accounts.access;
+    let old_max_total_lamports = access.max_total_lamports;
+    let old_max_epoch_deposit_lamports = access.max_epoch_deposit_lamports;
+    access.max_total_lamports = max_total_lamports;
+    access.max_epoch_deposit_lamports = max_epoch_deposit_lamports;
+    emit_cpi! {DepositCapsSet {
+        old_max_total_lamports,
+        new_max_total_lamports: max_total_lamports,
+        old_max_epoch_deposit_lamports,
+        new_max_epoch_deposit_lamports: max_epoch_deposit_lamports,
+    }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct SetAttester<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `SetAttester` instruction
+pub fn process_set_attester(ctx: Context<SetAttester>, attester: Option<Pubkey>) -> Result<()> {
+    let access = &mut ctx.accounts.access;
+    let old_attester = access.attester;
+    access.attester = attester;
+    emit_cpi! {AttesterSet {
+        old_attester,
+        new_attester: attester,
+    }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct SetMaxAgents<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `SetMaxAgents` instruction
+pub fn process_set_max_agents(ctx: Context<SetMaxAgents>, max_agents: Option<u32>) -> Result<()> {
+    let access = &mut ctx.accounts.access;
+    let old_max_agents = access.max_agents;
+    access.max_agents = max_agents;
+    emit_cpi! {MaxAgentsSet {
+        old_max_agents,
+        new_max_agents: max_agents,
+    }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct SetRentRecipient<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `SetRentRecipient` instruction
+pub fn process_set_rent_recipient(
+    ctx: Context<SetRentRecipient>,
+    rent_recipient: Option<Pubkey>,
+) -> Result<()> {
+    let access = &mut ctx.accounts.access;
+    let old_rent_recipient = access.rent_recipient;
+    access.rent_recipient = rent_recipient;
+    emit_cpi! {RentRecipientSet {
+        old_rent_recipient,
+        new_rent_recipient: rent_recipient,
+    }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct AddGuardian<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `AddGuardian` instruction
+pub fn process_add_guardian(ctx: Context<AddGuardian>, guardian: Pubkey) -> Result<()> {
+    let access = &mut ctx.accounts.access;
+    require!(
+        !access.guardians.contains(&guardian),
+        ErrorCode::GuardianAlreadyExists
+    );
+    require_gt!(
+        MAX_GUARDIANS,
+        access.guardians.len(),
+        ErrorCode::MaxGuardiansReached
+    );
+    access.guardians.push(guardian);
+    emit_cpi! {GuardianAdded { guardian }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct RemoveGuardian<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `RemoveGuardian` instruction
+pub fn process_remove_guardian(ctx: Context<RemoveGuardian>, guardian: Pubkey) -> Result<()> {
+    let access = &mut ctx.accounts.access;
+    let index = access
+        .guardians
+        .iter()
+        .position(|key| *key == guardian)
+        .ok_or(ErrorCode::GuardianNotFound)?;
+    access.guardians.remove(index);
+    emit_cpi! {GuardianRemoved { guardian }};
+    Ok(())
+}
+
+/// Fails unless the flags are a non-empty combination of the pause flags
+fn require_pause_flags_valid(flags: u8) -> Result<()> {
+    require!(
+        flags != 0 && flags & !PAUSE_ALL == 0,
+        ErrorCode::InvalidPauseFlags
+    );
     Ok(())
 }
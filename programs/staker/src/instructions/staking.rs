@@ -1,15 +1,27 @@
-use crate::{constants::STAKE_POOL_PROGRAM_ID, error::ErrorCode, state::*, ANCHOR_DISCRIMINATOR};
+use crate::{
+    attestation,
+    constants::{PAUSE_DEPOSITS, PAUSE_VALIDATOR_DEPOSITS, STAKE_POOL_PROGRAM_ID},
+    error::ErrorCode,
+    merkle, stake_pool_cpi,
+    state::*,
+    ANCHOR_DISCRIMINATOR,
+};
 use anchor_lang::{
     prelude::*,
     solana_program::{
-        instruction::Instruction,
-        program::invoke_signed,
-        stake,
-        sysvar::{clock, stake_history},
+        program::invoke,
+        stake::{
+            self,
+            state::{StakeAuthorize, StakeStateV2},
+        },
+        sysvar::{clock, instructions, stake_history},
     },
 };
 
-use anchor_spl::token::spl_token::ID as TOKEN_PROGRAM_ID;
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address, AssociatedToken},
+    token::{spl_token::ID as TOKEN_PROGRAM_ID, TokenAccount},
+};
 
 #[derive(Accounts)]
 #[event_cpi]
@@ -19,7 +31,6 @@ pub struct Deposit<'info> {
 
     #[account(
         init_if_needed,
-        constraint = user_whitelist_account.status == WhitelistUserStatus::Whitelisted @ ErrorCode::UserNotWhitelisted,
         payer = user,
         space = ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE,
         seeds = [b"user", user.key().as_ref()],
@@ -29,26 +40,34 @@ pub struct Deposit<'info> {
 
     #[account(
         mut,
-        constraint = !access.is_paused @ ErrorCode::ContractPaused,
         seeds = [b"access"],
         bump,
     )]
     pub access: Box<Account<'info, Access>>,
 
     /// CHECK: the stake pool account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
     pub stake_pool: AccountInfo<'info>,
 
     /// CHECK: the deposit authority PDA
-    #[account(mut)]
+    #[account(
+        seeds = [b"deposit"],
+        bump
+    )]
     pub deposit_authority: AccountInfo<'info>,
 
     /// CHECK: the withdraw authority PDA
-    #[account(mut)]
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
     pub withdraw_authority: AccountInfo<'info>,
 
     /// CHECK: the reserve account of the stake pool
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
     pub pool_reserve: AccountInfo<'info>,
 
     /// CHECK: User's pool token associated token account
@@ -56,11 +75,17 @@ pub struct Deposit<'info> {
     pub user_pool_token_account: AccountInfo<'info>,
 
     /// CHECK: Fee token account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.manager_fee_account @ ErrorCode::InvalidFeeTokenAccount
+    )]
     pub fee_token_account: AccountInfo<'info>,
 
     /// CHECK: Pool token mint
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.pool_mint @ ErrorCode::InvalidPoolMint
+    )]
     pub pool_mint: AccountInfo<'info>,
 
     /// CHECK: Referral fee token account (can be same as fee)
@@ -75,61 +100,263 @@ pub struct Deposit<'info> {
     #[account(address = STAKE_POOL_PROGRAM_ID)]
     pub stake_pool_program: AccountInfo<'info>,
 
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
     pub system_program: Program<'info, System>,
 }
 
 /// Processes the `Deposit` instruction
-pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+pub fn process_deposit(
+    ctx: Context<Deposit>,
+    amount: u64,
+    min_pool_tokens_out: Option<u64>,
+    whitelist_proof: Option<WhitelistProof>,
+) -> Result<()> {
+    require_whitelisted(
+        &mut ctx.accounts.user_whitelist_account,
+        ctx.accounts.access.whitelist_merkle_root,
+        ctx.accounts.user.key,
+        whitelist_proof,
+    )?;
     let accounts = &ctx.accounts;
+    prepare_user_pool_token_account(
+        &accounts.user,
+        &accounts.user,
+        &accounts.user_pool_token_account,
+        &accounts.pool_mint,
+        &accounts.token_program,
+        &accounts.associated_token_program,
+        &accounts.system_program,
+    )?;
 
-    // derive the deposit authority PDA from the staker program
-    let (deposit_authority_pda, deposit_authority_bump) =
-        Pubkey::find_program_address(&[b"deposit"], ctx.program_id);
+    let cpi_accounts = stake_pool_cpi::DepositSol {
+        stake_pool: accounts.stake_pool.to_account_info(),
+        withdraw_authority: accounts.withdraw_authority.to_account_info(),
+        reserve_stake: accounts.pool_reserve.to_account_info(),
+        lamports_from: accounts.user.to_account_info(),
+        pool_tokens_to: accounts.user_pool_token_account.to_account_info(),
+        manager_fee_account: accounts.fee_token_account.to_account_info(),
+        referrer_pool_tokens_account: accounts.referral_fee_token_account.to_account_info(),
+        pool_mint: accounts.pool_mint.to_account_info(),
+        system_program: accounts.system_program.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+        deposit_authority: accounts.deposit_authority.to_account_info(),
+    };
+    let stake_pool_program = accounts.stake_pool_program.to_account_info();
 
-    // Prepare the DepositSol instruction
-    let instruction_data = {
-        let mut data = vec![14]; // Instruction index for DepositSol
-        data.extend_from_slice(&amount.to_le_bytes()); // Deposit amount as u64 (little-endian)
-        data
+    deposit_sol(
+        &mut ctx.accounts.access,
+        &mut ctx.accounts.user_whitelist_account,
+        stake_pool_program,
+        cpi_accounts,
+        ctx.bumps.deposit_authority,
+        amount,
+        min_pool_tokens_out,
+    )?;
+
+    emit_cpi! {
+        Deposited {
+            amount,
+        }
     };
 
-    let deposit_sol_ix = Instruction {
-        program_id: STAKE_POOL_PROGRAM_ID,
-        accounts: vec![
-            AccountMeta::new(accounts.stake_pool.key(), false), // Stake pool
-            AccountMeta::new_readonly(accounts.withdraw_authority.key(), false), // Withdraw authority PDA
-            AccountMeta::new(accounts.pool_reserve.key(), false), // Reserve stake account of the stake pool
-            AccountMeta::new(accounts.user.key(), true), // Account providing the lamports to be deposited into the pool
-            AccountMeta::new(accounts.user_pool_token_account.key(), false), // User account to receive pool tokens
-            AccountMeta::new(accounts.fee_token_account.key(), false), // Account to receive fee tokens
-            AccountMeta::new(accounts.referral_fee_token_account.key(), false), //Account to receive a portion of fee as referral fees
-            AccountMeta::new(accounts.pool_mint.key(), false), // Pool token mint account
-            AccountMeta::new_readonly(accounts.system_program.key(), false), // System program
-            AccountMeta::new_readonly(accounts.token_program.key(), false), // Token program
-            AccountMeta::new_readonly(deposit_authority_pda, true), // (Optional) Stake pool sol deposit authority (Staker program id)
-        ],
-        data: instruction_data,
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct DepositWithAttestation<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE,
+        seeds = [b"user", user.key().as_ref()],
+        bump
+    )]
+    pub user_whitelist_account: Account<'info, UserStatus>,
+
+    #[account(
+        mut,
+        seeds = [b"access"],
+        bump,
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    /// CHECK: the stake pool account
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
+    pub stake_pool: AccountInfo<'info>,
+
+    /// CHECK: the deposit authority PDA
+    #[account(
+        seeds = [b"deposit"],
+        bump
+    )]
+    pub deposit_authority: AccountInfo<'info>,
+
+    /// CHECK: the withdraw authority PDA
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
+    pub withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: the reserve account of the stake pool
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
+    pub pool_reserve: AccountInfo<'info>,
+
+    /// CHECK: User's pool token associated token account
+    #[account(mut)]
+    pub user_pool_token_account: AccountInfo<'info>,
+
+    /// CHECK: Fee token account
+    #[account(
+        mut,
+        address = access.manager_fee_account @ ErrorCode::InvalidFeeTokenAccount
+    )]
+    pub fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: Pool token mint
+    #[account(
+        mut,
+        address = access.pool_mint @ ErrorCode::InvalidPoolMint
+    )]
+    pub pool_mint: AccountInfo<'info>,
+
+    /// CHECK: Referral fee token account (can be same as fee)
+    #[account(mut)]
+    pub referral_fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: Instructions sysvar, holding the Ed25519 instruction verifying the attestation
+    #[account(address = instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// CHECK: SPL Token program
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: Stake Pool program
+    #[account(address = STAKE_POOL_PROGRAM_ID)]
+    pub stake_pool_program: AccountInfo<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `DepositWithAttestation` instruction
+pub fn process_deposit_with_attestation(
+    ctx: Context<DepositWithAttestation>,
+    amount: u64,
+    min_pool_tokens_out: Option<u64>,
+    attestation: KycAttestation,
+) -> Result<()> {
+    // verify the attestation was issued to the user for this deployment by the registered attester and is still valid
+    let attester = ctx
+        .accounts
+        .access
+        .attester
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    require_keys_eq!(
+        attestation.program_id,
+        *ctx.program_id,
+        ErrorCode::InvalidAttestation
+    );
+    require_keys_eq!(
+        attestation.access,
+        ctx.accounts.access.key(),
+        ErrorCode::InvalidAttestation
+    );
+    require_keys_eq!(
+        attestation.user,
+        ctx.accounts.user.key(),
+        ErrorCode::InvalidAttestation
+    );
+    require_gt!(
+        attestation.expiry,
+        Clock::get()?.unix_timestamp,
+        ErrorCode::AttestationExpired
+    );
+    attestation::verify_attestation(
+        &ctx.accounts.instructions_sysvar,
+        &attester,
+        &attestation.try_to_vec()?,
+    )?;
+
+    // record the attested expiry, whitelisting users without a whitelist status with the attested limits
+    let user_status = &mut ctx.accounts.user_whitelist_account;
+    let old_status = user_status.status.clone();
+    require!(
+        old_status != WhitelistUserStatus::Blacklisted,
+        ErrorCode::UserBlacklisted
+    );
+    // an attestation cannot restore a user whose status an agent cleared
+    require!(
+        old_status != WhitelistUserStatus::None || user_status.status_change_reason.is_none(),
+        ErrorCode::UserStatusCleared
+    );
+    if old_status == WhitelistUserStatus::None {
+        user_status.deposit_limits = attestation.deposit_limits;
+    }
+    user_status.status = WhitelistUserStatus::Whitelisted;
+    user_status.whitelist_expiry = Some(attestation.expiry);
+    if old_status == WhitelistUserStatus::None {
+        emit_cpi! {
+            WhitelistingStatusChanged {
+                user: attestation.user,
+                old_status,
+                new_status: WhitelistUserStatus::Whitelisted,
+                reason: None,
+                case_reference: None,
+            }
+        };
+    }
+    emit_cpi! {
+        WhitelistExpirySet {
+            user: attestation.user,
+            expiry: Some(attestation.expiry)
+        }
     };
 
-    // Invoke the DepositSol instruction
-    let seeds: &[&[u8]] = &[b"deposit", &[deposit_authority_bump]];
+    let accounts = &ctx.accounts;
+    prepare_user_pool_token_account(
+        &accounts.user,
+        &accounts.user,
+        &accounts.user_pool_token_account,
+        &accounts.pool_mint,
+        &accounts.token_program,
+        &accounts.associated_token_program,
+        &accounts.system_program,
+    )?;
+
+    let cpi_accounts = stake_pool_cpi::DepositSol {
+        stake_pool: accounts.stake_pool.to_account_info(),
+        withdraw_authority: accounts.withdraw_authority.to_account_info(),
+        reserve_stake: accounts.pool_reserve.to_account_info(),
+        lamports_from: accounts.user.to_account_info(),
+        pool_tokens_to: accounts.user_pool_token_account.to_account_info(),
+        manager_fee_account: accounts.fee_token_account.to_account_info(),
+        referrer_pool_tokens_account: accounts.referral_fee_token_account.to_account_info(),
+        pool_mint: accounts.pool_mint.to_account_info(),
+        system_program: accounts.system_program.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+        deposit_authority: accounts.deposit_authority.to_account_info(),
+    };
+    let stake_pool_program = accounts.stake_pool_program.to_account_info();
 
-    invoke_signed(
-        &deposit_sol_ix,
-        &[
-            accounts.stake_pool.to_account_info(),
-            accounts.withdraw_authority.to_account_info(),
-            accounts.pool_reserve.to_account_info(),
-            accounts.user.to_account_info(),
-            accounts.user_pool_token_account.to_account_info(),
-            accounts.fee_token_account.to_account_info(),
-            accounts.referral_fee_token_account.to_account_info(),
-            accounts.pool_mint.to_account_info(),
-            accounts.system_program.to_account_info(),
-            accounts.token_program.to_account_info(),
-            accounts.deposit_authority.to_account_info(),
-        ],
-        &[seeds],
+    deposit_sol(
+        &mut ctx.accounts.access,
+        &mut ctx.accounts.user_whitelist_account,
+        stake_pool_program,
+        cpi_accounts,
+        ctx.bumps.deposit_authority,
+        amount,
+        min_pool_tokens_out,
     )?;
 
     emit_cpi! {
@@ -141,6 +368,369 @@ pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
     Ok(())
 }
 
+#[derive(Accounts)]
+#[event_cpi]
+#[instruction(beneficiary: Pubkey)]
+pub struct DepositFor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        constraint = payer_whitelist_account.status == WhitelistUserStatus::Whitelisted @ ErrorCode::UserNotWhitelisted,
+        seeds = [b"user", payer.key().as_ref()],
+        bump
+    )]
+    pub payer_whitelist_account: Box<Account<'info, UserStatus>>,
+
+    #[account(
+        mut,
+        constraint = beneficiary_whitelist_account.status == WhitelistUserStatus::Whitelisted @ ErrorCode::UserNotWhitelisted,
+        seeds = [b"user", beneficiary.as_ref()],
+        bump
+    )]
+    pub beneficiary_whitelist_account: Box<Account<'info, UserStatus>>,
+
+    /// CHECK: the beneficiary, owning the pool token account created for them if needed
+    #[account(address = beneficiary)]
+    pub beneficiary_info: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"access"],
+        bump,
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    /// CHECK: the stake pool account
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
+    pub stake_pool: AccountInfo<'info>,
+
+    /// CHECK: the deposit authority PDA
+    #[account(
+        seeds = [b"deposit"],
+        bump
+    )]
+    pub deposit_authority: AccountInfo<'info>,
+
+    /// CHECK: the withdraw authority PDA
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
+    pub withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: the reserve account of the stake pool
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
+    pub pool_reserve: AccountInfo<'info>,
+
+    /// CHECK: Beneficiary's pool token associated token account
+    #[account(mut)]
+    pub beneficiary_pool_token_account: AccountInfo<'info>,
+
+    /// CHECK: Fee token account
+    #[account(
+        mut,
+        address = access.manager_fee_account @ ErrorCode::InvalidFeeTokenAccount
+    )]
+    pub fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: Pool token mint
+    #[account(
+        mut,
+        address = access.pool_mint @ ErrorCode::InvalidPoolMint
+    )]
+    pub pool_mint: AccountInfo<'info>,
+
+    /// CHECK: Referral fee token account (can be same as fee)
+    #[account(mut)]
+    pub referral_fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: SPL Token program
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: Stake Pool program
+    #[account(address = STAKE_POOL_PROGRAM_ID)]
+    pub stake_pool_program: AccountInfo<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `DepositFor` instruction.
+/// The payer funds the deposit and the beneficiary receives the pool tokens, so the deposit counts towards the beneficiary's deposit limits.
+pub fn process_deposit_for(
+    ctx: Context<DepositFor>,
+    beneficiary: Pubkey,
+    amount: u64,
+    min_pool_tokens_out: Option<u64>,
+) -> Result<()> {
+    require_whitelist_not_expired(&ctx.accounts.payer_whitelist_account)?;
+    let accounts = &ctx.accounts;
+    prepare_user_pool_token_account(
+        &accounts.payer,
+        &accounts.beneficiary_info,
+        &accounts.beneficiary_pool_token_account,
+        &accounts.pool_mint,
+        &accounts.token_program,
+        &accounts.associated_token_program,
+        &accounts.system_program,
+    )?;
+
+    let cpi_accounts = stake_pool_cpi::DepositSol {
+        stake_pool: accounts.stake_pool.to_account_info(),
+        withdraw_authority: accounts.withdraw_authority.to_account_info(),
+        reserve_stake: accounts.pool_reserve.to_account_info(),
+        lamports_from: accounts.payer.to_account_info(),
+        pool_tokens_to: accounts.beneficiary_pool_token_account.to_account_info(),
+        manager_fee_account: accounts.fee_token_account.to_account_info(),
+        referrer_pool_tokens_account: accounts.referral_fee_token_account.to_account_info(),
+        pool_mint: accounts.pool_mint.to_account_info(),
+        system_program: accounts.system_program.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+        deposit_authority: accounts.deposit_authority.to_account_info(),
+    };
+    let stake_pool_program = accounts.stake_pool_program.to_account_info();
+
+    deposit_sol(
+        &mut ctx.accounts.access,
+        &mut ctx.accounts.beneficiary_whitelist_account,
+        stake_pool_program,
+        cpi_accounts,
+        ctx.bumps.deposit_authority,
+        amount,
+        min_pool_tokens_out,
+    )?;
+
+    emit_cpi! {
+        DepositedFor {
+            payer: ctx.accounts.payer.key(),
+            beneficiary,
+            amount,
+        }
+    };
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct DepositStake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        constraint = user_whitelist_account.status == WhitelistUserStatus::Whitelisted @ ErrorCode::UserNotWhitelisted,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE,
+        seeds = [b"user", user.key().as_ref()],
+        bump
+    )]
+    pub user_whitelist_account: Account<'info, UserStatus>,
+
+    #[account(
+        mut,
+        constraint = !access.is_paused(PAUSE_DEPOSITS) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump,
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    /// CHECK: the stake pool account
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
+    pub stake_pool: AccountInfo<'info>,
+
+    /// CHECK: Validator list account
+    #[account(
+        mut,
+        address = access.validator_list @ ErrorCode::InvalidValidatorList
+    )]
+    pub validator_list: AccountInfo<'info>,
+
+    /// CHECK: the deposit authority PDA
+    #[account(
+        seeds = [b"deposit"],
+        bump
+    )]
+    pub deposit_authority: AccountInfo<'info>,
+
+    /// CHECK: the withdraw authority PDA
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
+    pub withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: the user's stake account, delegated to a validator in the pool
+    #[account(
+        mut,
+        owner = stake::program::ID
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    /// CHECK: Validator stake account the stake is merged into
+    #[account(mut)]
+    pub validator_stake_account: AccountInfo<'info>,
+
+    /// CHECK: the reserve account of the stake pool
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
+    pub pool_reserve: AccountInfo<'info>,
+
+    /// CHECK: User's pool token associated token account
+    #[account(mut)]
+    pub user_pool_token_account: AccountInfo<'info>,
+
+    /// CHECK: Fee token account
+    #[account(
+        mut,
+        address = access.manager_fee_account @ ErrorCode::InvalidFeeTokenAccount
+    )]
+    pub fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: Referral fee token account (can be same as fee)
+    #[account(mut)]
+    pub referral_fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: Pool token mint
+    #[account(
+        mut,
+        address = access.pool_mint @ ErrorCode::InvalidPoolMint
+    )]
+    pub pool_mint: AccountInfo<'info>,
+
+    /// CHECK: Clock sysvar
+    #[account(address = clock::ID)]
+    pub clock_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Stake history sysvar
+    #[account(address = stake_history::ID)]
+    pub stake_history_sysvar: AccountInfo<'info>,
+
+    /// CHECK: SPL Token program
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: Stake program
+    #[account(address = stake::program::ID)]
+    pub stake_program: AccountInfo<'info>,
+
+    /// CHECK: Stake Pool program
+    #[account(address = STAKE_POOL_PROGRAM_ID)]
+    pub stake_pool_program: AccountInfo<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `DepositStake` instruction
+pub fn process_deposit_stake(
+    ctx: Context<DepositStake>,
+    min_pool_tokens_out: Option<u64>,
+) -> Result<()> {
+    let lamports = ctx.accounts.stake_account.lamports();
+    enforce_deposit_caps(&mut ctx.accounts.access, &ctx.accounts.stake_pool, lamports)?;
+    enforce_user_deposit_limits(&mut ctx.accounts.user_whitelist_account, lamports)?;
+    let accounts = &ctx.accounts;
+
+    prepare_user_pool_token_account(
+        &accounts.user,
+        &accounts.user,
+        &accounts.user_pool_token_account,
+        &accounts.pool_mint,
+        &accounts.token_program,
+        &accounts.associated_token_program,
+        &accounts.system_program,
+    )?;
+
+    // verify the stake account is delegated to a validator in the pool's validator list
+    let delegation = StakeStateV2::deserialize(&mut &accounts.stake_account.try_borrow_data()?[..])
+        .ok()
+        .and_then(|stake_state| stake_state.delegation())
+        .ok_or(ErrorCode::StakeAccountNotDelegated)?;
+    let validator = delegation.voter_pubkey;
+    require!(
+        ValidatorList::find_validator(&accounts.validator_list, &validator)?.is_some(),
+        ErrorCode::ValidatorNotInPool
+    );
+
+    // hand the stake and withdraw authorities of the stake account over to the deposit authority PDA
+    for stake_authorize in [StakeAuthorize::Staker, StakeAuthorize::Withdrawer] {
+        invoke(
+            &stake::instruction::authorize(
+                accounts.stake_account.key,
+                accounts.user.key,
+                accounts.deposit_authority.key,
+                stake_authorize,
+                None,
+            ),
+            &[
+                accounts.stake_account.to_account_info(),
+                accounts.clock_sysvar.to_account_info(),
+                accounts.user.to_account_info(),
+                accounts.stake_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    // invoke DepositStake, or DepositStakeWithSlippage when a minimum output is given, signed by the deposit authority PDA
+    let seeds: &[&[u8]] = &[b"deposit", &[ctx.bumps.deposit_authority]];
+    let signer_seeds = &[seeds];
+    let cpi_ctx = CpiContext::new_with_signer(
+        accounts.stake_pool_program.to_account_info(),
+        stake_pool_cpi::DepositStake {
+            stake_pool: accounts.stake_pool.to_account_info(),
+            validator_list: accounts.validator_list.to_account_info(),
+            deposit_authority: accounts.deposit_authority.to_account_info(),
+            withdraw_authority: accounts.withdraw_authority.to_account_info(),
+            stake_account: accounts.stake_account.to_account_info(),
+            validator_stake_account: accounts.validator_stake_account.to_account_info(),
+            reserve_stake: accounts.pool_reserve.to_account_info(),
+            pool_tokens_to: accounts.user_pool_token_account.to_account_info(),
+            manager_fee_account: accounts.fee_token_account.to_account_info(),
+            referrer_pool_tokens_account: accounts.referral_fee_token_account.to_account_info(),
+            pool_mint: accounts.pool_mint.to_account_info(),
+            clock_sysvar: accounts.clock_sysvar.to_account_info(),
+            stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+            stake_program: accounts.stake_program.to_account_info(),
+        },
+        signer_seeds,
+    );
+    match min_pool_tokens_out {
+        Some(min_pool_tokens_out) => {
+            let pool_tokens_out = StakePool::from_account_info(&accounts.stake_pool)?
+                .calc_pool_tokens_for_stake_deposit(lamports)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require_gte!(
+                pool_tokens_out,
+                min_pool_tokens_out,
+                ErrorCode::SlippageExceeded
+            );
+            stake_pool_cpi::deposit_stake_with_slippage(cpi_ctx, min_pool_tokens_out)?
+        }
+        None => stake_pool_cpi::deposit_stake(cpi_ctx)?,
+    }
+
+    emit_cpi! {
+        StakeDeposited {
+            stake_account: accounts.stake_account.key(),
+            validator,
+            lamports,
+        }
+    };
+
+    Ok(())
+}
+
 #[derive(Accounts)]
 #[event_cpi]
 pub struct DepositToSpecificValidator<'info> {
@@ -159,7 +749,7 @@ pub struct DepositToSpecificValidator<'info> {
 
     #[account(
         mut,
-        constraint = !access.is_paused @ ErrorCode::ContractPaused,
+        constraint = !access.is_paused(PAUSE_VALIDATOR_DEPOSITS) @ ErrorCode::ContractPaused,
         seeds = [b"access"],
         bump,
     )]
@@ -173,19 +763,28 @@ pub struct DepositToSpecificValidator<'info> {
     pub staker_authority: AccountInfo<'info>,
 
     /// CHECK: the stake pool account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
     pub stake_pool: AccountInfo<'info>,
 
     /// CHECK: the deposit authority PDA
-    #[account(mut)]
+    #[account(
+        seeds = [b"deposit"],
+        bump
+    )]
     pub deposit_authority: AccountInfo<'info>,
 
     /// CHECK: the withdraw authority
-    #[account(mut)]
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
     pub withdraw_authority: AccountInfo<'info>,
 
     /// CHECK: the reserve account of the stake pool
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
     pub pool_reserve: AccountInfo<'info>,
 
     /// CHECK: User's pool token associated token account
@@ -193,11 +792,17 @@ pub struct DepositToSpecificValidator<'info> {
     pub user_pool_token_account: AccountInfo<'info>,
 
     /// CHECK: Fee token account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.manager_fee_account @ ErrorCode::InvalidFeeTokenAccount
+    )]
     pub fee_token_account: AccountInfo<'info>,
 
     /// CHECK: Pool token mint
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.pool_mint @ ErrorCode::InvalidPoolMint
+    )]
     pub pool_mint: AccountInfo<'info>,
 
     /// CHECK: Referral fee token account (can be same as fee)
@@ -209,7 +814,10 @@ pub struct DepositToSpecificValidator<'info> {
     pub token_program: AccountInfo<'info>,
 
     /// CHECK: Validator list account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.validator_list @ ErrorCode::InvalidValidatorList
+    )]
     pub validator_list: AccountInfo<'info>,
 
     /// CHECK: Validator ephemera stake account
@@ -248,6 +856,8 @@ pub struct DepositToSpecificValidator<'info> {
     #[account(address = STAKE_POOL_PROGRAM_ID)]
     pub stake_pool_program: AccountInfo<'info>,
 
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -257,111 +867,90 @@ pub fn process_deposit_to_specific_validator(
     amount: u64,
     transient_stake_seed: u64,
     ephemeral_stake_seed: u64,
+    min_pool_tokens_out: Option<u64>,
 ) -> Result<()> {
-    let accounts = &ctx.accounts;
-
-    // derive the deposit authority PDA from the staker program
-    let (deposit_authority_pda, deposit_authority_bump) =
-        Pubkey::find_program_address(&[b"deposit"], ctx.program_id);
-
     let rent = Rent::get()?.minimum_balance(stake::state::StakeStateV2::size_of());
-    let total_amount = amount + rent;
-
-    // Prepare the DepositSol instruction
-    let instruction_data = {
-        let mut data = vec![14]; // Instruction index for DepositSol
-        data.extend_from_slice(&total_amount.to_le_bytes()); // Deposit amount as u64
-        data
-    };
+    let total_amount = amount.checked_add(rent).ok_or(ErrorCode::MathOverflow)?;
+    enforce_deposit_caps(
+        &mut ctx.accounts.access,
+        &ctx.accounts.stake_pool,
+        total_amount,
+    )?;
+    enforce_user_deposit_limits(&mut ctx.accounts.user_whitelist_account, total_amount)?;
+    let accounts = &ctx.accounts;
 
-    let deposit_sol_ix = Instruction {
-        program_id: STAKE_POOL_PROGRAM_ID,
-        accounts: vec![
-            AccountMeta::new(accounts.stake_pool.key(), false), // Stake pool
-            AccountMeta::new_readonly(accounts.withdraw_authority.key(), false), // Withdraw authority PDA
-            AccountMeta::new(accounts.pool_reserve.key(), false), // Reserve stake account of the stake pool
-            AccountMeta::new(accounts.user.key(), true), // Account providing the lamports to be deposited into the pool
-            AccountMeta::new(accounts.user_pool_token_account.key(), false), // User account to receive pool tokens
-            AccountMeta::new(accounts.fee_token_account.key(), false), // Account to receive fee tokens
-            AccountMeta::new(accounts.referral_fee_token_account.key(), false), //Account to receive a portion of fee as referral fees
-            AccountMeta::new(accounts.pool_mint.key(), false), // Pool token mint account
-            AccountMeta::new_readonly(accounts.system_program.key(), false), // System program
-            AccountMeta::new_readonly(accounts.token_program.key(), false), // Token program
-            AccountMeta::new_readonly(deposit_authority_pda, true), // (Optional) Stake pool sol deposit authority (Staker program id)
-        ],
-        data: instruction_data,
-    };
+    prepare_user_pool_token_account(
+        &accounts.user,
+        &accounts.user,
+        &accounts.user_pool_token_account,
+        &accounts.pool_mint,
+        &accounts.token_program,
+        &accounts.associated_token_program,
+        &accounts.system_program,
+    )?;
 
-    // Invoke the DepositSol instruction
-    let seeds: &[&[u8]] = &[b"deposit", &[deposit_authority_bump]];
+    // verify the transient and ephemeral stake accounts match the given seeds
+    require_keys_eq!(
+        accounts.transient_stake_account.key(),
+        find_transient_stake_address(
+            accounts.validator_vote_account.key,
+            accounts.stake_pool.key,
+            transient_stake_seed
+        ),
+        ErrorCode::InvalidTransientStakeAccount
+    );
+    require_keys_eq!(
+        accounts.ephemeral_stake_account.key(),
+        find_ephemeral_stake_address(accounts.stake_pool.key, ephemeral_stake_seed),
+        ErrorCode::InvalidEphemeralStakeAccount
+    );
 
-    invoke_signed(
-        &deposit_sol_ix,
-        &[
-            accounts.stake_pool.to_account_info(),
-            accounts.withdraw_authority.to_account_info(),
-            accounts.pool_reserve.to_account_info(),
-            accounts.user.to_account_info(),
-            accounts.user_pool_token_account.to_account_info(),
-            accounts.fee_token_account.to_account_info(),
-            accounts.referral_fee_token_account.to_account_info(),
-            accounts.pool_mint.to_account_info(),
-            accounts.system_program.to_account_info(),
-            accounts.token_program.to_account_info(),
-            accounts.deposit_authority.to_account_info(),
-        ],
-        &[seeds],
+    invoke_deposit_sol(
+        accounts.stake_pool_program.to_account_info(),
+        stake_pool_cpi::DepositSol {
+            stake_pool: accounts.stake_pool.to_account_info(),
+            withdraw_authority: accounts.withdraw_authority.to_account_info(),
+            reserve_stake: accounts.pool_reserve.to_account_info(),
+            lamports_from: accounts.user.to_account_info(),
+            pool_tokens_to: accounts.user_pool_token_account.to_account_info(),
+            manager_fee_account: accounts.fee_token_account.to_account_info(),
+            referrer_pool_tokens_account: accounts.referral_fee_token_account.to_account_info(),
+            pool_mint: accounts.pool_mint.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+            deposit_authority: accounts.deposit_authority.to_account_info(),
+        },
+        ctx.bumps.deposit_authority,
+        total_amount,
+        min_pool_tokens_out,
     )?;
 
-    let instruction_data = {
-        let mut data = vec![19]; // index for IncreaseAdditionalValidatorStake
-        data.extend_from_slice(&amount.to_le_bytes());
-        data.extend_from_slice(&transient_stake_seed.to_le_bytes());
-        data.extend_from_slice(&ephemeral_stake_seed.to_le_bytes());
-        data
-    };
-    let increase_validator_stake_ix = Instruction {
-        program_id: STAKE_POOL_PROGRAM_ID,
-        accounts: vec![
-            AccountMeta::new_readonly(accounts.stake_pool.key(), false),
-            AccountMeta::new_readonly(accounts.staker_authority.key(), true),
-            AccountMeta::new_readonly(accounts.withdraw_authority.key(), false),
-            AccountMeta::new(accounts.validator_list.key(), false),
-            AccountMeta::new(accounts.pool_reserve.key(), false),
-            AccountMeta::new(accounts.ephemeral_stake_account.key(), false),
-            AccountMeta::new(accounts.transient_stake_account.key(), false),
-            AccountMeta::new_readonly(accounts.validator_stake_account.key(), false),
-            AccountMeta::new_readonly(accounts.validator_vote_account.key(), false),
-            AccountMeta::new_readonly(accounts.clock_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_history_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_config_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.system_program.key(), false),
-            AccountMeta::new_readonly(accounts.stake_program.key(), false),
-        ],
-        data: instruction_data,
-    };
-
-    // invoke the IncreaseAdditionalValidatorStake instruction
+    // invoke the IncreaseAdditionalValidatorStake instruction, signed by the staker authority PDA
     let seeds: &[&[u8]] = &[b"staker", &[ctx.bumps.staker_authority]];
-    invoke_signed(
-        &increase_validator_stake_ix,
-        &[
-            accounts.stake_pool.clone(),
-            accounts.staker_authority.clone(),
-            accounts.withdraw_authority.clone(),
-            accounts.validator_list.clone(),
-            accounts.pool_reserve.clone(),
-            accounts.ephemeral_stake_account.clone(),
-            accounts.transient_stake_account.clone(),
-            accounts.validator_stake_account.clone(),
-            accounts.validator_vote_account.clone(),
-            accounts.clock_sysvar.clone(),
-            accounts.stake_history_sysvar.clone(),
-            accounts.stake_config_sysvar.clone(),
-            accounts.system_program.to_account_info(),
-            accounts.stake_program.clone(),
-        ],
-        &[seeds],
+    stake_pool_cpi::increase_additional_validator_stake(
+        CpiContext::new_with_signer(
+            accounts.stake_pool_program.to_account_info(),
+            stake_pool_cpi::IncreaseAdditionalValidatorStake {
+                stake_pool: accounts.stake_pool.to_account_info(),
+                staker: accounts.staker_authority.to_account_info(),
+                withdraw_authority: accounts.withdraw_authority.to_account_info(),
+                validator_list: accounts.validator_list.to_account_info(),
+                reserve_stake: accounts.pool_reserve.to_account_info(),
+                ephemeral_stake_account: accounts.ephemeral_stake_account.to_account_info(),
+                transient_stake_account: accounts.transient_stake_account.to_account_info(),
+                validator_stake_account: accounts.validator_stake_account.to_account_info(),
+                validator_vote_account: accounts.validator_vote_account.to_account_info(),
+                clock_sysvar: accounts.clock_sysvar.to_account_info(),
+                stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+                stake_config_sysvar: accounts.stake_config_sysvar.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                stake_program: accounts.stake_program.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+        transient_stake_seed,
+        ephemeral_stake_seed,
     )?;
 
     emit_cpi! {
@@ -372,3 +961,233 @@ pub fn process_deposit_to_specific_validator(
     };
     Ok(())
 }
+
+/// Verifies that depositing `lamports` into the stake pool mints at least `min_pool_tokens_out` pool tokens to the depositor
+fn check_deposit_slippage(
+    stake_pool: &AccountInfo,
+    lamports: u64,
+    min_pool_tokens_out: u64,
+) -> Result<()> {
+    let pool_tokens_out = StakePool::from_account_info(stake_pool)?
+        .calc_pool_tokens_for_sol_deposit(lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_gte!(
+        pool_tokens_out,
+        min_pool_tokens_out,
+        ErrorCode::SlippageExceeded
+    );
+    Ok(())
+}
+
+/// Deposits `amount` lamports into the stake pool for a beneficiary whose whitelisting has been verified.
+/// Fails if deposits are paused, enforces the deposit caps and the beneficiary's deposit limits, and records the deposit on the beneficiary's PDA.
+fn deposit_sol<'info>(
+    access: &mut Access,
+    beneficiary_status: &mut UserStatus,
+    stake_pool_program: AccountInfo<'info>,
+    cpi_accounts: stake_pool_cpi::DepositSol<'info>,
+    deposit_authority_bump: u8,
+    amount: u64,
+    min_pool_tokens_out: Option<u64>,
+) -> Result<()> {
+    require!(!access.is_paused(PAUSE_DEPOSITS), ErrorCode::ContractPaused);
+    enforce_deposit_caps(access, &cpi_accounts.stake_pool, amount)?;
+    enforce_user_deposit_limits(beneficiary_status, amount)?;
+    invoke_deposit_sol(
+        stake_pool_program,
+        cpi_accounts,
+        deposit_authority_bump,
+        amount,
+        min_pool_tokens_out,
+    )
+}
+
+/// Invokes DepositSol, or DepositSolWithSlippage when a minimum output is given, signed by the deposit authority PDA
+fn invoke_deposit_sol<'info>(
+    stake_pool_program: AccountInfo<'info>,
+    cpi_accounts: stake_pool_cpi::DepositSol<'info>,
+    deposit_authority_bump: u8,
+    amount: u64,
+    min_pool_tokens_out: Option<u64>,
+) -> Result<()> {
+    let stake_pool = cpi_accounts.stake_pool.clone();
+    let seeds: &[&[u8]] = &[b"deposit", &[deposit_authority_bump]];
+    let signer_seeds = &[seeds];
+    let cpi_ctx = CpiContext::new_with_signer(stake_pool_program, cpi_accounts, signer_seeds);
+    match min_pool_tokens_out {
+        Some(min_pool_tokens_out) => {
+            check_deposit_slippage(&stake_pool, amount, min_pool_tokens_out)?;
+            stake_pool_cpi::deposit_sol_with_slippage(cpi_ctx, amount, min_pool_tokens_out)
+        }
+        None => stake_pool_cpi::deposit_sol(cpi_ctx, amount),
+    }
+}
+
+/// Verifies that the user is whitelisted, either by their whitelist status or by a proof against the whitelist Merkle root.
+/// Blacklisted users are refused even with a valid proof. Users proving their inclusion keep their status, so they must prove it again on every deposit and lose access once the root no longer includes them, while the deposit limits of the proof are recorded on their PDA.
+fn require_whitelisted(
+    user_status: &mut UserStatus,
+    merkle_root: Option<[u8; 32]>,
+    user: &Pubkey,
+    whitelist_proof: Option<WhitelistProof>,
+) -> Result<()> {
+    match user_status.status {
+        WhitelistUserStatus::Whitelisted => Ok(()),
+        WhitelistUserStatus::Blacklisted => err!(ErrorCode::UserNotWhitelisted),
+        WhitelistUserStatus::None => {
+            let (Some(merkle_root), Some(whitelist_proof)) = (merkle_root, whitelist_proof) else {
+                return err!(ErrorCode::UserNotWhitelisted);
+            };
+            let leaf = merkle::whitelist_leaf(user, &whitelist_proof.deposit_limits);
+            require!(
+                merkle::verify_proof(&whitelist_proof.proof, &merkle_root, leaf),
+                ErrorCode::InvalidWhitelistProof
+            );
+            // the proof cannot loosen limits an agent set on the record
+            user_status.deposit_limits = user_status
+                .deposit_limits
+                .tighter(&whitelist_proof.deposit_limits);
+            Ok(())
+        }
+    }
+}
+
+/// Verifies that depositing `lamports` stays within the TVL and per-epoch deposit caps, and records the deposit volume of the epoch
+fn enforce_deposit_caps(
+    access: &mut Access,
+    stake_pool: &AccountInfo,
+    lamports: u64,
+) -> Result<()> {
+    if let Some(max_total_lamports) = access.max_total_lamports {
+        let total_lamports = StakePool::from_account_info(stake_pool)?
+            .total_lamports
+            .checked_add(lamports)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require_gte!(
+            max_total_lamports,
+            total_lamports,
+            ErrorCode::DepositCapExceeded
+        );
+    }
+
+    // reset the deposit volume at the start of each epoch
+    let epoch = Clock::get()?.epoch;
+    if access.deposit_epoch != epoch {
+        access.deposit_epoch = epoch;
+        access.epoch_deposit_lamports = 0;
+    }
+    access.epoch_deposit_lamports = access
+        .epoch_deposit_lamports
+        .checked_add(lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if let Some(max_epoch_deposit_lamports) = access.max_epoch_deposit_lamports {
+        require_gte!(
+            max_epoch_deposit_lamports,
+            access.epoch_deposit_lamports,
+            ErrorCode::DepositCapExceeded
+        );
+    }
+    Ok(())
+}
+
+/// Verifies that the user's whitelisting has not lapsed and that depositing `lamports` stays within their lifetime and per-epoch deposit limits, and records the deposit on the user's PDA
+fn enforce_user_deposit_limits(user_status: &mut UserStatus, lamports: u64) -> Result<()> {
+    require_whitelist_not_expired(user_status)?;
+
+    user_status.total_deposited_lamports = user_status
+        .total_deposited_lamports
+        .checked_add(lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if let Some(max_lifetime_lamports) = user_status.deposit_limits.max_lifetime_lamports {
+        require_gte!(
+            max_lifetime_lamports,
+            user_status.total_deposited_lamports,
+            ErrorCode::UserDepositLimitExceeded
+        );
+    }
+
+    // reset the user's deposit volume at the start of each epoch
+    let epoch = Clock::get()?.epoch;
+    if user_status.deposit_epoch != epoch {
+        user_status.deposit_epoch = epoch;
+        user_status.epoch_deposited_lamports = 0;
+    }
+    user_status.epoch_deposited_lamports = user_status
+        .epoch_deposited_lamports
+        .checked_add(lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if let Some(max_epoch_lamports) = user_status.deposit_limits.max_epoch_lamports {
+        require_gte!(
+            max_epoch_lamports,
+            user_status.epoch_deposited_lamports,
+            ErrorCode::UserDepositLimitExceeded
+        );
+    }
+    Ok(())
+}
+
+/// Verifies that the user's whitelisting has not lapsed
+fn require_whitelist_not_expired(user_status: &UserStatus) -> Result<()> {
+    if let Some(whitelist_expiry) = user_status.whitelist_expiry {
+        require_gt!(
+            whitelist_expiry,
+            Clock::get()?.unix_timestamp,
+            ErrorCode::WhitelistExpired
+        );
+    }
+    Ok(())
+}
+
+/// Creates the user's associated pool token account, funded by the payer, when `pool_token_account` does not exist yet,
+/// and verifies that it is a pool token account owned by the user
+fn prepare_user_pool_token_account<'info>(
+    payer: &AccountInfo<'info>,
+    user: &AccountInfo<'info>,
+    pool_token_account: &AccountInfo<'info>,
+    pool_mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if pool_token_account.data_is_empty() {
+        // only the user's associated token account can be created
+        require_keys_eq!(
+            pool_token_account.key(),
+            get_associated_token_address(user.key, pool_mint.key),
+            ErrorCode::InvalidPoolTokenAccount
+        );
+        associated_token::create(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: payer.to_account_info(),
+                associated_token: pool_token_account.to_account_info(),
+                authority: user.to_account_info(),
+                mint: pool_mint.to_account_info(),
+                system_program: system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))?;
+    }
+    require_pool_token_account(pool_token_account, user.key, pool_mint.key)
+}
+
+/// Verifies that `pool_token_account` is a token account of the pool mint owned by `owner`
+fn require_pool_token_account(
+    pool_token_account: &AccountInfo,
+    owner: &Pubkey,
+    pool_mint: &Pubkey,
+) -> Result<()> {
+    require_keys_eq!(
+        *pool_token_account.owner,
+        TOKEN_PROGRAM_ID,
+        ErrorCode::InvalidPoolTokenAccount
+    );
+    let token_account =
+        TokenAccount::try_deserialize(&mut &pool_token_account.try_borrow_data()?[..])
+            .map_err(|_| ErrorCode::InvalidPoolTokenAccount)?;
+    require!(
+        token_account.mint == *pool_mint && token_account.owner == *owner,
+        ErrorCode::InvalidPoolTokenAccount
+    );
+    Ok(())
+}
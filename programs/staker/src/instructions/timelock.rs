@@ -0,0 +1,165 @@
+use crate::error::ErrorCode;
+use crate::{state::*, ANCHOR_DISCRIMINATOR, MAX_TIMELOCK_DELAY, MIN_TIMELOCK_DELAY};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct QueueAction<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = ANCHOR_DISCRIMINATOR + PendingAction::INIT_SPACE,
+        seeds = [b"pending_action", access.next_action_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `QueueAction` instruction
+pub fn process_queue_action(ctx: Context<QueueAction>, action: TimelockedAction) -> Result<()> {
+    if let TimelockedAction::SetTimelockDelay { delay } = action {
+        require_timelock_delay_valid(delay)?;
+    }
+
+    let access = &mut ctx.accounts.access;
+    let id = access.next_action_id;
+    let execute_after = Clock::get()?
+        .unix_timestamp
+        .checked_add(access.timelock_delay)
+        .ok_or(ErrorCode::MathOverflow)?;
+    access.next_action_id = id.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+    let pending_action = &mut ctx.accounts.pending_action;
+    pending_action.id = id;
+    pending_action.action = action.clone();
+    pending_action.execute_after = execute_after;
+
+    emit_cpi! {ActionQueued {
+        id,
+        action,
+        execute_after,
+    }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct CancelAction<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        constraint = access.is_owner_or_guardian(signer.key) @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
+    /// CHECK: The owner, who paid for the pending action and receives its rent
+    #[account(mut, address = access.owner @ ErrorCode::NotAuthorized)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+}
+
+/// Processes the `CancelAction` instruction
+pub fn process_cancel_action(ctx: Context<CancelAction>) -> Result<()> {
+    // only the owner can cancel actions that guardians could otherwise block indefinitely
+    require!(
+        ctx.accounts.signer.key() == ctx.accounts.access.owner
+            || ctx.accounts.pending_action.action.is_guardian_cancellable(),
+        ErrorCode::NotAuthorized
+    );
+    emit_cpi! {ActionCancelled {
+        id: ctx.accounts.pending_action.id,
+        cancelled_by: ctx.accounts.signer.key(),
+    }};
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct SetTimelockDelay<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::NotAuthorized,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+}
+
+/// Processes the `SetTimelockDelay` instruction
+pub fn process_set_timelock_delay(ctx: Context<SetTimelockDelay>, delay: i64) -> Result<()> {
+    let id = require_action_executable(
+        &ctx.accounts.pending_action,
+        &TimelockedAction::SetTimelockDelay { delay },
+    )?;
+    require_timelock_delay_valid(delay)?;
+
+    let access = &mut ctx.accounts.access;
+    let old_delay = access.timelock_delay;
+    access.timelock_delay = delay;
+    emit_cpi! {TimelockDelaySet {
+        old_delay,
+        new_delay: delay,
+    }};
+    emit_cpi! {ActionExecuted { id }};
+    Ok(())
+}
+
+/// Fails unless the pending action is the given action and its timelock has elapsed.
+/// Returns the ID of the pending action.
+pub fn require_action_executable(
+    pending_action: &PendingAction,
+    action: &TimelockedAction,
+) -> Result<u64> {
+    require!(
+        pending_action.action == *action,
+        ErrorCode::PendingActionMismatch
+    );
+    require_gte!(
+        Clock::get()?.unix_timestamp,
+        pending_action.execute_after,
+        ErrorCode::TimelockNotElapsed
+    );
+    Ok(pending_action.id)
+}
+
+/// Fails if the timelock delay is below the minimum delay or exceeds the maximum delay
+fn require_timelock_delay_valid(delay: i64) -> Result<()> {
+    require!(
+        (MIN_TIMELOCK_DELAY..=MAX_TIMELOCK_DELAY).contains(&delay),
+        ErrorCode::InvalidTimelockDelay
+    );
+    Ok(())
+}
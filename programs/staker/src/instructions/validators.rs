@@ -1,9 +1,17 @@
-use crate::{constants::STAKE_POOL_PROGRAM_ID, error::ErrorCode, state::*};
+use crate::{
+    constants::{PAUSE_REBALANCING, PAUSE_VALIDATOR_CHANGES, STAKE_POOL_PROGRAM_ID},
+    error::ErrorCode,
+    instructions::{
+        timelock::require_action_executable,
+        withdrawals::{funded_unstake_lamports, require_reserve_covers_unstake_funding},
+    },
+    stake_pool_cpi,
+    state::*,
+};
 use anchor_lang::{
     prelude::*,
     solana_program::{
-        instruction::Instruction,
-        program::{invoke, invoke_signed},
+        program::invoke,
         stake, system_instruction,
         sysvar::{clock, rent, stake_history},
     },
@@ -18,13 +26,25 @@ pub struct AddValidator<'info> {
     #[account(
         mut,
         has_one = owner @ ErrorCode::NotAuthorized,
+        constraint = !access.is_paused(PAUSE_VALIDATOR_CHANGES) @ ErrorCode::ContractPaused,
         seeds = [b"access"],
         bump
     )]
     pub access: Account<'info, Access>,
 
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
     /// CHECK: The stake pool account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
     pub stake_pool: AccountInfo<'info>,
 
     /// CHECK: The Staker authority PDA
@@ -35,15 +55,21 @@ pub struct AddValidator<'info> {
     pub staker_authority: AccountInfo<'info>,
 
     /// CHECK: Reserve stake account of the pool
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
     pub reserve_stake: AccountInfo<'info>,
 
     /// CHECK:  Stake pool withdraw authority
-    #[account()]
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
     pub withdraw_authority: AccountInfo<'info>,
 
     /// CHECK: Validator stake list account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.validator_list @ ErrorCode::InvalidValidatorList
+    )]
     pub validator_list: AccountInfo<'info>,
 
     /// CHECK: The stake account to add to the pool
@@ -83,6 +109,13 @@ pub struct AddValidator<'info> {
 
 pub fn process_add_validator(ctx: Context<AddValidator>, validator_seed: u32) -> Result<()> {
     let accounts = &ctx.accounts;
+    let id = require_action_executable(
+        &accounts.pending_action,
+        &TimelockedAction::AddValidator {
+            validator_vote_account: accounts.validator_vote_account.key(),
+            validator_seed,
+        },
+    )?;
 
     // calculate the lamports needed to fund the new validator stake account
     let initial_stake_account_balance = {
@@ -105,52 +138,29 @@ pub fn process_add_validator(ctx: Context<AddValidator>, validator_seed: u32) ->
         ],
     )?;
 
-    let instruction_data = {
-        let mut data = vec![1]; // index for AddValidatorToPool
-        data.extend_from_slice(&validator_seed.to_le_bytes()); //
-        data
-    };
-
-    let add_validator_ix = Instruction {
-        program_id: STAKE_POOL_PROGRAM_ID,
-        accounts: vec![
-            AccountMeta::new(accounts.stake_pool.key(), false),
-            AccountMeta::new_readonly(accounts.staker_authority.key(), true),
-            AccountMeta::new(accounts.reserve_stake.key(), false),
-            AccountMeta::new_readonly(accounts.withdraw_authority.key(), false),
-            AccountMeta::new(accounts.validator_list.key(), false),
-            AccountMeta::new(accounts.validator_stake_account.key(), false),
-            AccountMeta::new_readonly(accounts.validator_vote_account.key(), false),
-            AccountMeta::new_readonly(accounts.rent_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.clock_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_history_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_config_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.system_program.key(), false),
-            AccountMeta::new_readonly(accounts.stake_program.key(), false),
-        ],
-        data: instruction_data,
-    };
-
-    // invoke the AddValidatorToPool instruction
+    // invoke the AddValidatorToPool instruction, signed by the staker authority PDA
     let seeds: &[&[u8]] = &[b"staker", &[ctx.bumps.staker_authority]];
-    invoke_signed(
-        &add_validator_ix,
-        &[
-            accounts.stake_pool.clone(),
-            accounts.staker_authority.clone(),
-            accounts.reserve_stake.clone(),
-            accounts.withdraw_authority.clone(),
-            accounts.validator_list.clone(),
-            accounts.validator_stake_account.clone(),
-            accounts.validator_vote_account.clone(),
-            accounts.rent_sysvar.clone(),
-            accounts.clock_sysvar.clone(),
-            accounts.stake_history_sysvar.clone(),
-            accounts.stake_config_sysvar.clone(),
-            accounts.system_program.to_account_info(),
-            accounts.stake_program.clone(),
-        ],
-        &[seeds], // Seeds for PDA
+    stake_pool_cpi::add_validator_to_pool(
+        CpiContext::new_with_signer(
+            accounts.stake_pool_program.to_account_info(),
+            stake_pool_cpi::AddValidatorToPool {
+                stake_pool: accounts.stake_pool.to_account_info(),
+                staker: accounts.staker_authority.to_account_info(),
+                reserve_stake: accounts.reserve_stake.to_account_info(),
+                withdraw_authority: accounts.withdraw_authority.to_account_info(),
+                validator_list: accounts.validator_list.to_account_info(),
+                validator_stake_account: accounts.validator_stake_account.to_account_info(),
+                validator_vote_account: accounts.validator_vote_account.to_account_info(),
+                rent_sysvar: accounts.rent_sysvar.to_account_info(),
+                clock_sysvar: accounts.clock_sysvar.to_account_info(),
+                stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+                stake_config_sysvar: accounts.stake_config_sysvar.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                stake_program: accounts.stake_program.to_account_info(),
+            },
+            &[seeds],
+        ),
+        validator_seed,
     )?;
 
     emit_cpi! {
@@ -158,6 +168,7 @@ pub fn process_add_validator(ctx: Context<AddValidator>, validator_seed: u32) ->
             validator: accounts.validator_vote_account.key(),
         }
     };
+    emit_cpi! {ActionExecuted { id }};
 
     Ok(())
 }
@@ -171,13 +182,25 @@ pub struct RemoveValidator<'info> {
     #[account(
         mut,
         has_one = owner @ ErrorCode::NotAuthorized,
+        constraint = !access.is_paused(PAUSE_VALIDATOR_CHANGES) @ ErrorCode::ContractPaused,
         seeds = [b"access"],
         bump
     )]
     pub access: Account<'info, Access>,
 
+    #[account(
+        mut,
+        seeds = [b"pending_action", pending_action.id.to_le_bytes().as_ref()],
+        bump,
+        close = owner
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
     /// CHECK: The stake pool account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
     pub stake_pool: AccountInfo<'info>,
 
     /// CHECK: The PDA of the program as staker authority
@@ -188,11 +211,14 @@ pub struct RemoveValidator<'info> {
     pub staker_authority: AccountInfo<'info>,
 
     /// CHECK:  Stake pool withdraw authority
-    #[account()]
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
     pub withdraw_authority: AccountInfo<'info>,
 
     /// CHECK: Validator stake list account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.validator_list @ ErrorCode::InvalidValidatorList
+    )]
     pub validator_list: AccountInfo<'info>,
 
     /// CHECK: Stake account to remove from the pool
@@ -221,44 +247,36 @@ pub struct RemoveValidator<'info> {
 /// Processes the `RemoveValidator` instruction
 pub fn process_remove_validator(ctx: Context<RemoveValidator>) -> Result<()> {
     let accounts = &ctx.accounts;
+    let id = require_action_executable(
+        &accounts.pending_action,
+        &TimelockedAction::RemoveValidator {
+            validator_stake_account: accounts.validator_stake_account.key(),
+        },
+    )?;
 
-    let remove_validator_ix = Instruction {
-        program_id: STAKE_POOL_PROGRAM_ID,
-        accounts: vec![
-            AccountMeta::new(accounts.stake_pool.key(), false),
-            AccountMeta::new_readonly(accounts.staker_authority.key(), true),
-            AccountMeta::new_readonly(accounts.withdraw_authority.key(), false),
-            AccountMeta::new(accounts.validator_list.key(), false),
-            AccountMeta::new(accounts.validator_stake_account.key(), false),
-            AccountMeta::new(accounts.transient_stake_account.key(), false),
-            AccountMeta::new_readonly(accounts.clock_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_program.key(), false),
-        ],
-        data: vec![2], // index for RemoveValidatorFromPool
-    };
-
-    // invoke the RemoveValidatorFromPool instruction
+    // invoke the RemoveValidatorFromPool instruction, signed by the staker authority PDA
     let seeds: &[&[u8]] = &[b"staker", &[ctx.bumps.staker_authority]];
-    invoke_signed(
-        &remove_validator_ix,
-        &[
-            accounts.stake_pool.clone(),
-            accounts.staker_authority.clone(),
-            accounts.withdraw_authority.clone(),
-            accounts.validator_list.clone(),
-            accounts.validator_stake_account.clone(),
-            accounts.transient_stake_account.clone(),
-            accounts.clock_sysvar.clone(),
-            accounts.stake_program.clone(),
-        ],
+    stake_pool_cpi::remove_validator_from_pool(CpiContext::new_with_signer(
+        accounts.stake_pool_program.to_account_info(),
+        stake_pool_cpi::RemoveValidatorFromPool {
+            stake_pool: accounts.stake_pool.to_account_info(),
+            staker: accounts.staker_authority.to_account_info(),
+            withdraw_authority: accounts.withdraw_authority.to_account_info(),
+            validator_list: accounts.validator_list.to_account_info(),
+            validator_stake_account: accounts.validator_stake_account.to_account_info(),
+            transient_stake_account: accounts.transient_stake_account.to_account_info(),
+            clock_sysvar: accounts.clock_sysvar.to_account_info(),
+            stake_program: accounts.stake_program.to_account_info(),
+        },
         &[seeds],
-    )?;
+    ))?;
 
     emit_cpi! {
         ValidatorRemoved {
             stake_account: accounts.validator_stake_account.key(),
         }
     };
+    emit_cpi! {ActionExecuted { id }};
 
     Ok(())
 }
@@ -276,8 +294,25 @@ pub struct IncreaseValidatorStake<'info> {
     )]
     pub stake_manager: Account<'info, StakeManager>,
 
+    #[account(
+        constraint = !access.is_paused(PAUSE_REBALANCING) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
+    /// CHECK: the unstake queue PDA, which may not exist
+    #[account(
+        seeds = [b"unstake_queue"],
+        bump
+    )]
+    pub unstake_queue: AccountInfo<'info>,
+
     /// CHECK: The stake pool
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
     pub stake_pool: AccountInfo<'info>,
 
     /// CHECK: Stake pool staker authority
@@ -288,15 +323,21 @@ pub struct IncreaseValidatorStake<'info> {
     pub staker_authority: AccountInfo<'info>,
 
     /// CHECK: Stake pool withdraw authority
-    #[account()]
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
     pub withdraw_authority: AccountInfo<'info>,
 
     /// CHECK: Validator list account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.validator_list @ ErrorCode::InvalidValidatorList
+    )]
     pub validator_list: AccountInfo<'info>,
 
     /// CHECK: Pool reserve stake account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
     pub reserve_stake: AccountInfo<'info>,
 
     /// CHECK: Validator ephemera stake account
@@ -347,55 +388,54 @@ pub fn process_increase_validator_stake(
 ) -> Result<()> {
     let accounts = &ctx.accounts;
 
-    let instruction_data = {
-        let mut data = vec![19]; // index for IncreaseAdditionalValidatorStake
-        data.extend_from_slice(&amount.to_le_bytes());
-        data.extend_from_slice(&transient_stake_seed.to_le_bytes());
-        data.extend_from_slice(&ephemeral_stake_seed.to_le_bytes());
-        data
-    };
-    let increase_validator_stake_ix = Instruction {
-        program_id: STAKE_POOL_PROGRAM_ID,
-        accounts: vec![
-            AccountMeta::new(accounts.stake_pool.key(), false),
-            AccountMeta::new_readonly(accounts.staker_authority.key(), true),
-            AccountMeta::new_readonly(accounts.withdraw_authority.key(), false),
-            AccountMeta::new(accounts.validator_list.key(), false),
-            AccountMeta::new(accounts.reserve_stake.key(), false),
-            AccountMeta::new(accounts.ephemeral_stake_account.key(), false),
-            AccountMeta::new(accounts.transient_stake_account.key(), false),
-            AccountMeta::new_readonly(accounts.validator_stake_account.key(), false),
-            AccountMeta::new_readonly(accounts.validator_vote_account.key(), false),
-            AccountMeta::new_readonly(accounts.clock_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_history_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_config_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.system_program.key(), false),
-            AccountMeta::new_readonly(accounts.stake_program.key(), false),
-        ],
-        data: instruction_data,
-    };
-
-    // invoke the IncreaseAdditionalValidatorStake instruction
+    // verify the transient and ephemeral stake accounts match the given seeds
+    require_keys_eq!(
+        accounts.transient_stake_account.key(),
+        find_transient_stake_address(
+            accounts.validator_vote_account.key,
+            accounts.stake_pool.key,
+            transient_stake_seed
+        ),
+        ErrorCode::InvalidTransientStakeAccount
+    );
+    require_keys_eq!(
+        accounts.ephemeral_stake_account.key(),
+        find_ephemeral_stake_address(accounts.stake_pool.key, ephemeral_stake_seed),
+        ErrorCode::InvalidEphemeralStakeAccount
+    );
+
+    // invoke the IncreaseAdditionalValidatorStake instruction, signed by the staker authority PDA
     let seeds: &[&[u8]] = &[b"staker", &[ctx.bumps.staker_authority]];
-    invoke_signed(
-        &increase_validator_stake_ix,
-        &[
-            accounts.stake_pool.clone(),
-            accounts.staker_authority.clone(),
-            accounts.withdraw_authority.clone(),
-            accounts.validator_list.clone(),
-            accounts.reserve_stake.clone(),
-            accounts.ephemeral_stake_account.clone(),
-            accounts.transient_stake_account.clone(),
-            accounts.validator_stake_account.clone(),
-            accounts.validator_vote_account.clone(),
-            accounts.clock_sysvar.clone(),
-            accounts.stake_history_sysvar.clone(),
-            accounts.stake_config_sysvar.clone(),
-            accounts.system_program.to_account_info(),
-            accounts.stake_program.clone(),
-        ],
-        &[seeds],
+    stake_pool_cpi::increase_additional_validator_stake(
+        CpiContext::new_with_signer(
+            accounts.stake_pool_program.to_account_info(),
+            stake_pool_cpi::IncreaseAdditionalValidatorStake {
+                stake_pool: accounts.stake_pool.to_account_info(),
+                staker: accounts.staker_authority.to_account_info(),
+                withdraw_authority: accounts.withdraw_authority.to_account_info(),
+                validator_list: accounts.validator_list.to_account_info(),
+                reserve_stake: accounts.reserve_stake.to_account_info(),
+                ephemeral_stake_account: accounts.ephemeral_stake_account.to_account_info(),
+                transient_stake_account: accounts.transient_stake_account.to_account_info(),
+                validator_stake_account: accounts.validator_stake_account.to_account_info(),
+                validator_vote_account: accounts.validator_vote_account.to_account_info(),
+                clock_sysvar: accounts.clock_sysvar.to_account_info(),
+                stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+                stake_config_sysvar: accounts.stake_config_sysvar.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                stake_program: accounts.stake_program.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+        transient_stake_seed,
+        ephemeral_stake_seed,
+    )?;
+
+    // the SOL funded for unstake tickets cannot be staked
+    require_reserve_covers_unstake_funding(
+        &accounts.reserve_stake,
+        funded_unstake_lamports(&accounts.unstake_queue, ctx.program_id)?,
     )?;
 
     emit_cpi! {
@@ -421,8 +461,18 @@ pub struct DecreaseValidatorStake<'info> {
     )]
     pub stake_manager: Account<'info, StakeManager>,
 
+    #[account(
+        constraint = !access.is_paused(PAUSE_REBALANCING) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
     /// CHECK: The stake pool
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
     pub stake_pool: AccountInfo<'info>,
 
     /// CHECK: Stake pool staker authority
@@ -433,15 +483,21 @@ pub struct DecreaseValidatorStake<'info> {
     pub staker_authority: AccountInfo<'info>,
 
     /// CHECK: Stake pool withdraw authority
-    #[account()]
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
     pub withdraw_authority: AccountInfo<'info>,
 
     /// CHECK: Validator list account
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.validator_list @ ErrorCode::InvalidValidatorList
+    )]
     pub validator_list: AccountInfo<'info>,
 
     /// CHECK :Stake pool reserve stake
-    #[account(mut)]
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
     pub reserve_stake: AccountInfo<'info>,
 
     /// CHECK: Validator stake account
@@ -488,52 +544,46 @@ pub fn process_decrease_validator_stake(
 ) -> Result<()> {
     let accounts = &ctx.accounts;
 
-    let instruction_data = {
-        let mut data = vec![20]; // index for DecreaseAdditionalValidatorStake
-        data.extend_from_slice(&amount.to_le_bytes());
-        data.extend_from_slice(&transient_stake_seed.to_le_bytes());
-        data.extend_from_slice(&ephemeral_stake_seed.to_le_bytes());
-        data
-    };
-
-    let decrease_validator_stake_ix = Instruction {
-        program_id: STAKE_POOL_PROGRAM_ID,
-        accounts: vec![
-            AccountMeta::new(accounts.stake_pool.key(), false),
-            AccountMeta::new_readonly(accounts.staker_authority.key(), true),
-            AccountMeta::new_readonly(accounts.withdraw_authority.key(), false),
-            AccountMeta::new(accounts.validator_list.key(), false),
-            AccountMeta::new(accounts.reserve_stake.key(), false),
-            AccountMeta::new(accounts.validator_stake_account.key(), false),
-            AccountMeta::new(accounts.ephemeral_stake_account.key(), false),
-            AccountMeta::new(accounts.transient_stake_account.key(), false),
-            AccountMeta::new_readonly(accounts.clock_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.stake_history_sysvar.key(), false),
-            AccountMeta::new_readonly(accounts.system_program.key(), false),
-            AccountMeta::new_readonly(accounts.stake_program.key(), false),
-        ],
-        data: instruction_data,
-    };
-
-    // invoke the DecreaseAdditionalValidatorStake instruction
+    // verify the transient and ephemeral stake accounts match the given seeds
+    require_keys_eq!(
+        accounts.transient_stake_account.key(),
+        find_transient_stake_address(
+            accounts.validator_vote_account.key,
+            accounts.stake_pool.key,
+            transient_stake_seed
+        ),
+        ErrorCode::InvalidTransientStakeAccount
+    );
+    require_keys_eq!(
+        accounts.ephemeral_stake_account.key(),
+        find_ephemeral_stake_address(accounts.stake_pool.key, ephemeral_stake_seed),
+        ErrorCode::InvalidEphemeralStakeAccount
+    );
+
+    // invoke the DecreaseAdditionalValidatorStake instruction, signed by the staker authority PDA
     let seeds: &[&[u8]] = &[b"staker", &[ctx.bumps.staker_authority]];
-    invoke_signed(
-        &decrease_validator_stake_ix,
-        &[
-            accounts.stake_pool.clone(),
-            accounts.staker_authority.clone(),
-            accounts.withdraw_authority.clone(),
-            accounts.validator_list.clone(),
-            accounts.reserve_stake.clone(),
-            accounts.validator_stake_account.clone(),
-            accounts.ephemeral_stake_account.clone(),
-            accounts.transient_stake_account.clone(),
-            accounts.clock_sysvar.clone(),
-            accounts.stake_history_sysvar.clone(),
-            accounts.system_program.to_account_info(),
-            accounts.stake_program.clone(),
-        ],
-        &[seeds],
+    stake_pool_cpi::decrease_additional_validator_stake(
+        CpiContext::new_with_signer(
+            accounts.stake_pool_program.to_account_info(),
+            stake_pool_cpi::DecreaseAdditionalValidatorStake {
+                stake_pool: accounts.stake_pool.to_account_info(),
+                staker: accounts.staker_authority.to_account_info(),
+                withdraw_authority: accounts.withdraw_authority.to_account_info(),
+                validator_list: accounts.validator_list.to_account_info(),
+                reserve_stake: accounts.reserve_stake.to_account_info(),
+                validator_stake_account: accounts.validator_stake_account.to_account_info(),
+                ephemeral_stake_account: accounts.ephemeral_stake_account.to_account_info(),
+                transient_stake_account: accounts.transient_stake_account.to_account_info(),
+                clock_sysvar: accounts.clock_sysvar.to_account_info(),
+                stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                stake_program: accounts.stake_program.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+        transient_stake_seed,
+        ephemeral_stake_seed,
     )?;
 
     emit_cpi! {
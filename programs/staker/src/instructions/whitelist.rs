@@ -1,5 +1,14 @@
-use crate::{error::ErrorCode, state::*, ANCHOR_DISCRIMINATOR};
-use anchor_lang::prelude::*;
+use crate::{
+    constants::{
+        AGENT_ALL_PERMISSIONS, AGENT_CAN_BLACKLIST, AGENT_CAN_CLEAR, AGENT_CAN_MANAGE_AGENTS,
+        AGENT_CAN_WHITELIST,
+    },
+    error::ErrorCode,
+    migration,
+    state::*,
+    ANCHOR_DISCRIMINATOR,
+};
+use anchor_lang::{prelude::*, system_program};
 
 #[derive(Accounts)]
 #[event_cpi]
@@ -21,16 +30,56 @@ pub struct AddAgent<'info> {
         seeds = [b"agent", signer.key().as_ref()],
         bump
     )]
-    pub agent_account: Account<'info, Agent>,
+    pub agent_account: Option<Account<'info, Agent>>,
+
+    #[account(
+        mut,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
 
     pub system_program: Program<'info, System>,
 }
 
 /// Processes the `AddAgent` instruction
-pub fn process_add_agent(ctx: Context<AddAgent>, agent: Pubkey) -> Result<()> {
+pub fn process_add_agent(ctx: Context<AddAgent>, agent: Pubkey, permissions: u8) -> Result<()> {
+    let signer = ctx.accounts.signer.key();
+    require_agent_admin(
+        &signer,
+        &ctx.accounts.access,
+        ctx.accounts.agent_account.as_ref(),
+    )?;
+    require!(
+        permissions & !AGENT_ALL_PERMISSIONS == 0,
+        ErrorCode::InvalidAgentPermissions
+    );
+    // only the owner can grant the right to manage agents
+    if permissions & AGENT_CAN_MANAGE_AGENTS != 0 {
+        require_keys_eq!(signer, ctx.accounts.access.owner, ErrorCode::NotAuthorized);
+    }
+
+    let access = &mut ctx.accounts.access;
+    access.active_agents = access
+        .active_agents
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if let Some(max_agents) = access.max_agents {
+        require_gte!(
+            max_agents,
+            access.active_agents,
+            ErrorCode::MaxAgentsReached
+        );
+    }
+
+    let new_agent = &mut ctx.accounts.new_agent_account;
+    new_agent.permissions = permissions;
+    new_agent.created_by = signer;
     emit_cpi! {
         AgentAdded {
-            new_agent: agent
+            new_agent: agent,
+            permissions,
+            added_by: signer
         }
     };
 
@@ -57,11 +106,12 @@ pub struct RemoveAgent<'info> {
         seeds = [b"agent", signer.key().as_ref()],
         bump
     )]
-    pub agent_account: Account<'info, Agent>,
+    pub agent_account: Option<Account<'info, Agent>>,
 
     pub system_program: Program<'info, System>,
 
     #[account(
+        mut,
         seeds = [b"access"],
         bump
     )]
@@ -70,15 +120,106 @@ pub struct RemoveAgent<'info> {
 
 /// Processes the `RemoveAgent` instruction
 pub fn process_remove_agent(ctx: Context<RemoveAgent>, agent: Pubkey) -> Result<()> {
+    let signer = ctx.accounts.signer.key();
+    require_agent_admin(
+        &signer,
+        &ctx.accounts.access,
+        ctx.accounts.agent_account.as_ref(),
+    )?;
+    // only the owner can remove agents that manage agents
+    if ctx
+        .accounts
+        .agent_account_to_remove
+        .has_permissions(AGENT_CAN_MANAGE_AGENTS)
+    {
+        require_keys_eq!(signer, ctx.accounts.access.owner, ErrorCode::NotAuthorized);
+    }
+
+    let access = &mut ctx.accounts.access;
+    access.active_agents = access
+        .active_agents
+        .checked_sub(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
     emit_cpi! {
         AgentRemoved {
-            removed_agent: agent
+            removed_agent: agent,
+            removed_by: signer
         }
     };
 
     Ok(())
 }
 
+#[derive(Accounts)]
+#[event_cpi]
+#[instruction(agent: Pubkey)]
+pub struct MigrateAgent<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: The agent account, deserialized in the instruction as its layout may be outdated
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"agent", agent.as_ref()],
+        bump
+    )]
+    pub agent_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"access"],
+        bump,
+        has_one = owner @ ErrorCode::NotAuthorized
+    )]
+    pub access: Account<'info, Access>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `MigrateAgent` instruction.
+/// Grows an agent account created before agents had permissions, grants it `permissions` and counts it as an active agent.
+pub fn process_migrate_agent(
+    ctx: Context<MigrateAgent>,
+    agent: Pubkey,
+    permissions: u8,
+) -> Result<()> {
+    require!(
+        permissions & !AGENT_ALL_PERMISSIONS == 0,
+        ErrorCode::InvalidAgentPermissions
+    );
+    let agent_account = &ctx.accounts.agent_account;
+    migration::require_agent_v0_data(&agent_account.try_borrow_data()?)?;
+    migration::grow_account(
+        agent_account,
+        &ctx.accounts.owner,
+        &ctx.accounts.system_program,
+        ANCHOR_DISCRIMINATOR + Agent::INIT_SPACE,
+    )?;
+    let owner = ctx.accounts.owner.key();
+    Agent {
+        permissions,
+        created_by: owner,
+    }
+    .try_serialize(&mut &mut agent_account.try_borrow_mut_data()?[..])?;
+
+    // agents created before the count was kept are not limited by the maximum number of agents
+    let access = &mut ctx.accounts.access;
+    access.active_agents = access
+        .active_agents
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit_cpi! {
+        AgentMigrated {
+            agent,
+            permissions
+        }
+    };
+    Ok(())
+}
+
 #[derive(Accounts)]
 #[event_cpi]
 #[instruction(user: Pubkey)]
@@ -97,6 +238,7 @@ pub struct AddUserToWhitelist<'info> {
     pub user_whitelist_account: Account<'info, UserStatus>,
 
     #[account(
+        constraint = agent_account.has_permissions(AGENT_CAN_WHITELIST) @ ErrorCode::MissingAgentPermission,
         seeds = [b"agent", signer.key().as_ref()],
         bump
     )]
@@ -106,15 +248,70 @@ pub struct AddUserToWhitelist<'info> {
 }
 
 /// Processes the `AddUserToWhitelist` instruction
-pub fn process_add_user_to_whitelist(ctx: Context<AddUserToWhitelist>, user: Pubkey) -> Result<()> {
+pub fn process_add_user_to_whitelist(
+    ctx: Context<AddUserToWhitelist>,
+    user: Pubkey,
+    deposit_limits: DepositLimits,
+    expiry: Option<i64>,
+) -> Result<()> {
+    require_future_expiry(expiry)?;
     let user_status = &mut ctx.accounts.user_whitelist_account;
     let old_status = user_status.status.clone();
     user_status.status = WhitelistUserStatus::Whitelisted;
+    user_status.whitelist_expiry = expiry;
+    user_status.deposit_limits = deposit_limits;
     emit_cpi! {
         WhitelistingStatusChanged {
             user,
             old_status,
-            new_status: WhitelistUserStatus::Whitelisted
+            new_status: WhitelistUserStatus::Whitelisted,
+            reason: None,
+            case_reference: None,
+        }
+    };
+    emit_cpi! {
+        WhitelistExpirySet {
+            user,
+            expiry
+        }
+    };
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+#[instruction(user: Pubkey)]
+pub struct RenewWhitelist<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = user_whitelist_account.status == WhitelistUserStatus::Whitelisted @ ErrorCode::UserNotWhitelisted,
+        seeds = [b"user", user.as_ref()],
+        bump
+    )]
+    pub user_whitelist_account: Account<'info, UserStatus>,
+
+    #[account(
+        constraint = agent_account.has_permissions(AGENT_CAN_WHITELIST) @ ErrorCode::MissingAgentPermission,
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent_account: Account<'info, Agent>,
+}
+
+/// Processes the `RenewWhitelist` instruction
+pub fn process_renew_whitelist(
+    ctx: Context<RenewWhitelist>,
+    user: Pubkey,
+    expiry: Option<i64>,
+) -> Result<()> {
+    require_future_expiry(expiry)?;
+    ctx.accounts.user_whitelist_account.whitelist_expiry = expiry;
+    emit_cpi! {
+        WhitelistExpirySet {
+            user,
+            expiry
         }
     };
     Ok(())
@@ -138,6 +335,7 @@ pub struct AddUserToBlacklist<'info> {
     pub user_whitelist_account: Account<'info, UserStatus>,
 
     #[account(
+        constraint = agent_account.has_permissions(AGENT_CAN_BLACKLIST) @ ErrorCode::MissingAgentPermission,
         seeds = [b"agent", signer.key().as_ref()],
         bump
     )]
@@ -147,15 +345,24 @@ pub struct AddUserToBlacklist<'info> {
 }
 
 /// Processes the `AddUserToBlacklist` instruction
-pub fn process_add_user_to_blacklist(ctx: Context<AddUserToBlacklist>, user: Pubkey) -> Result<()> {
+pub fn process_add_user_to_blacklist(
+    ctx: Context<AddUserToBlacklist>,
+    user: Pubkey,
+    reason: StatusChangeReason,
+    case_reference: [u8; 32],
+) -> Result<()> {
     let user_status = &mut ctx.accounts.user_whitelist_account;
     let old_status = user_status.status.clone();
     user_status.status = WhitelistUserStatus::Blacklisted;
+    user_status.status_change_reason = Some(reason);
+    user_status.case_reference = case_reference;
     emit_cpi! {
         WhitelistingStatusChanged {
             user,
             old_status,
-            new_status: WhitelistUserStatus::Blacklisted
+            new_status: WhitelistUserStatus::Blacklisted,
+            reason: Some(reason),
+            case_reference: Some(case_reference),
         }
     };
     Ok(())
@@ -165,39 +372,482 @@ pub fn process_add_user_to_blacklist(ctx: Context<AddUserToBlacklist>, user: Pub
 #[event_cpi]
 #[instruction(user: Pubkey)]
 pub struct ClearUserStatus<'info> {
-    #[account(mut)]
     pub signer: Signer<'info>,
 
     #[account(
-        init_if_needed,
+        mut,
         constraint = user_whitelist_account.status != WhitelistUserStatus::None @ ErrorCode::AlreadyCleared,
-        payer = signer,
-        space = ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE,
         seeds = [b"user", user.as_ref()],
         bump
     )]
     pub user_whitelist_account: Account<'info, UserStatus>,
 
     #[account(
+        constraint = agent_account.has_permissions(AGENT_CAN_CLEAR) @ ErrorCode::MissingAgentPermission,
         seeds = [b"agent", signer.key().as_ref()],
         bump
     )]
     pub agent_account: Account<'info, Agent>,
-
-    pub system_program: Program<'info, System>,
 }
 
 /// Processes the `ClearUserStatus` instruction
-pub fn process_clear_user_status(ctx: Context<ClearUserStatus>, user: Pubkey) -> Result<()> {
+pub fn process_clear_user_status(
+    ctx: Context<ClearUserStatus>,
+    user: Pubkey,
+    reason: StatusChangeReason,
+    case_reference: [u8; 32],
+) -> Result<()> {
     let user_status = &mut ctx.accounts.user_whitelist_account;
     let old_status = user_status.status.clone();
     user_status.status = WhitelistUserStatus::None;
+    user_status.status_change_reason = Some(reason);
+    user_status.case_reference = case_reference;
     emit_cpi! {
         WhitelistingStatusChanged {
             user,
             old_status,
-            new_status: WhitelistUserStatus::None
+            new_status: WhitelistUserStatus::None,
+            reason: Some(reason),
+            case_reference: Some(case_reference),
+        }
+    };
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+#[instruction(user: Pubkey)]
+pub struct CloseUserStatus<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = user_whitelist_account.status == WhitelistUserStatus::None @ ErrorCode::UserStatusNotCleared,
+        // closing a record with limits or deposits would reset them when the record is recreated
+        constraint = user_whitelist_account.deposit_limits == DepositLimits::default()
+            && user_whitelist_account.total_deposited_lamports == 0 @ ErrorCode::UserStatusHasDepositHistory,
+        seeds = [b"user", user.as_ref()],
+        bump,
+        close = rent_recipient
+    )]
+    pub user_whitelist_account: Account<'info, UserStatus>,
+
+    #[account(
+        constraint = agent_account.has_permissions(AGENT_CAN_CLEAR) @ ErrorCode::MissingAgentPermission,
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent_account: Account<'info, Agent>,
+
+    #[account(
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
+    /// CHECK: the account receiving the rent of the closed record
+    #[account(
+        mut,
+        constraint = rent_recipient.key() == access.rent_recipient.unwrap_or(access.owner) @ ErrorCode::InvalidRentRecipient
+    )]
+    pub rent_recipient: AccountInfo<'info>,
+}
+
+/// Processes the `CloseUserStatus` instruction
+pub fn process_close_user_status(ctx: Context<CloseUserStatus>, user: Pubkey) -> Result<()> {
+    emit_cpi! {
+        UserStatusClosed {
+            user,
+            rent_recipient: ctx.accounts.rent_recipient.key()
         }
     };
     Ok(())
 }
+
+#[derive(Accounts)]
+#[event_cpi]
+#[instruction(old_user: Pubkey)]
+pub struct MigrateUser<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub new_user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = old_user_whitelist_account.status == WhitelistUserStatus::Whitelisted @ ErrorCode::UserNotWhitelisted,
+        seeds = [b"user", old_user.as_ref()],
+        bump,
+        close = signer
+    )]
+    pub old_user_whitelist_account: Account<'info, UserStatus>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE,
+        seeds = [b"user", new_user.key().as_ref()],
+        bump
+    )]
+    pub new_user_whitelist_account: Account<'info, UserStatus>,
+
+    #[account(
+        constraint = agent_account.has_permissions(AGENT_CAN_WHITELIST) @ ErrorCode::MissingAgentPermission,
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent_account: Account<'info, Agent>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `MigrateUser` instruction.
+/// Moves the whitelist record of `old_user`, with its limits, deposit history and expiry, to the new wallet.
+pub fn process_migrate_user(ctx: Context<MigrateUser>, old_user: Pubkey) -> Result<()> {
+    let user_status = (*ctx.accounts.old_user_whitelist_account).clone();
+    ctx.accounts
+        .new_user_whitelist_account
+        .set_inner(user_status);
+    emit_cpi! {
+        UserMigrated {
+            old_user,
+            new_user: ctx.accounts.new_user.key()
+        }
+    };
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+#[instruction(user: Pubkey)]
+pub struct MigrateUserStatus<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECK: The user's status account, deserialized in the instruction as its layout may be outdated
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"user", user.as_ref()],
+        bump
+    )]
+    pub user_whitelist_account: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent_account: Option<Account<'info, Agent>>,
+
+    #[account(
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `MigrateUserStatus` instruction.
+/// Grows a user's status record from its original layout and converts it, keeping the user's status.
+pub fn process_migrate_user_status(ctx: Context<MigrateUserStatus>, user: Pubkey) -> Result<()> {
+    require_owner_or_agent(
+        &ctx.accounts.signer.key(),
+        &ctx.accounts.access,
+        ctx.accounts.agent_account.as_ref(),
+        AGENT_CAN_WHITELIST,
+    )?;
+
+    let user_whitelist_account = &ctx.accounts.user_whitelist_account;
+    let user_status =
+        migration::migrate_user_status_data(&user_whitelist_account.try_borrow_data()?)?;
+    migration::grow_account(
+        user_whitelist_account,
+        &ctx.accounts.signer,
+        &ctx.accounts.system_program,
+        ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE,
+    )?;
+    user_status.try_serialize(&mut &mut user_whitelist_account.try_borrow_mut_data()?[..])?;
+
+    emit_cpi! {
+        UserStatusMigrated {
+            user
+        }
+    };
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct SetWhitelistMerkleRoot<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        constraint = agent_account.has_permissions(AGENT_CAN_WHITELIST) @ ErrorCode::MissingAgentPermission,
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent_account: Account<'info, Agent>,
+
+    #[account(
+        mut,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Account<'info, Access>,
+}
+
+/// Processes the `SetWhitelistMerkleRoot` instruction
+pub fn process_set_whitelist_merkle_root(
+    ctx: Context<SetWhitelistMerkleRoot>,
+    merkle_root: Option<[u8; 32]>,
+) -> Result<()> {
+    let access = &mut ctx.accounts.access;
+    let old_merkle_root = access.whitelist_merkle_root;
+    access.whitelist_merkle_root = merkle_root;
+    emit_cpi! {
+        WhitelistMerkleRootSet {
+            old_merkle_root,
+            new_merkle_root: merkle_root
+        }
+    };
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct BatchSetUserStatus<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent_account: Account<'info, Agent>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `BatchSetUserStatus` instruction.
+/// The `UserStatus` PDAs of the users are passed as remaining accounts, in the same order as `updates`.
+pub fn process_batch_set_user_status<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchSetUserStatus<'info>>,
+    updates: Vec<UserStatusUpdate>,
+) -> Result<()> {
+    require_eq!(
+        ctx.remaining_accounts.len(),
+        updates.len(),
+        ErrorCode::UserStatusAccountsMismatch
+    );
+
+    for (update, user_whitelist_account) in updates.into_iter().zip(ctx.remaining_accounts) {
+        let required_permission = match update.status {
+            WhitelistUserStatus::Whitelisted => AGENT_CAN_WHITELIST,
+            WhitelistUserStatus::Blacklisted => AGENT_CAN_BLACKLIST,
+            WhitelistUserStatus::None => AGENT_CAN_CLEAR,
+        };
+        require!(
+            ctx.accounts
+                .agent_account
+                .has_permissions(required_permission),
+            ErrorCode::MissingAgentPermission
+        );
+
+        let (expected_address, bump) =
+            Pubkey::find_program_address(&[b"user", update.user.as_ref()], ctx.program_id);
+        require_keys_eq!(
+            user_whitelist_account.key(),
+            expected_address,
+            ErrorCode::InvalidUserStatusAccount
+        );
+
+        // blacklisting and clearing a user is recorded with its reason and case reference
+        let status_change_reason = match update.status {
+            WhitelistUserStatus::Whitelisted => None,
+            WhitelistUserStatus::Blacklisted | WhitelistUserStatus::None => {
+                let (Some(reason), Some(case_reference)) = (update.reason, update.case_reference)
+                else {
+                    return err!(ErrorCode::MissingStatusChangeReason);
+                };
+                Some((reason, case_reference))
+            }
+        };
+
+        let existing_status = if user_whitelist_account.owner == ctx.program_id {
+            Some(UserStatus::try_deserialize(
+                &mut &user_whitelist_account.try_borrow_data()?[..],
+            )?)
+        } else {
+            None
+        };
+
+        // skip entries that would not change the user's status, before creating any record
+        let old_status = existing_status
+            .as_ref()
+            .map_or(WhitelistUserStatus::None, |user_status| {
+                user_status.status.clone()
+            });
+        if old_status == update.status {
+            msg!("Skipping {}: status unchanged", update.user);
+            continue;
+        }
+
+        let mut user_status = match existing_status {
+            Some(user_status) => user_status,
+            None => {
+                create_user_status_account(
+                    &ctx.accounts.signer,
+                    user_whitelist_account,
+                    &ctx.accounts.system_program,
+                    &[b"user", update.user.as_ref(), &[bump]],
+                    ctx.program_id,
+                )?;
+                UserStatus {
+                    status: WhitelistUserStatus::None,
+                    whitelist_expiry: None,
+                    deposit_limits: DepositLimits::default(),
+                    total_deposited_lamports: 0,
+                    deposit_epoch: 0,
+                    epoch_deposited_lamports: 0,
+                    status_change_reason: None,
+                    case_reference: [0; 32],
+                }
+            }
+        };
+
+        let whitelisted = update.status == WhitelistUserStatus::Whitelisted;
+        if whitelisted {
+            require_future_expiry(update.expiry)?;
+            user_status.whitelist_expiry = update.expiry;
+            user_status.deposit_limits = update.deposit_limits;
+        }
+        if let Some((reason, case_reference)) = status_change_reason {
+            user_status.status_change_reason = Some(reason);
+            user_status.case_reference = case_reference;
+        }
+        user_status.status = update.status.clone();
+        user_status.try_serialize(&mut &mut user_whitelist_account.try_borrow_mut_data()?[..])?;
+
+        emit_cpi! {
+            WhitelistingStatusChanged {
+                user: update.user,
+                old_status,
+                new_status: update.status,
+                reason: status_change_reason.map(|(reason, _)| reason),
+                case_reference: status_change_reason.map(|(_, case_reference)| case_reference),
+            }
+        };
+        if whitelisted {
+            emit_cpi! {
+                WhitelistExpirySet {
+                    user: update.user,
+                    expiry: update.expiry
+                }
+            };
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a `UserStatus` PDA owned by the program, paid for by `payer`
+fn create_user_status_account<'info>(
+    payer: &Signer<'info>,
+    user_whitelist_account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<()> {
+    let space = ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE;
+    let rent = Rent::get()?.minimum_balance(space);
+    let current_lamports = user_whitelist_account.lamports();
+
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: user_whitelist_account.clone(),
+                },
+                &[seeds],
+            ),
+            rent,
+            space as u64,
+            program_id,
+        );
+    }
+
+    // the account was pre-funded, so top it up and assign it instead
+    if current_lamports < rent {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: user_whitelist_account.clone(),
+                },
+            ),
+            rent - current_lamports,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate {
+                account_to_allocate: user_whitelist_account.clone(),
+            },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign {
+                account_to_assign: user_whitelist_account.clone(),
+            },
+            &[seeds],
+        ),
+        program_id,
+    )
+}
+
+/// Fails unless the signer is the owner or an agent admin, an agent holding the permission to manage agents
+fn require_agent_admin(
+    signer: &Pubkey,
+    access: &Access,
+    agent_account: Option<&Account<Agent>>,
+) -> Result<()> {
+    require_owner_or_agent(signer, access, agent_account, AGENT_CAN_MANAGE_AGENTS)
+}
+
+/// Fails unless the signer is the owner or an agent holding the given permission bits
+fn require_owner_or_agent(
+    signer: &Pubkey,
+    access: &Access,
+    agent_account: Option<&Account<Agent>>,
+    permissions: u8,
+) -> Result<()> {
+    if *signer == access.owner {
+        return Ok(());
+    }
+    match agent_account {
+        Some(agent_account) if agent_account.has_permissions(permissions) => Ok(()),
+        Some(_) => err!(ErrorCode::MissingAgentPermission),
+        None => err!(ErrorCode::NotAuthorized),
+    }
+}
+
+/// Fails if the whitelist expiry is set and not in the future
+fn require_future_expiry(expiry: Option<i64>) -> Result<()> {
+    if let Some(expiry) = expiry {
+        require_gt!(
+            expiry,
+            Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidWhitelistExpiry
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,773 @@
+use crate::{
+    constants::{
+        PAUSE_REBALANCING, PAUSE_WITHDRAWALS, STAKE_POOL_PROGRAM_ID, UNSTAKE_DELAY_EPOCHS,
+    },
+    error::ErrorCode,
+    migration, stake_pool_cpi,
+    state::*,
+    ANCHOR_DISCRIMINATOR,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        stake::{self, state::StakeStateV2},
+        sysvar::{clock, stake_history},
+    },
+};
+
+use anchor_spl::token::{self, spl_token::ID as TOKEN_PROGRAM_ID, Token, TokenAccount};
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct WithdrawSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: the user's whitelist status PDA, which may not exist
+    #[account(
+        seeds = [b"user", user.key().as_ref()],
+        bump
+    )]
+    pub user_whitelist_account: AccountInfo<'info>,
+
+    #[account(
+        constraint = !access.is_paused(PAUSE_WITHDRAWALS) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump,
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    /// CHECK: the unstake queue PDA, which may not exist
+    #[account(
+        seeds = [b"unstake_queue"],
+        bump
+    )]
+    pub unstake_queue: AccountInfo<'info>,
+
+    /// CHECK: the stake pool account
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
+    pub stake_pool: AccountInfo<'info>,
+
+    /// CHECK: the withdraw authority PDA of the stake pool
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
+    pub withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: the SOL withdraw authority PDA
+    #[account(
+        seeds = [b"withdraw"],
+        bump
+    )]
+    pub sol_withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: User's pool token account the pool tokens are burned from
+    #[account(mut)]
+    pub user_pool_token_account: AccountInfo<'info>,
+
+    /// CHECK: the reserve account of the stake pool
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
+    pub pool_reserve: AccountInfo<'info>,
+
+    /// CHECK: Fee token account
+    #[account(
+        mut,
+        address = access.manager_fee_account @ ErrorCode::InvalidFeeTokenAccount
+    )]
+    pub fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: Pool token mint
+    #[account(
+        mut,
+        address = access.pool_mint @ ErrorCode::InvalidPoolMint
+    )]
+    pub pool_mint: AccountInfo<'info>,
+
+    /// CHECK: Clock sysvar
+    #[account(address = clock::ID)]
+    pub clock_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Stake history sysvar
+    #[account(address = stake_history::ID)]
+    pub stake_history_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Stake program
+    #[account(address = stake::program::ID)]
+    pub stake_program: AccountInfo<'info>,
+
+    /// CHECK: SPL Token program
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: Stake Pool program
+    #[account(address = STAKE_POOL_PROGRAM_ID)]
+    pub stake_pool_program: AccountInfo<'info>,
+}
+
+/// Processes the `WithdrawSol` instruction
+pub fn process_withdraw_sol(ctx: Context<WithdrawSol>, pool_tokens: u64) -> Result<()> {
+    let accounts = &ctx.accounts;
+
+    require_not_blacklisted(&accounts.user_whitelist_account, ctx.program_id)?;
+
+    // invoke the WithdrawSol instruction, signed by the SOL withdraw authority PDA
+    let lamports_pre = accounts.user.lamports();
+    let seeds: &[&[u8]] = &[b"withdraw", &[ctx.bumps.sol_withdraw_authority]];
+    stake_pool_cpi::withdraw_sol(
+        CpiContext::new_with_signer(
+            accounts.stake_pool_program.to_account_info(),
+            stake_pool_cpi::WithdrawSol {
+                stake_pool: accounts.stake_pool.to_account_info(),
+                withdraw_authority: accounts.withdraw_authority.to_account_info(),
+                user_transfer_authority: accounts.user.to_account_info(),
+                pool_tokens_from: accounts.user_pool_token_account.to_account_info(),
+                reserve_stake: accounts.pool_reserve.to_account_info(),
+                lamports_to: accounts.user.to_account_info(),
+                manager_fee_account: accounts.fee_token_account.to_account_info(),
+                pool_mint: accounts.pool_mint.to_account_info(),
+                clock_sysvar: accounts.clock_sysvar.to_account_info(),
+                stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+                stake_program: accounts.stake_program.to_account_info(),
+                token_program: accounts.token_program.to_account_info(),
+                sol_withdraw_authority: accounts.sol_withdraw_authority.to_account_info(),
+            },
+            &[seeds],
+        ),
+        pool_tokens,
+    )?;
+    let lamports = accounts
+        .user
+        .lamports()
+        .checked_sub(lamports_pre)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    require_reserve_covers_unstake_funding(
+        &accounts.pool_reserve,
+        funded_unstake_lamports(&accounts.unstake_queue, ctx.program_id)?,
+    )?;
+
+    emit_cpi! {
+        Withdrawn {
+            user: accounts.user.key(),
+            pool_tokens,
+            lamports,
+        }
+    };
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct RequestUnstake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: the user's whitelist status PDA, which may not exist
+    #[account(
+        seeds = [b"user", user.key().as_ref()],
+        bump
+    )]
+    pub user_whitelist_account: AccountInfo<'info>,
+
+    #[account(
+        constraint = !access.is_paused(PAUSE_WITHDRAWALS) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump,
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    /// CHECK: the stake pool account
+    #[account(address = access.stake_pool @ ErrorCode::InvalidStakePool)]
+    pub stake_pool: AccountInfo<'info>,
+
+    /// CHECK: Pool token mint
+    #[account(address = access.pool_mint @ ErrorCode::InvalidPoolMint)]
+    pub pool_mint: AccountInfo<'info>,
+
+    /// CHECK: User's pool token account the pool tokens are escrowed from
+    #[account(mut)]
+    pub user_pool_token_account: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        token::mint = pool_mint,
+        token::authority = unstake_escrow,
+        seeds = [b"unstake_escrow"],
+        bump
+    )]
+    pub unstake_escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR + UnstakeQueue::INIT_SPACE,
+        seeds = [b"unstake_queue"],
+        bump
+    )]
+    pub unstake_queue: Account<'info, UnstakeQueue>,
+
+    #[account(
+        init,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR + UnstakeTicket::INIT_SPACE,
+        seeds = [b"unstake_ticket", user.key().as_ref()],
+        bump
+    )]
+    pub unstake_ticket: Account<'info, UnstakeTicket>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `RequestUnstake` instruction
+pub fn process_request_unstake(ctx: Context<RequestUnstake>, pool_tokens: u64) -> Result<()> {
+    require_not_blacklisted(&ctx.accounts.user_whitelist_account, ctx.program_id)?;
+
+    // record the SOL owed at the current exchange rate of the pool
+    let lamports = StakePool::from_account_info(&ctx.accounts.stake_pool)?
+        .calc_lamports_for_sol_withdrawal(pool_tokens)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let claimable_epoch = Clock::get()?.epoch + UNSTAKE_DELAY_EPOCHS;
+
+    // escrow the pool tokens until the ticket is claimed
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.user_pool_token_account.to_account_info(),
+                to: ctx.accounts.unstake_escrow.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        pool_tokens,
+    )?;
+
+    let unstake_queue = &mut ctx.accounts.unstake_queue;
+    unstake_queue.pending_lamports = unstake_queue
+        .pending_lamports
+        .checked_add(lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let unstake_ticket = &mut ctx.accounts.unstake_ticket;
+    unstake_ticket.user = ctx.accounts.user.key();
+    unstake_ticket.pool_tokens = pool_tokens;
+    unstake_ticket.lamports = lamports;
+    unstake_ticket.claimable_epoch = claimable_epoch;
+
+    emit_cpi! {
+        UnstakeRequested {
+            user: ctx.accounts.user.key(),
+            pool_tokens,
+            lamports,
+            claimable_epoch,
+        }
+    };
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct FundUnstakeTickets<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"stake_manager", signer.key().as_ref()],
+        bump
+    )]
+    pub stake_manager: Account<'info, StakeManager>,
+
+    #[account(
+        constraint = !access.is_paused(PAUSE_REBALANCING) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    #[account(
+        mut,
+        seeds = [b"unstake_queue"],
+        bump
+    )]
+    pub unstake_queue: Account<'info, UnstakeQueue>,
+
+    /// CHECK: The stake pool
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
+    pub stake_pool: AccountInfo<'info>,
+
+    /// CHECK: Stake pool staker authority
+    #[account(
+        seeds = [b"staker"],
+        bump
+    )]
+    pub staker_authority: AccountInfo<'info>,
+
+    /// CHECK: Stake pool withdraw authority
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
+    pub withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: Validator list account
+    #[account(
+        mut,
+        address = access.validator_list @ ErrorCode::InvalidValidatorList
+    )]
+    pub validator_list: AccountInfo<'info>,
+
+    /// CHECK: Stake pool reserve stake
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
+    pub reserve_stake: AccountInfo<'info>,
+
+    /// CHECK: Validator stake account
+    #[account(mut)]
+    pub validator_stake_account: AccountInfo<'info>,
+
+    /// CHECK: Validator ephemeral stake account
+    #[account(mut)]
+    pub ephemeral_stake_account: AccountInfo<'info>,
+
+    /// CHECK: Validator transient stake account
+    #[account(mut)]
+    pub transient_stake_account: AccountInfo<'info>,
+
+    /// CHECK: The vote account of the validator
+    pub validator_vote_account: AccountInfo<'info>,
+
+    /// CHECK: Clock sysvar
+    #[account(address = clock::ID)]
+    pub clock_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Stake history sysvar
+    #[account(address = stake_history::ID)]
+    pub stake_history_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Stake program
+    #[account(address = stake::program::ID)]
+    pub stake_program: AccountInfo<'info>,
+
+    /// CHECK: Stake Pool program
+    #[account(address = STAKE_POOL_PROGRAM_ID)]
+    pub stake_pool_program: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Processes the `FundUnstakeTickets` instruction
+pub fn process_fund_unstake_tickets(
+    ctx: Context<FundUnstakeTickets>,
+    amount: u64,
+    transient_stake_seed: u64,
+    ephemeral_stake_seed: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+
+    // only decrease stake for SOL owed to tickets that are not funded yet
+    let funded_lamports = accounts
+        .unstake_queue
+        .funded_lamports
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_gte!(
+        accounts.unstake_queue.pending_lamports,
+        funded_lamports,
+        ErrorCode::UnstakeFundingExceeded
+    );
+
+    // verify the transient and ephemeral stake accounts match the given seeds
+    require_keys_eq!(
+        accounts.transient_stake_account.key(),
+        find_transient_stake_address(
+            accounts.validator_vote_account.key,
+            accounts.stake_pool.key,
+            transient_stake_seed
+        ),
+        ErrorCode::InvalidTransientStakeAccount
+    );
+    require_keys_eq!(
+        accounts.ephemeral_stake_account.key(),
+        find_ephemeral_stake_address(accounts.stake_pool.key, ephemeral_stake_seed),
+        ErrorCode::InvalidEphemeralStakeAccount
+    );
+
+    // invoke the DecreaseAdditionalValidatorStake instruction, signed by the staker authority PDA
+    let seeds: &[&[u8]] = &[b"staker", &[ctx.bumps.staker_authority]];
+    stake_pool_cpi::decrease_additional_validator_stake(
+        CpiContext::new_with_signer(
+            accounts.stake_pool_program.to_account_info(),
+            stake_pool_cpi::DecreaseAdditionalValidatorStake {
+                stake_pool: accounts.stake_pool.to_account_info(),
+                staker: accounts.staker_authority.to_account_info(),
+                withdraw_authority: accounts.withdraw_authority.to_account_info(),
+                validator_list: accounts.validator_list.to_account_info(),
+                reserve_stake: accounts.reserve_stake.to_account_info(),
+                validator_stake_account: accounts.validator_stake_account.to_account_info(),
+                ephemeral_stake_account: accounts.ephemeral_stake_account.to_account_info(),
+                transient_stake_account: accounts.transient_stake_account.to_account_info(),
+                clock_sysvar: accounts.clock_sysvar.to_account_info(),
+                stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                stake_program: accounts.stake_program.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+        transient_stake_seed,
+        ephemeral_stake_seed,
+    )?;
+
+    ctx.accounts.unstake_queue.funded_lamports = funded_lamports;
+
+    emit_cpi! {
+        UnstakeTicketsFunded {
+            validator: ctx.accounts.validator_vote_account.key(),
+            amount,
+        }
+    };
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct ClaimUnstake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = !access.is_paused(PAUSE_WITHDRAWALS) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump,
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    #[account(
+        mut,
+        seeds = [b"unstake_queue"],
+        bump
+    )]
+    pub unstake_queue: Account<'info, UnstakeQueue>,
+
+    #[account(
+        mut,
+        has_one = user @ ErrorCode::NotAuthorized,
+        close = user,
+        seeds = [b"unstake_ticket", user.key().as_ref()],
+        bump
+    )]
+    pub unstake_ticket: Account<'info, UnstakeTicket>,
+
+    #[account(
+        mut,
+        seeds = [b"unstake_escrow"],
+        bump
+    )]
+    pub unstake_escrow: Account<'info, TokenAccount>,
+
+    /// CHECK: User's pool token account the pool tokens not needed for the SOL owed are returned to
+    #[account(mut)]
+    pub user_pool_token_account: AccountInfo<'info>,
+
+    /// CHECK: the stake pool account
+    #[account(
+        mut,
+        address = access.stake_pool @ ErrorCode::InvalidStakePool
+    )]
+    pub stake_pool: AccountInfo<'info>,
+
+    /// CHECK: the withdraw authority PDA of the stake pool
+    #[account(address = access.withdraw_authority @ ErrorCode::InvalidWithdrawAuthority)]
+    pub withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: the SOL withdraw authority PDA
+    #[account(
+        seeds = [b"withdraw"],
+        bump
+    )]
+    pub sol_withdraw_authority: AccountInfo<'info>,
+
+    /// CHECK: the reserve account of the stake pool
+    #[account(
+        mut,
+        address = access.reserve_stake @ ErrorCode::InvalidReserveStake
+    )]
+    pub pool_reserve: AccountInfo<'info>,
+
+    /// CHECK: Fee token account
+    #[account(
+        mut,
+        address = access.manager_fee_account @ ErrorCode::InvalidFeeTokenAccount
+    )]
+    pub fee_token_account: AccountInfo<'info>,
+
+    /// CHECK: Pool token mint
+    #[account(
+        mut,
+        address = access.pool_mint @ ErrorCode::InvalidPoolMint
+    )]
+    pub pool_mint: AccountInfo<'info>,
+
+    /// CHECK: Clock sysvar
+    #[account(address = clock::ID)]
+    pub clock_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Stake history sysvar
+    #[account(address = stake_history::ID)]
+    pub stake_history_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Stake program
+    #[account(address = stake::program::ID)]
+    pub stake_program: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Stake Pool program
+    #[account(address = STAKE_POOL_PROGRAM_ID)]
+    pub stake_pool_program: AccountInfo<'info>,
+}
+
+/// Processes the `ClaimUnstake` instruction.
+/// Pays out the SOL recorded on the ticket by burning only the escrowed pool tokens it takes at the current exchange rate, and returns the remaining pool tokens to the user.
+pub fn process_claim_unstake(ctx: Context<ClaimUnstake>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let (user, escrowed_pool_tokens, owed_lamports) = (
+        accounts.unstake_ticket.user,
+        accounts.unstake_ticket.pool_tokens,
+        accounts.unstake_ticket.lamports,
+    );
+    require_gte!(
+        Clock::get()?.epoch,
+        accounts.unstake_ticket.claimable_epoch,
+        ErrorCode::UnstakeTicketNotClaimable
+    );
+    require_gte!(
+        accounts.unstake_queue.funded_lamports,
+        owed_lamports,
+        ErrorCode::UnstakeTicketNotFunded
+    );
+
+    // burn no more pool tokens than needed for the SOL owed, so the payout stays within the funded SOL,
+    // or all of them if the exchange rate fell, in which case the slippage check fails
+    let pool_tokens = StakePool::from_account_info(&accounts.stake_pool)?
+        .calc_pool_tokens_for_sol_withdrawal(owed_lamports, escrowed_pool_tokens)
+        .unwrap_or(escrowed_pool_tokens);
+    let escrow_seeds: &[&[u8]] = &[b"unstake_escrow", &[ctx.bumps.unstake_escrow]];
+
+    // burn the pool tokens for at least the SOL owed, signed by the escrow and SOL withdraw authority PDAs
+    let lamports_pre = accounts.user.lamports();
+    let withdraw_seeds: &[&[u8]] = &[b"withdraw", &[ctx.bumps.sol_withdraw_authority]];
+    stake_pool_cpi::withdraw_sol_with_slippage(
+        CpiContext::new_with_signer(
+            accounts.stake_pool_program.to_account_info(),
+            stake_pool_cpi::WithdrawSol {
+                stake_pool: accounts.stake_pool.to_account_info(),
+                withdraw_authority: accounts.withdraw_authority.to_account_info(),
+                user_transfer_authority: accounts.unstake_escrow.to_account_info(),
+                pool_tokens_from: accounts.unstake_escrow.to_account_info(),
+                reserve_stake: accounts.pool_reserve.to_account_info(),
+                lamports_to: accounts.user.to_account_info(),
+                manager_fee_account: accounts.fee_token_account.to_account_info(),
+                pool_mint: accounts.pool_mint.to_account_info(),
+                clock_sysvar: accounts.clock_sysvar.to_account_info(),
+                stake_history_sysvar: accounts.stake_history_sysvar.to_account_info(),
+                stake_program: accounts.stake_program.to_account_info(),
+                token_program: accounts.token_program.to_account_info(),
+                sol_withdraw_authority: accounts.sol_withdraw_authority.to_account_info(),
+            },
+            &[escrow_seeds, withdraw_seeds],
+        ),
+        pool_tokens,
+        owed_lamports,
+    )?;
+    let lamports = accounts
+        .user
+        .lamports()
+        .checked_sub(lamports_pre)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // return the pool tokens that were not needed, signed by the escrow PDA
+    let remaining_pool_tokens = escrowed_pool_tokens
+        .checked_sub(pool_tokens)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if remaining_pool_tokens > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: accounts.unstake_escrow.to_account_info(),
+                    to: accounts.user_pool_token_account.to_account_info(),
+                    authority: accounts.unstake_escrow.to_account_info(),
+                },
+                &[escrow_seeds],
+            ),
+            remaining_pool_tokens,
+        )?;
+    }
+
+    let unstake_queue = &mut ctx.accounts.unstake_queue;
+    unstake_queue.pending_lamports = unstake_queue
+        .pending_lamports
+        .checked_sub(owed_lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+    unstake_queue.funded_lamports = unstake_queue
+        .funded_lamports
+        .checked_sub(owed_lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_reserve_covers_unstake_funding(
+        &ctx.accounts.pool_reserve,
+        ctx.accounts.unstake_queue.funded_lamports,
+    )?;
+
+    emit_cpi! {
+        UnstakeClaimed {
+            user,
+            pool_tokens,
+            lamports,
+        }
+    };
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[event_cpi]
+pub struct CancelUnstake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = !access.is_paused(PAUSE_WITHDRAWALS) @ ErrorCode::ContractPaused,
+        seeds = [b"access"],
+        bump,
+    )]
+    pub access: Box<Account<'info, Access>>,
+
+    #[account(
+        mut,
+        seeds = [b"unstake_queue"],
+        bump
+    )]
+    pub unstake_queue: Account<'info, UnstakeQueue>,
+
+    #[account(
+        mut,
+        has_one = user @ ErrorCode::NotAuthorized,
+        close = user,
+        seeds = [b"unstake_ticket", user.key().as_ref()],
+        bump
+    )]
+    pub unstake_ticket: Account<'info, UnstakeTicket>,
+
+    #[account(
+        mut,
+        seeds = [b"unstake_escrow"],
+        bump
+    )]
+    pub unstake_escrow: Account<'info, TokenAccount>,
+
+    /// CHECK: User's pool token account the escrowed pool tokens are returned to
+    #[account(mut)]
+    pub user_pool_token_account: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Processes the `CancelUnstake` instruction.
+/// Returns the escrowed pool tokens to the user and closes their ticket, so that a ticket that cannot be claimed, e.g. after the exchange rate fell, does not lock the tokens.
+pub fn process_cancel_unstake(ctx: Context<CancelUnstake>) -> Result<()> {
+    let (user, pool_tokens, lamports) = (
+        ctx.accounts.unstake_ticket.user,
+        ctx.accounts.unstake_ticket.pool_tokens,
+        ctx.accounts.unstake_ticket.lamports,
+    );
+
+    // return the escrowed pool tokens, signed by the escrow PDA
+    let escrow_seeds: &[&[u8]] = &[b"unstake_escrow", &[ctx.bumps.unstake_escrow]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.unstake_escrow.to_account_info(),
+                to: ctx.accounts.user_pool_token_account.to_account_info(),
+                authority: ctx.accounts.unstake_escrow.to_account_info(),
+            },
+            &[escrow_seeds],
+        ),
+        pool_tokens,
+    )?;
+
+    // SOL funded beyond what the remaining tickets are owed is no longer ring-fenced
+    let unstake_queue = &mut ctx.accounts.unstake_queue;
+    unstake_queue.pending_lamports = unstake_queue
+        .pending_lamports
+        .checked_sub(lamports)
+        .ok_or(ErrorCode::MathOverflow)?;
+    unstake_queue.funded_lamports = unstake_queue
+        .funded_lamports
+        .min(unstake_queue.pending_lamports);
+
+    emit_cpi! {
+        UnstakeCancelled {
+            user,
+            pool_tokens,
+            lamports,
+        }
+    };
+
+    Ok(())
+}
+
+/// Fails unless the reserve still holds its rent-exempt minimum and the SOL funded for unstake tickets,
+/// which stays in the reserve until the tickets are claimed
+pub fn require_reserve_covers_unstake_funding(
+    pool_reserve: &AccountInfo,
+    funded_lamports: u64,
+) -> Result<()> {
+    let reserve_rent = Rent::get()?.minimum_balance(StakeStateV2::size_of());
+    require_gte!(
+        pool_reserve.lamports(),
+        reserve_rent
+            .checked_add(funded_lamports)
+            .ok_or(ErrorCode::MathOverflow)?,
+        ErrorCode::ReserveFundedForUnstakeTickets
+    );
+    Ok(())
+}
+
+/// Returns the SOL funded for unstake tickets, or zero if the unstake queue account does not exist
+pub fn funded_unstake_lamports(unstake_queue: &AccountInfo, program_id: &Pubkey) -> Result<u64> {
+    if unstake_queue.owner != program_id {
+        return Ok(0);
+    }
+    let unstake_queue = UnstakeQueue::try_deserialize(&mut &unstake_queue.try_borrow_data()?[..])?;
+    Ok(unstake_queue.funded_lamports)
+}
+
+/// Fails if the user's whitelist status account exists and marks them as blacklisted
+fn require_not_blacklisted(
+    user_whitelist_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<()> {
+    // users without a whitelist status account have never been blacklisted, and records that were
+    // not migrated yet only hold the status
+    if user_whitelist_account.owner == program_id {
+        let status = migration::read_user_status(&user_whitelist_account.try_borrow_data()?)?;
+        require!(
+            status != WhitelistUserStatus::Blacklisted,
+            ErrorCode::UserBlacklisted
+        );
+    }
+    Ok(())
+}
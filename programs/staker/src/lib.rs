@@ -1,6 +1,10 @@
+pub mod attestation;
 pub mod constants;
 pub mod error;
 pub mod instructions;
+pub mod merkle;
+pub mod migration;
+pub mod stake_pool_cpi;
 pub mod state;
 
 use anchor_lang::prelude::*;
@@ -30,19 +34,28 @@ pub mod staker {
         initialize::process_initialize_staker(ctx)
     }
 
-    pub fn pause(ctx: Context<Pause>) -> Result<()> {
-        setters::process_pause(ctx)
+    pub fn migrate_access(ctx: Context<MigrateAccess>) -> Result<()> {
+        initialize::process_migrate_access(ctx)
     }
 
-    pub fn unpause(ctx: Context<Unpause>) -> Result<()> {
-        setters::process_unpause(ctx)
+    pub fn pause(ctx: Context<Pause>, flags: u8) -> Result<()> {
+        setters::process_pause(ctx, flags)
+    }
+
+    pub fn unpause(ctx: Context<Unpause>, flags: u8) -> Result<()> {
+        setters::process_unpause(ctx, flags)
     }
 
     pub fn set_pending_owner(
         ctx: Context<SetStakerPendingOwner>,
         pending_owner: Pubkey,
+        deadline: Option<u64>,
     ) -> Result<()> {
-        setters::process_set_pending_owner(ctx, pending_owner)
+        setters::process_set_pending_owner(ctx, pending_owner, deadline)
+    }
+
+    pub fn cancel_pending_owner(ctx: Context<CancelPendingOwner>) -> Result<()> {
+        setters::process_cancel_pending_owner(ctx)
     }
 
     pub fn claim_ownership(ctx: Context<StakerClaimOwnership>) -> Result<()> {
@@ -53,35 +66,204 @@ pub mod staker {
         setters::process_set_stake_manager(ctx)
     }
 
-    pub fn add_agent(ctx: Context<AddAgent>, agent: Pubkey) -> Result<()> {
-        whitelist::process_add_agent(ctx, agent)
+    pub fn set_stake_pool(ctx: Context<SetStakePool>) -> Result<()> {
+        setters::process_set_stake_pool(ctx)
+    }
+
+    pub fn set_deposit_caps(
+        ctx: Context<SetDepositCaps>,
+        max_total_lamports: Option<u64>,
+        max_epoch_deposit_lamports: Option<u64>,
+    ) -> Result<()> {
+        setters::process_set_deposit_caps(ctx, max_total_lamports, max_epoch_deposit_lamports)
+    }
+
+    pub fn set_attester(ctx: Context<SetAttester>, attester: Option<Pubkey>) -> Result<()> {
+        setters::process_set_attester(ctx, attester)
+    }
+
+    pub fn set_max_agents(ctx: Context<SetMaxAgents>, max_agents: Option<u32>) -> Result<()> {
+        setters::process_set_max_agents(ctx, max_agents)
+    }
+
+    pub fn set_rent_recipient(
+        ctx: Context<SetRentRecipient>,
+        rent_recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        setters::process_set_rent_recipient(ctx, rent_recipient)
+    }
+
+    pub fn add_guardian(ctx: Context<AddGuardian>, guardian: Pubkey) -> Result<()> {
+        setters::process_add_guardian(ctx, guardian)
+    }
+
+    pub fn remove_guardian(ctx: Context<RemoveGuardian>, guardian: Pubkey) -> Result<()> {
+        setters::process_remove_guardian(ctx, guardian)
+    }
+
+    pub fn queue_action(ctx: Context<QueueAction>, action: TimelockedAction) -> Result<()> {
+        timelock::process_queue_action(ctx, action)
+    }
+
+    pub fn cancel_action(ctx: Context<CancelAction>) -> Result<()> {
+        timelock::process_cancel_action(ctx)
+    }
+
+    pub fn set_timelock_delay(ctx: Context<SetTimelockDelay>, delay: i64) -> Result<()> {
+        timelock::process_set_timelock_delay(ctx, delay)
+    }
+
+    pub fn add_agent(ctx: Context<AddAgent>, agent: Pubkey, permissions: u8) -> Result<()> {
+        whitelist::process_add_agent(ctx, agent, permissions)
     }
 
     pub fn remove_agent(ctx: Context<RemoveAgent>, agent: Pubkey) -> Result<()> {
         whitelist::process_remove_agent(ctx, agent)
     }
 
-    pub fn add_user_to_whitelist(ctx: Context<AddUserToWhitelist>, user: Pubkey) -> Result<()> {
-        whitelist::process_add_user_to_whitelist(ctx, user)
+    pub fn migrate_agent(ctx: Context<MigrateAgent>, agent: Pubkey, permissions: u8) -> Result<()> {
+        whitelist::process_migrate_agent(ctx, agent, permissions)
+    }
+
+    pub fn add_user_to_whitelist(
+        ctx: Context<AddUserToWhitelist>,
+        user: Pubkey,
+        deposit_limits: DepositLimits,
+        expiry: Option<i64>,
+    ) -> Result<()> {
+        whitelist::process_add_user_to_whitelist(ctx, user, deposit_limits, expiry)
     }
 
-    pub fn add_user_to_blacklist(ctx: Context<AddUserToBlacklist>, user: Pubkey) -> Result<()> {
-        whitelist::process_add_user_to_blacklist(ctx, user)
+    pub fn batch_set_user_status<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSetUserStatus<'info>>,
+        updates: Vec<UserStatusUpdate>,
+    ) -> Result<()> {
+        whitelist::process_batch_set_user_status(ctx, updates)
     }
 
-    pub fn clear_user_status(ctx: Context<ClearUserStatus>, user: Pubkey) -> Result<()> {
-        whitelist::process_clear_user_status(ctx, user)
+    pub fn set_whitelist_merkle_root(
+        ctx: Context<SetWhitelistMerkleRoot>,
+        merkle_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        whitelist::process_set_whitelist_merkle_root(ctx, merkle_root)
     }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-        staking::process_deposit(ctx, amount)
+    pub fn renew_whitelist(
+        ctx: Context<RenewWhitelist>,
+        user: Pubkey,
+        expiry: Option<i64>,
+    ) -> Result<()> {
+        whitelist::process_renew_whitelist(ctx, user, expiry)
+    }
+
+    pub fn add_user_to_blacklist(
+        ctx: Context<AddUserToBlacklist>,
+        user: Pubkey,
+        reason: StatusChangeReason,
+        case_reference: [u8; 32],
+    ) -> Result<()> {
+        whitelist::process_add_user_to_blacklist(ctx, user, reason, case_reference)
+    }
+
+    pub fn clear_user_status(
+        ctx: Context<ClearUserStatus>,
+        user: Pubkey,
+        reason: StatusChangeReason,
+        case_reference: [u8; 32],
+    ) -> Result<()> {
+        whitelist::process_clear_user_status(ctx, user, reason, case_reference)
+    }
+
+    pub fn migrate_user(ctx: Context<MigrateUser>, old_user: Pubkey) -> Result<()> {
+        whitelist::process_migrate_user(ctx, old_user)
+    }
+
+    pub fn migrate_user_status(ctx: Context<MigrateUserStatus>, user: Pubkey) -> Result<()> {
+        whitelist::process_migrate_user_status(ctx, user)
+    }
+
+    pub fn close_user_status(ctx: Context<CloseUserStatus>, user: Pubkey) -> Result<()> {
+        whitelist::process_close_user_status(ctx, user)
+    }
+
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        amount: u64,
+        min_pool_tokens_out: Option<u64>,
+        whitelist_proof: Option<WhitelistProof>,
+    ) -> Result<()> {
+        staking::process_deposit(ctx, amount, min_pool_tokens_out, whitelist_proof)
+    }
+
+    pub fn deposit_with_attestation(
+        ctx: Context<DepositWithAttestation>,
+        amount: u64,
+        min_pool_tokens_out: Option<u64>,
+        attestation: KycAttestation,
+    ) -> Result<()> {
+        staking::process_deposit_with_attestation(ctx, amount, min_pool_tokens_out, attestation)
+    }
+
+    pub fn deposit_for(
+        ctx: Context<DepositFor>,
+        beneficiary: Pubkey,
+        amount: u64,
+        min_pool_tokens_out: Option<u64>,
+    ) -> Result<()> {
+        staking::process_deposit_for(ctx, beneficiary, amount, min_pool_tokens_out)
+    }
+
+    pub fn deposit_stake(
+        ctx: Context<DepositStake>,
+        min_pool_tokens_out: Option<u64>,
+    ) -> Result<()> {
+        staking::process_deposit_stake(ctx, min_pool_tokens_out)
     }
 
     pub fn deposit_to_specific_validator(
         ctx: Context<DepositToSpecificValidator>,
         amount: u64,
+        transient_stake_seed: u64,
+        ephemeral_stake_seed: u64,
+        min_pool_tokens_out: Option<u64>,
     ) -> Result<()> {
-        staking::process_deposit_to_specific_validator(ctx, amount, 0, 0)
+        staking::process_deposit_to_specific_validator(
+            ctx,
+            amount,
+            transient_stake_seed,
+            ephemeral_stake_seed,
+            min_pool_tokens_out,
+        )
+    }
+
+    pub fn withdraw_sol(ctx: Context<WithdrawSol>, pool_tokens: u64) -> Result<()> {
+        withdrawals::process_withdraw_sol(ctx, pool_tokens)
+    }
+
+    pub fn request_unstake(ctx: Context<RequestUnstake>, pool_tokens: u64) -> Result<()> {
+        withdrawals::process_request_unstake(ctx, pool_tokens)
+    }
+
+    pub fn fund_unstake_tickets(
+        ctx: Context<FundUnstakeTickets>,
+        amount: u64,
+        transient_stake_seed: u64,
+        ephemeral_stake_seed: u64,
+    ) -> Result<()> {
+        withdrawals::process_fund_unstake_tickets(
+            ctx,
+            amount,
+            transient_stake_seed,
+            ephemeral_stake_seed,
+        )
+    }
+
+    pub fn claim_unstake(ctx: Context<ClaimUnstake>) -> Result<()> {
+        withdrawals::process_claim_unstake(ctx)
+    }
+
+    pub fn cancel_unstake(ctx: Context<CancelUnstake>) -> Result<()> {
+        withdrawals::process_cancel_unstake(ctx)
     }
 
     pub fn add_validator(ctx: Context<AddValidator>, validator_seed: u32) -> Result<()> {
@@ -95,14 +277,28 @@ pub mod staker {
     pub fn increase_validator_stake(
         ctx: Context<IncreaseValidatorStake>,
         amount: u64,
+        transient_stake_seed: u64,
+        ephemeral_stake_seed: u64,
     ) -> Result<()> {
-        validators::process_increase_validator_stake(ctx, amount, 0, 0)
+        validators::process_increase_validator_stake(
+            ctx,
+            amount,
+            transient_stake_seed,
+            ephemeral_stake_seed,
+        )
     }
 
     pub fn decrease_validator_stake(
         ctx: Context<DecreaseValidatorStake>,
         amount: u64,
+        transient_stake_seed: u64,
+        ephemeral_stake_seed: u64,
     ) -> Result<()> {
-        validators::process_decrease_validator_stake(ctx, amount, 0, 0)
+        validators::process_decrease_validator_stake(
+            ctx,
+            amount,
+            transient_stake_seed,
+            ephemeral_stake_seed,
+        )
     }
 }
@@ -0,0 +1,82 @@
+//! Merkle proofs for the whitelist mode where agents publish a root of approved wallets.
+//!
+//! Leaves commit to a user and their deposit limits, and are hashed with a different prefix
+//! than internal nodes. Internal nodes hash their children in sorted order, so proofs do not
+//! need to record whether each sibling sits on the left or the right.
+
+use crate::state::DepositLimits;
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Returns the leaf of the whitelist Merkle tree for `user` with the given deposit limits.
+pub fn whitelist_leaf(user: &Pubkey, deposit_limits: &DepositLimits) -> [u8; 32] {
+    let limits = deposit_limits.try_to_vec().unwrap();
+    hashv(&[LEAF_PREFIX, user.as_ref(), &limits]).to_bytes()
+}
+
+/// Returns whether `proof` links `leaf` to `root`.
+pub fn verify_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed_root = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[NODE_PREFIX, &node, sibling]).to_bytes()
+        } else {
+            hashv(&[NODE_PREFIX, sibling, &node]).to_bytes()
+        }
+    });
+    computed_root == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[NODE_PREFIX, &left, &right]).to_bytes()
+    }
+
+    #[test]
+    fn leaf_commits_to_deposit_limits() {
+        let user = Pubkey::new_unique();
+        let unlimited = DepositLimits::default();
+        let limited = DepositLimits {
+            max_lifetime_lamports: Some(100),
+            max_epoch_lamports: None,
+        };
+
+        assert_ne!(
+            whitelist_leaf(&user, &unlimited),
+            whitelist_leaf(&user, &limited)
+        );
+    }
+
+    #[test]
+    fn verifies_proofs_of_every_leaf() {
+        let leaves: Vec<[u8; 32]> = (0..4)
+            .map(|_| whitelist_leaf(&Pubkey::new_unique(), &DepositLimits::default()))
+            .collect();
+        let left = node(leaves[0], leaves[1]);
+        let right = node(leaves[2], leaves[3]);
+        let root = node(left, right);
+
+        assert!(verify_proof(&[leaves[1], right], &root, leaves[0]));
+        assert!(verify_proof(&[leaves[0], right], &root, leaves[1]));
+        assert!(verify_proof(&[leaves[3], left], &root, leaves[2]));
+        assert!(verify_proof(&[leaves[2], left], &root, leaves[3]));
+    }
+
+    #[test]
+    fn rejects_invalid_proofs() {
+        let leaves: Vec<[u8; 32]> = (0..2)
+            .map(|_| whitelist_leaf(&Pubkey::new_unique(), &DepositLimits::default()))
+            .collect();
+        let root = node(leaves[0], leaves[1]);
+        let outsider = whitelist_leaf(&Pubkey::new_unique(), &DepositLimits::default());
+
+        assert!(!verify_proof(&[leaves[1]], &root, outsider));
+        assert!(!verify_proof(&[], &root, leaves[0]));
+        assert!(!verify_proof(&[leaves[0]], &root, leaves[0]));
+    }
+}
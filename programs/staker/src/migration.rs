@@ -0,0 +1,364 @@
+//! Migration of program accounts from older layouts.
+//!
+//! `Access` layouts before version 1 have no version byte, so they are recognised by the size of the
+//! account, which is always allocated with the maximum space of its layout. Versioned layouts
+//! store their version in the first byte after the discriminator. `UserStatus` records are not
+//! versioned, so their layout is recognised by their size alone, as are `Agent` accounts.
+
+use crate::{
+    constants::{
+        ACCESS_RESERVED_SPACE, ACCESS_VERSION, ANCHOR_DISCRIMINATOR, MIN_TIMELOCK_DELAY, PAUSE_ALL,
+    },
+    error::ErrorCode,
+    state::{Access, Agent, DepositLimits, UserStatus, WhitelistUserStatus},
+};
+use anchor_lang::{prelude::*, system_program, Discriminator};
+
+/// Layout of the `Access` account before it was versioned, as deployed by the original program
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct AccessV0 {
+    pub owner: Pubkey,
+    pub stake_manager: Pubkey,
+    pub is_paused: bool,
+    pub pending_owner: Option<Pubkey>,
+}
+
+impl From<AccessV0> for Access {
+    /// Converts the original layout, pausing every operation if the contract was paused.
+    /// The stake pool and the settings added since are left unset for the owner to configure,
+    /// except for the timelock delay, which starts at its minimum.
+    fn from(access: AccessV0) -> Self {
+        Access {
+            version: ACCESS_VERSION,
+            owner: access.owner,
+            stake_manager: access.stake_manager,
+            paused_flags: if access.is_paused { PAUSE_ALL } else { 0 },
+            pending_owner: access.pending_owner,
+            stake_pool: Pubkey::default(),
+            pool_mint: Pubkey::default(),
+            reserve_stake: Pubkey::default(),
+            validator_list: Pubkey::default(),
+            manager_fee_account: Pubkey::default(),
+            withdraw_authority: Pubkey::default(),
+            max_total_lamports: None,
+            max_epoch_deposit_lamports: None,
+            deposit_epoch: 0,
+            epoch_deposit_lamports: 0,
+            whitelist_merkle_root: None,
+            attester: None,
+            active_agents: 0,
+            max_agents: None,
+            rent_recipient: None,
+            timelock_delay: MIN_TIMELOCK_DELAY,
+            next_action_id: 0,
+            guardians: Vec::new(),
+            pending_owner_deadline: None,
+            reserved: [0; ACCESS_RESERVED_SPACE],
+        }
+    }
+}
+
+/// Layout of the `UserStatus` account before deposit limits and whitelist expiries were added
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct UserStatusV0 {
+    pub status: WhitelistUserStatus,
+}
+
+impl From<UserStatusV0> for UserStatus {
+    fn from(user_status: UserStatusV0) -> Self {
+        UserStatus {
+            status: user_status.status,
+            whitelist_expiry: None,
+            deposit_limits: DepositLimits::default(),
+            total_deposited_lamports: 0,
+            deposit_epoch: 0,
+            epoch_deposited_lamports: 0,
+            status_change_reason: None,
+            case_reference: [0; 32],
+        }
+    }
+}
+
+/// Layout of the `Agent` account before agents were given permissions
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct AgentV0 {}
+
+/// Returns the version of the `Access` account data and the account upgraded to the latest
+/// layout.
+pub fn migrate_access_data(data: &[u8]) -> Result<(u8, Access)> {
+    require!(
+        data.starts_with(&Access::DISCRIMINATOR),
+        ErrorCode::UnknownAccessLayout
+    );
+    let mut body = &data[ANCHOR_DISCRIMINATOR..];
+
+    if data.len() == ANCHOR_DISCRIMINATOR + Access::INIT_SPACE {
+        require_neq!(body[0], ACCESS_VERSION, ErrorCode::AccessAlreadyMigrated);
+        return err!(ErrorCode::UnknownAccessLayout);
+    }
+    require_eq!(
+        data.len(),
+        ANCHOR_DISCRIMINATOR + AccessV0::INIT_SPACE,
+        ErrorCode::UnknownAccessLayout
+    );
+    let access = AccessV0::deserialize(&mut body).map_err(|_| ErrorCode::UnknownAccessLayout)?;
+    Ok((0, access.into()))
+}
+
+/// Returns the `UserStatus` account data upgraded to the latest layout
+pub fn migrate_user_status_data(data: &[u8]) -> Result<UserStatus> {
+    require!(
+        data.starts_with(&UserStatus::DISCRIMINATOR),
+        ErrorCode::UnknownUserStatusLayout
+    );
+    require_neq!(
+        data.len(),
+        ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE,
+        ErrorCode::UserStatusAlreadyMigrated
+    );
+    require_eq!(
+        data.len(),
+        ANCHOR_DISCRIMINATOR + UserStatusV0::INIT_SPACE,
+        ErrorCode::UnknownUserStatusLayout
+    );
+    let user_status = UserStatusV0::deserialize(&mut &data[ANCHOR_DISCRIMINATOR..])
+        .map_err(|_| ErrorCode::UnknownUserStatusLayout)?;
+    Ok(user_status.into())
+}
+
+/// Reads the whitelist status of `UserStatus` account data of any layout, as the status is the
+/// first field of every layout
+pub fn read_user_status(data: &[u8]) -> Result<WhitelistUserStatus> {
+    require!(
+        data.starts_with(&UserStatus::DISCRIMINATOR),
+        ErrorCode::UnknownUserStatusLayout
+    );
+    WhitelistUserStatus::deserialize(&mut &data[ANCHOR_DISCRIMINATOR..])
+        .map_err(|_| error!(ErrorCode::UnknownUserStatusLayout))
+}
+
+/// Verifies that the `Agent` account data has the layout of an agent without permissions
+pub fn require_agent_v0_data(data: &[u8]) -> Result<()> {
+    require!(
+        data.starts_with(&Agent::DISCRIMINATOR),
+        ErrorCode::UnknownAgentLayout
+    );
+    require_neq!(
+        data.len(),
+        ANCHOR_DISCRIMINATOR + Agent::INIT_SPACE,
+        ErrorCode::AgentAlreadyMigrated
+    );
+    require_eq!(
+        data.len(),
+        ANCHOR_DISCRIMINATOR + AgentV0::INIT_SPACE,
+        ErrorCode::UnknownAgentLayout
+    );
+    Ok(())
+}
+
+/// Grows a program account to `space` bytes, with `payer` funding the rent of the larger account
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let rent_shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if rent_shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            rent_shortfall,
+        )?;
+    }
+    account.realloc(space, true)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access_v0(is_paused: bool) -> AccessV0 {
+        AccessV0 {
+            owner: Pubkey::new_unique(),
+            stake_manager: Pubkey::new_unique(),
+            is_paused,
+            pending_owner: Some(Pubkey::new_unique()),
+        }
+    }
+
+    /// Returns the data of an account of `space` bytes holding `value` after the discriminator
+    fn account_data(value: &impl AnchorSerialize, space: usize) -> Vec<u8> {
+        let mut data = Access::DISCRIMINATOR.to_vec();
+        data.extend(value.try_to_vec().unwrap());
+        data.resize(ANCHOR_DISCRIMINATOR + space, 0);
+        data
+    }
+
+    #[test]
+    fn migrates_original_layout() {
+        let old = access_v0(false);
+        let data = account_data(&old, AccessV0::INIT_SPACE);
+        // the original account was allocated with the space of its layout
+        assert_eq!(data.len(), ANCHOR_DISCRIMINATOR + 32 + 32 + 1 + 33);
+
+        let (old_version, access) = migrate_access_data(&data).unwrap();
+        assert_eq!(old_version, 0);
+        assert_eq!(access.version, ACCESS_VERSION);
+        assert_eq!(access.owner, old.owner);
+        assert_eq!(access.stake_manager, old.stake_manager);
+        assert_eq!(access.paused_flags, 0);
+        assert_eq!(access.pending_owner, old.pending_owner);
+        assert_eq!(access.stake_pool, Pubkey::default());
+        assert_eq!(access.active_agents, 0);
+        assert_eq!(access.timelock_delay, MIN_TIMELOCK_DELAY);
+        assert!(access.guardians.is_empty());
+        assert_eq!(access.reserved, [0; ACCESS_RESERVED_SPACE]);
+
+        // the migrated account fits the space of the latest layout
+        assert!(access.try_to_vec().unwrap().len() <= Access::INIT_SPACE);
+    }
+
+    #[test]
+    fn migrates_paused_original_layout() {
+        let mut old = access_v0(true);
+        old.pending_owner = None;
+        let data = account_data(&old, AccessV0::INIT_SPACE);
+
+        let (_, access) = migrate_access_data(&data).unwrap();
+        assert_eq!(access.paused_flags, PAUSE_ALL);
+        assert_eq!(access.pending_owner, None);
+    }
+
+    #[test]
+    fn rejects_migrated_layout() {
+        let access: Access = access_v0(false).into();
+        let data = account_data(&access, Access::INIT_SPACE);
+
+        assert_eq!(
+            migrate_access_data(&data).err().unwrap(),
+            ErrorCode::AccessAlreadyMigrated.into()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_layouts() {
+        let data = account_data(&access_v0(false), AccessV0::INIT_SPACE);
+
+        // wrong discriminator
+        let mut wrong_discriminator = data.clone();
+        wrong_discriminator[0] ^= 1;
+        assert_eq!(
+            migrate_access_data(&wrong_discriminator).err().unwrap(),
+            ErrorCode::UnknownAccessLayout.into()
+        );
+
+        // unexpected size
+        assert_eq!(
+            migrate_access_data(&data[..data.len() - 1]).err().unwrap(),
+            ErrorCode::UnknownAccessLayout.into()
+        );
+    }
+
+    #[test]
+    fn migrates_user_status_without_limits() {
+        let mut data = UserStatus::DISCRIMINATOR.to_vec();
+        data.extend(
+            UserStatusV0 {
+                status: WhitelistUserStatus::Blacklisted,
+            }
+            .try_to_vec()
+            .unwrap(),
+        );
+
+        let user_status = migrate_user_status_data(&data).unwrap();
+        assert_eq!(user_status.status, WhitelistUserStatus::Blacklisted);
+        assert_eq!(user_status.whitelist_expiry, None);
+        assert_eq!(user_status.deposit_limits, DepositLimits::default());
+        assert_eq!(user_status.total_deposited_lamports, 0);
+        assert_eq!(user_status.status_change_reason, None);
+    }
+
+    #[test]
+    fn rejects_migrated_user_status() {
+        let user_status: UserStatus = UserStatusV0 {
+            status: WhitelistUserStatus::Whitelisted,
+        }
+        .into();
+        let mut data = UserStatus::DISCRIMINATOR.to_vec();
+        data.extend(user_status.try_to_vec().unwrap());
+        data.resize(ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE, 0);
+
+        assert_eq!(
+            migrate_user_status_data(&data).err().unwrap(),
+            ErrorCode::UserStatusAlreadyMigrated.into()
+        );
+
+        // an unknown status variant
+        let mut unknown_status = UserStatus::DISCRIMINATOR.to_vec();
+        unknown_status.push(3);
+        assert_eq!(
+            migrate_user_status_data(&unknown_status).err().unwrap(),
+            ErrorCode::UnknownUserStatusLayout.into()
+        );
+    }
+
+    #[test]
+    fn reads_status_of_any_user_status_layout() {
+        let mut data = UserStatus::DISCRIMINATOR.to_vec();
+        data.extend(
+            UserStatusV0 {
+                status: WhitelistUserStatus::Blacklisted,
+            }
+            .try_to_vec()
+            .unwrap(),
+        );
+        assert_eq!(
+            read_user_status(&data).unwrap(),
+            WhitelistUserStatus::Blacklisted
+        );
+
+        let mut user_status = migrate_user_status_data(&data).unwrap();
+        user_status.status = WhitelistUserStatus::Whitelisted;
+        let mut data = UserStatus::DISCRIMINATOR.to_vec();
+        data.extend(user_status.try_to_vec().unwrap());
+        data.resize(ANCHOR_DISCRIMINATOR + UserStatus::INIT_SPACE, 0);
+        assert_eq!(
+            read_user_status(&data).unwrap(),
+            WhitelistUserStatus::Whitelisted
+        );
+
+        assert_eq!(
+            read_user_status(&Agent::DISCRIMINATOR).err().unwrap(),
+            ErrorCode::UnknownUserStatusLayout.into()
+        );
+    }
+
+    #[test]
+    fn recognises_agents_without_permissions() {
+        let data = Agent::DISCRIMINATOR.to_vec();
+        assert!(require_agent_v0_data(&data).is_ok());
+
+        let mut migrated = data.clone();
+        migrated.resize(ANCHOR_DISCRIMINATOR + Agent::INIT_SPACE, 0);
+        assert_eq!(
+            require_agent_v0_data(&migrated).err().unwrap(),
+            ErrorCode::AgentAlreadyMigrated.into()
+        );
+
+        let mut wrong_discriminator = data.clone();
+        wrong_discriminator[0] ^= 1;
+        assert_eq!(
+            require_agent_v0_data(&wrong_discriminator).err().unwrap(),
+            ErrorCode::UnknownAgentLayout.into()
+        );
+    }
+}
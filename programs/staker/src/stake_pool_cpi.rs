@@ -0,0 +1,850 @@
+//! Typed CPI helpers for the SPL Stake Pool program.
+//!
+//! Each instruction has an accounts struct, in the order expected by the Stake Pool program,
+//! and a function that invokes it from a `CpiContext`, following the style of `anchor_spl`.
+
+use crate::constants::STAKE_POOL_PROGRAM_ID;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, program::invoke_signed},
+};
+
+/// The instructions of the Stake Pool program invoked by the Staker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StakePoolInstruction {
+    AddValidatorToPool {
+        validator_seed: u32,
+    },
+    RemoveValidatorFromPool,
+    DepositStake,
+    DepositSol {
+        lamports: u64,
+    },
+    WithdrawSol {
+        pool_tokens: u64,
+    },
+    IncreaseAdditionalValidatorStake {
+        lamports: u64,
+        transient_stake_seed: u64,
+        ephemeral_stake_seed: u64,
+    },
+    DecreaseAdditionalValidatorStake {
+        lamports: u64,
+        transient_stake_seed: u64,
+        ephemeral_stake_seed: u64,
+    },
+    DepositStakeWithSlippage {
+        minimum_pool_tokens_out: u64,
+    },
+    DepositSolWithSlippage {
+        lamports_in: u64,
+        minimum_pool_tokens_out: u64,
+    },
+    WithdrawSolWithSlippage {
+        pool_tokens_in: u64,
+        minimum_lamports_out: u64,
+    },
+}
+
+impl StakePoolInstruction {
+    /// Returns the index of the instruction in the Stake Pool program.
+    pub fn index(&self) -> u8 {
+        match self {
+            Self::AddValidatorToPool { .. } => 1,
+            Self::RemoveValidatorFromPool => 2,
+            Self::DepositStake => 9,
+            Self::DepositSol { .. } => 14,
+            Self::WithdrawSol { .. } => 16,
+            Self::IncreaseAdditionalValidatorStake { .. } => 19,
+            Self::DecreaseAdditionalValidatorStake { .. } => 20,
+            Self::DepositStakeWithSlippage { .. } => 23,
+            Self::DepositSolWithSlippage { .. } => 25,
+            Self::WithdrawSolWithSlippage { .. } => 26,
+        }
+    }
+
+    /// Returns the borsh serialized instruction data.
+    pub fn data(&self) -> Vec<u8> {
+        let mut data = vec![self.index()];
+        match self {
+            Self::AddValidatorToPool { validator_seed } => {
+                data.extend_from_slice(&validator_seed.to_le_bytes());
+            }
+            Self::RemoveValidatorFromPool | Self::DepositStake => {}
+            Self::DepositSol { lamports } => {
+                data.extend_from_slice(&lamports.to_le_bytes());
+            }
+            Self::WithdrawSol { pool_tokens } => {
+                data.extend_from_slice(&pool_tokens.to_le_bytes());
+            }
+            Self::IncreaseAdditionalValidatorStake {
+                lamports,
+                transient_stake_seed,
+                ephemeral_stake_seed,
+            }
+            | Self::DecreaseAdditionalValidatorStake {
+                lamports,
+                transient_stake_seed,
+                ephemeral_stake_seed,
+            } => {
+                data.extend_from_slice(&lamports.to_le_bytes());
+                data.extend_from_slice(&transient_stake_seed.to_le_bytes());
+                data.extend_from_slice(&ephemeral_stake_seed.to_le_bytes());
+            }
+            Self::DepositStakeWithSlippage {
+                minimum_pool_tokens_out,
+            } => {
+                data.extend_from_slice(&minimum_pool_tokens_out.to_le_bytes());
+            }
+            Self::DepositSolWithSlippage {
+                lamports_in,
+                minimum_pool_tokens_out,
+            } => {
+                data.extend_from_slice(&lamports_in.to_le_bytes());
+                data.extend_from_slice(&minimum_pool_tokens_out.to_le_bytes());
+            }
+            Self::WithdrawSolWithSlippage {
+                pool_tokens_in,
+                minimum_lamports_out,
+            } => {
+                data.extend_from_slice(&pool_tokens_in.to_le_bytes());
+                data.extend_from_slice(&minimum_lamports_out.to_le_bytes());
+            }
+        }
+        data
+    }
+}
+
+/// Builds the instruction from the CPI context and invokes it with the context signer seeds.
+fn invoke_stake_pool_instruction<'info, T>(
+    ctx: CpiContext<'_, '_, '_, 'info, T>,
+    instruction: StakePoolInstruction,
+) -> Result<()>
+where
+    T: ToAccountMetas + ToAccountInfos<'info>,
+{
+    let ix = Instruction {
+        program_id: STAKE_POOL_PROGRAM_ID,
+        accounts: ctx.to_account_metas(None),
+        data: instruction.data(),
+    };
+    invoke_signed(&ix, &ctx.to_account_infos(), ctx.signer_seeds).map_err(Into::into)
+}
+
+pub fn add_validator_to_pool<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, AddValidatorToPool<'info>>,
+    validator_seed: u32,
+) -> Result<()> {
+    invoke_stake_pool_instruction(
+        ctx,
+        StakePoolInstruction::AddValidatorToPool { validator_seed },
+    )
+}
+
+pub fn remove_validator_from_pool<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, RemoveValidatorFromPool<'info>>,
+) -> Result<()> {
+    invoke_stake_pool_instruction(ctx, StakePoolInstruction::RemoveValidatorFromPool)
+}
+
+pub fn deposit_stake<'info>(ctx: CpiContext<'_, '_, '_, 'info, DepositStake<'info>>) -> Result<()> {
+    invoke_stake_pool_instruction(ctx, StakePoolInstruction::DepositStake)
+}
+
+pub fn deposit_stake_with_slippage<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DepositStake<'info>>,
+    minimum_pool_tokens_out: u64,
+) -> Result<()> {
+    invoke_stake_pool_instruction(
+        ctx,
+        StakePoolInstruction::DepositStakeWithSlippage {
+            minimum_pool_tokens_out,
+        },
+    )
+}
+
+pub fn deposit_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DepositSol<'info>>,
+    lamports: u64,
+) -> Result<()> {
+    invoke_stake_pool_instruction(ctx, StakePoolInstruction::DepositSol { lamports })
+}
+
+pub fn deposit_sol_with_slippage<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DepositSol<'info>>,
+    lamports_in: u64,
+    minimum_pool_tokens_out: u64,
+) -> Result<()> {
+    invoke_stake_pool_instruction(
+        ctx,
+        StakePoolInstruction::DepositSolWithSlippage {
+            lamports_in,
+            minimum_pool_tokens_out,
+        },
+    )
+}
+
+pub fn withdraw_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, WithdrawSol<'info>>,
+    pool_tokens: u64,
+) -> Result<()> {
+    invoke_stake_pool_instruction(ctx, StakePoolInstruction::WithdrawSol { pool_tokens })
+}
+
+pub fn withdraw_sol_with_slippage<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, WithdrawSol<'info>>,
+    pool_tokens_in: u64,
+    minimum_lamports_out: u64,
+) -> Result<()> {
+    invoke_stake_pool_instruction(
+        ctx,
+        StakePoolInstruction::WithdrawSolWithSlippage {
+            pool_tokens_in,
+            minimum_lamports_out,
+        },
+    )
+}
+
+pub fn increase_additional_validator_stake<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, IncreaseAdditionalValidatorStake<'info>>,
+    lamports: u64,
+    transient_stake_seed: u64,
+    ephemeral_stake_seed: u64,
+) -> Result<()> {
+    invoke_stake_pool_instruction(
+        ctx,
+        StakePoolInstruction::IncreaseAdditionalValidatorStake {
+            lamports,
+            transient_stake_seed,
+            ephemeral_stake_seed,
+        },
+    )
+}
+
+pub fn decrease_additional_validator_stake<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DecreaseAdditionalValidatorStake<'info>>,
+    lamports: u64,
+    transient_stake_seed: u64,
+    ephemeral_stake_seed: u64,
+) -> Result<()> {
+    invoke_stake_pool_instruction(
+        ctx,
+        StakePoolInstruction::DecreaseAdditionalValidatorStake {
+            lamports,
+            transient_stake_seed,
+            ephemeral_stake_seed,
+        },
+    )
+}
+
+pub struct AddValidatorToPool<'info> {
+    pub stake_pool: AccountInfo<'info>,
+    pub staker: AccountInfo<'info>,
+    pub reserve_stake: AccountInfo<'info>,
+    pub withdraw_authority: AccountInfo<'info>,
+    pub validator_list: AccountInfo<'info>,
+    pub validator_stake_account: AccountInfo<'info>,
+    pub validator_vote_account: AccountInfo<'info>,
+    pub rent_sysvar: AccountInfo<'info>,
+    pub clock_sysvar: AccountInfo<'info>,
+    pub stake_history_sysvar: AccountInfo<'info>,
+    pub stake_config_sysvar: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub stake_program: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for AddValidatorToPool<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.stake_pool.key(), false),
+            AccountMeta::new_readonly(self.staker.key(), true),
+            AccountMeta::new(self.reserve_stake.key(), false),
+            AccountMeta::new_readonly(self.withdraw_authority.key(), false),
+            AccountMeta::new(self.validator_list.key(), false),
+            AccountMeta::new(self.validator_stake_account.key(), false),
+            AccountMeta::new_readonly(self.validator_vote_account.key(), false),
+            AccountMeta::new_readonly(self.rent_sysvar.key(), false),
+            AccountMeta::new_readonly(self.clock_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_history_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_config_sysvar.key(), false),
+            AccountMeta::new_readonly(self.system_program.key(), false),
+            AccountMeta::new_readonly(self.stake_program.key(), false),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for AddValidatorToPool<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.stake_pool.clone(),
+            self.staker.clone(),
+            self.reserve_stake.clone(),
+            self.withdraw_authority.clone(),
+            self.validator_list.clone(),
+            self.validator_stake_account.clone(),
+            self.validator_vote_account.clone(),
+            self.rent_sysvar.clone(),
+            self.clock_sysvar.clone(),
+            self.stake_history_sysvar.clone(),
+            self.stake_config_sysvar.clone(),
+            self.system_program.clone(),
+            self.stake_program.clone(),
+        ]
+    }
+}
+
+pub struct RemoveValidatorFromPool<'info> {
+    pub stake_pool: AccountInfo<'info>,
+    pub staker: AccountInfo<'info>,
+    pub withdraw_authority: AccountInfo<'info>,
+    pub validator_list: AccountInfo<'info>,
+    pub validator_stake_account: AccountInfo<'info>,
+    pub transient_stake_account: AccountInfo<'info>,
+    pub clock_sysvar: AccountInfo<'info>,
+    pub stake_program: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for RemoveValidatorFromPool<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.stake_pool.key(), false),
+            AccountMeta::new_readonly(self.staker.key(), true),
+            AccountMeta::new_readonly(self.withdraw_authority.key(), false),
+            AccountMeta::new(self.validator_list.key(), false),
+            AccountMeta::new(self.validator_stake_account.key(), false),
+            AccountMeta::new(self.transient_stake_account.key(), false),
+            AccountMeta::new_readonly(self.clock_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_program.key(), false),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for RemoveValidatorFromPool<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.stake_pool.clone(),
+            self.staker.clone(),
+            self.withdraw_authority.clone(),
+            self.validator_list.clone(),
+            self.validator_stake_account.clone(),
+            self.transient_stake_account.clone(),
+            self.clock_sysvar.clone(),
+            self.stake_program.clone(),
+        ]
+    }
+}
+
+pub struct DepositStake<'info> {
+    pub stake_pool: AccountInfo<'info>,
+    pub validator_list: AccountInfo<'info>,
+    pub deposit_authority: AccountInfo<'info>,
+    pub withdraw_authority: AccountInfo<'info>,
+    pub stake_account: AccountInfo<'info>,
+    pub validator_stake_account: AccountInfo<'info>,
+    pub reserve_stake: AccountInfo<'info>,
+    pub pool_tokens_to: AccountInfo<'info>,
+    pub manager_fee_account: AccountInfo<'info>,
+    pub referrer_pool_tokens_account: AccountInfo<'info>,
+    pub pool_mint: AccountInfo<'info>,
+    pub clock_sysvar: AccountInfo<'info>,
+    pub stake_history_sysvar: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub stake_program: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for DepositStake<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.stake_pool.key(), false),
+            AccountMeta::new(self.validator_list.key(), false),
+            AccountMeta::new_readonly(self.deposit_authority.key(), true),
+            AccountMeta::new_readonly(self.withdraw_authority.key(), false),
+            AccountMeta::new(self.stake_account.key(), false),
+            AccountMeta::new(self.validator_stake_account.key(), false),
+            AccountMeta::new(self.reserve_stake.key(), false),
+            AccountMeta::new(self.pool_tokens_to.key(), false),
+            AccountMeta::new(self.manager_fee_account.key(), false),
+            AccountMeta::new(self.referrer_pool_tokens_account.key(), false),
+            AccountMeta::new(self.pool_mint.key(), false),
+            AccountMeta::new_readonly(self.clock_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_history_sysvar.key(), false),
+            AccountMeta::new_readonly(self.token_program.key(), false),
+            AccountMeta::new_readonly(self.stake_program.key(), false),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for DepositStake<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.stake_pool.clone(),
+            self.validator_list.clone(),
+            self.deposit_authority.clone(),
+            self.withdraw_authority.clone(),
+            self.stake_account.clone(),
+            self.validator_stake_account.clone(),
+            self.reserve_stake.clone(),
+            self.pool_tokens_to.clone(),
+            self.manager_fee_account.clone(),
+            self.referrer_pool_tokens_account.clone(),
+            self.pool_mint.clone(),
+            self.clock_sysvar.clone(),
+            self.stake_history_sysvar.clone(),
+            self.token_program.clone(),
+            self.stake_program.clone(),
+        ]
+    }
+}
+
+pub struct DepositSol<'info> {
+    pub stake_pool: AccountInfo<'info>,
+    pub withdraw_authority: AccountInfo<'info>,
+    pub reserve_stake: AccountInfo<'info>,
+    pub lamports_from: AccountInfo<'info>,
+    pub pool_tokens_to: AccountInfo<'info>,
+    pub manager_fee_account: AccountInfo<'info>,
+    pub referrer_pool_tokens_account: AccountInfo<'info>,
+    pub pool_mint: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub deposit_authority: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for DepositSol<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.stake_pool.key(), false),
+            AccountMeta::new_readonly(self.withdraw_authority.key(), false),
+            AccountMeta::new(self.reserve_stake.key(), false),
+            AccountMeta::new(self.lamports_from.key(), true),
+            AccountMeta::new(self.pool_tokens_to.key(), false),
+            AccountMeta::new(self.manager_fee_account.key(), false),
+            AccountMeta::new(self.referrer_pool_tokens_account.key(), false),
+            AccountMeta::new(self.pool_mint.key(), false),
+            AccountMeta::new_readonly(self.system_program.key(), false),
+            AccountMeta::new_readonly(self.token_program.key(), false),
+            AccountMeta::new_readonly(self.deposit_authority.key(), true),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for DepositSol<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.stake_pool.clone(),
+            self.withdraw_authority.clone(),
+            self.reserve_stake.clone(),
+            self.lamports_from.clone(),
+            self.pool_tokens_to.clone(),
+            self.manager_fee_account.clone(),
+            self.referrer_pool_tokens_account.clone(),
+            self.pool_mint.clone(),
+            self.system_program.clone(),
+            self.token_program.clone(),
+            self.deposit_authority.clone(),
+        ]
+    }
+}
+
+pub struct WithdrawSol<'info> {
+    pub stake_pool: AccountInfo<'info>,
+    pub withdraw_authority: AccountInfo<'info>,
+    pub user_transfer_authority: AccountInfo<'info>,
+    pub pool_tokens_from: AccountInfo<'info>,
+    pub reserve_stake: AccountInfo<'info>,
+    pub lamports_to: AccountInfo<'info>,
+    pub manager_fee_account: AccountInfo<'info>,
+    pub pool_mint: AccountInfo<'info>,
+    pub clock_sysvar: AccountInfo<'info>,
+    pub stake_history_sysvar: AccountInfo<'info>,
+    pub stake_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub sol_withdraw_authority: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for WithdrawSol<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.stake_pool.key(), false),
+            AccountMeta::new_readonly(self.withdraw_authority.key(), false),
+            AccountMeta::new_readonly(self.user_transfer_authority.key(), true),
+            AccountMeta::new(self.pool_tokens_from.key(), false),
+            AccountMeta::new(self.reserve_stake.key(), false),
+            AccountMeta::new(self.lamports_to.key(), false),
+            AccountMeta::new(self.manager_fee_account.key(), false),
+            AccountMeta::new(self.pool_mint.key(), false),
+            AccountMeta::new_readonly(self.clock_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_history_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_program.key(), false),
+            AccountMeta::new_readonly(self.token_program.key(), false),
+            AccountMeta::new_readonly(self.sol_withdraw_authority.key(), true),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for WithdrawSol<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.stake_pool.clone(),
+            self.withdraw_authority.clone(),
+            self.user_transfer_authority.clone(),
+            self.pool_tokens_from.clone(),
+            self.reserve_stake.clone(),
+            self.lamports_to.clone(),
+            self.manager_fee_account.clone(),
+            self.pool_mint.clone(),
+            self.clock_sysvar.clone(),
+            self.stake_history_sysvar.clone(),
+            self.stake_program.clone(),
+            self.token_program.clone(),
+            self.sol_withdraw_authority.clone(),
+        ]
+    }
+}
+
+pub struct IncreaseAdditionalValidatorStake<'info> {
+    pub stake_pool: AccountInfo<'info>,
+    pub staker: AccountInfo<'info>,
+    pub withdraw_authority: AccountInfo<'info>,
+    pub validator_list: AccountInfo<'info>,
+    pub reserve_stake: AccountInfo<'info>,
+    pub ephemeral_stake_account: AccountInfo<'info>,
+    pub transient_stake_account: AccountInfo<'info>,
+    pub validator_stake_account: AccountInfo<'info>,
+    pub validator_vote_account: AccountInfo<'info>,
+    pub clock_sysvar: AccountInfo<'info>,
+    pub stake_history_sysvar: AccountInfo<'info>,
+    pub stake_config_sysvar: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub stake_program: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for IncreaseAdditionalValidatorStake<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.stake_pool.key(), false),
+            AccountMeta::new_readonly(self.staker.key(), true),
+            AccountMeta::new_readonly(self.withdraw_authority.key(), false),
+            AccountMeta::new(self.validator_list.key(), false),
+            AccountMeta::new(self.reserve_stake.key(), false),
+            AccountMeta::new(self.ephemeral_stake_account.key(), false),
+            AccountMeta::new(self.transient_stake_account.key(), false),
+            AccountMeta::new_readonly(self.validator_stake_account.key(), false),
+            AccountMeta::new_readonly(self.validator_vote_account.key(), false),
+            AccountMeta::new_readonly(self.clock_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_history_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_config_sysvar.key(), false),
+            AccountMeta::new_readonly(self.system_program.key(), false),
+            AccountMeta::new_readonly(self.stake_program.key(), false),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for IncreaseAdditionalValidatorStake<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.stake_pool.clone(),
+            self.staker.clone(),
+            self.withdraw_authority.clone(),
+            self.validator_list.clone(),
+            self.reserve_stake.clone(),
+            self.ephemeral_stake_account.clone(),
+            self.transient_stake_account.clone(),
+            self.validator_stake_account.clone(),
+            self.validator_vote_account.clone(),
+            self.clock_sysvar.clone(),
+            self.stake_history_sysvar.clone(),
+            self.stake_config_sysvar.clone(),
+            self.system_program.clone(),
+            self.stake_program.clone(),
+        ]
+    }
+}
+
+pub struct DecreaseAdditionalValidatorStake<'info> {
+    pub stake_pool: AccountInfo<'info>,
+    pub staker: AccountInfo<'info>,
+    pub withdraw_authority: AccountInfo<'info>,
+    pub validator_list: AccountInfo<'info>,
+    pub reserve_stake: AccountInfo<'info>,
+    pub validator_stake_account: AccountInfo<'info>,
+    pub ephemeral_stake_account: AccountInfo<'info>,
+    pub transient_stake_account: AccountInfo<'info>,
+    pub clock_sysvar: AccountInfo<'info>,
+    pub stake_history_sysvar: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub stake_program: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for DecreaseAdditionalValidatorStake<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.stake_pool.key(), false),
+            AccountMeta::new_readonly(self.staker.key(), true),
+            AccountMeta::new_readonly(self.withdraw_authority.key(), false),
+            AccountMeta::new(self.validator_list.key(), false),
+            AccountMeta::new(self.reserve_stake.key(), false),
+            AccountMeta::new(self.validator_stake_account.key(), false),
+            AccountMeta::new(self.ephemeral_stake_account.key(), false),
+            AccountMeta::new(self.transient_stake_account.key(), false),
+            AccountMeta::new_readonly(self.clock_sysvar.key(), false),
+            AccountMeta::new_readonly(self.stake_history_sysvar.key(), false),
+            AccountMeta::new_readonly(self.system_program.key(), false),
+            AccountMeta::new_readonly(self.stake_program.key(), false),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for DecreaseAdditionalValidatorStake<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.stake_pool.clone(),
+            self.staker.clone(),
+            self.withdraw_authority.clone(),
+            self.validator_list.clone(),
+            self.reserve_stake.clone(),
+            self.validator_stake_account.clone(),
+            self.ephemeral_stake_account.clone(),
+            self.transient_stake_account.clone(),
+            self.clock_sysvar.clone(),
+            self.stake_history_sysvar.clone(),
+            self.system_program.clone(),
+            self.stake_program.clone(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_info(key: Pubkey) -> AccountInfo<'static> {
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            false,
+            Box::leak(Box::new(0)),
+            Box::leak(Vec::new().into_boxed_slice()),
+            &STAKE_POOL_PROGRAM_ID,
+            false,
+            0,
+        )
+    }
+
+    fn flags(metas: &[AccountMeta]) -> Vec<(bool, bool)> {
+        metas.iter().map(|m| (m.is_writable, m.is_signer)).collect()
+    }
+
+    #[test]
+    fn test_instruction_indexes() {
+        let expected = [
+            (
+                StakePoolInstruction::AddValidatorToPool { validator_seed: 0 },
+                1,
+            ),
+            (StakePoolInstruction::RemoveValidatorFromPool, 2),
+            (StakePoolInstruction::DepositStake, 9),
+            (StakePoolInstruction::DepositSol { lamports: 0 }, 14),
+            (StakePoolInstruction::WithdrawSol { pool_tokens: 0 }, 16),
+            (
+                StakePoolInstruction::IncreaseAdditionalValidatorStake {
+                    lamports: 0,
+                    transient_stake_seed: 0,
+                    ephemeral_stake_seed: 0,
+                },
+                19,
+            ),
+            (
+                StakePoolInstruction::DecreaseAdditionalValidatorStake {
+                    lamports: 0,
+                    transient_stake_seed: 0,
+                    ephemeral_stake_seed: 0,
+                },
+                20,
+            ),
+            (
+                StakePoolInstruction::DepositStakeWithSlippage {
+                    minimum_pool_tokens_out: 0,
+                },
+                23,
+            ),
+            (
+                StakePoolInstruction::DepositSolWithSlippage {
+                    lamports_in: 0,
+                    minimum_pool_tokens_out: 0,
+                },
+                25,
+            ),
+            (
+                StakePoolInstruction::WithdrawSolWithSlippage {
+                    pool_tokens_in: 0,
+                    minimum_lamports_out: 0,
+                },
+                26,
+            ),
+        ];
+
+        for (instruction, index) in expected {
+            assert_eq!(instruction.index(), index, "{:?}", instruction);
+            assert_eq!(instruction.data()[0], index, "{:?}", instruction);
+        }
+    }
+
+    #[test]
+    fn test_add_validator_to_pool_data() {
+        let data = StakePoolInstruction::AddValidatorToPool {
+            validator_seed: 0x0403_0201,
+        }
+        .data();
+        assert_eq!(data, vec![1, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_deposit_sol_data() {
+        let data = StakePoolInstruction::DepositSol { lamports: 1_000 }.data();
+        assert_eq!(data, vec![14, 0xe8, 0x03, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_validator_stake_data() {
+        let increase = StakePoolInstruction::IncreaseAdditionalValidatorStake {
+            lamports: 1,
+            transient_stake_seed: 2,
+            ephemeral_stake_seed: 3,
+        }
+        .data();
+        let decrease = StakePoolInstruction::DecreaseAdditionalValidatorStake {
+            lamports: 1,
+            transient_stake_seed: 2,
+            ephemeral_stake_seed: 3,
+        }
+        .data();
+
+        let fields = [
+            1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(increase[0], 19);
+        assert_eq!(increase[1..], fields);
+        assert_eq!(decrease[0], 20);
+        assert_eq!(decrease[1..], fields);
+    }
+
+    #[test]
+    fn test_slippage_data() {
+        let deposit_sol = StakePoolInstruction::DepositSolWithSlippage {
+            lamports_in: 5,
+            minimum_pool_tokens_out: 4,
+        }
+        .data();
+        assert_eq!(
+            deposit_sol,
+            vec![25, 5, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let withdraw_sol = StakePoolInstruction::WithdrawSolWithSlippage {
+            pool_tokens_in: 5,
+            minimum_lamports_out: 4,
+        }
+        .data();
+        assert_eq!(
+            withdraw_sol,
+            vec![26, 5, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let deposit_stake = StakePoolInstruction::DepositStakeWithSlippage {
+            minimum_pool_tokens_out: 4,
+        }
+        .data();
+        assert_eq!(deposit_stake, vec![23, 4, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_deposit_sol_accounts() {
+        let keys: Vec<Pubkey> = (0..11).map(|_| Pubkey::new_unique()).collect();
+        let infos: Vec<AccountInfo> = keys.iter().map(|k| account_info(*k)).collect();
+        let accounts = DepositSol {
+            stake_pool: infos[0].clone(),
+            withdraw_authority: infos[1].clone(),
+            reserve_stake: infos[2].clone(),
+            lamports_from: infos[3].clone(),
+            pool_tokens_to: infos[4].clone(),
+            manager_fee_account: infos[5].clone(),
+            referrer_pool_tokens_account: infos[6].clone(),
+            pool_mint: infos[7].clone(),
+            system_program: infos[8].clone(),
+            token_program: infos[9].clone(),
+            deposit_authority: infos[10].clone(),
+        };
+
+        let metas = accounts.to_account_metas(None);
+        assert_eq!(metas.iter().map(|m| m.pubkey).collect::<Vec<_>>(), keys);
+        assert_eq!(
+            flags(&metas),
+            vec![
+                (true, false),
+                (false, false),
+                (true, false),
+                (true, true),
+                (true, false),
+                (true, false),
+                (true, false),
+                (true, false),
+                (false, false),
+                (false, false),
+                (false, true),
+            ]
+        );
+        assert_eq!(
+            accounts
+                .to_account_infos()
+                .iter()
+                .map(|a| *a.key)
+                .collect::<Vec<_>>(),
+            keys
+        );
+    }
+
+    #[test]
+    fn test_increase_additional_validator_stake_accounts() {
+        let keys: Vec<Pubkey> = (0..14).map(|_| Pubkey::new_unique()).collect();
+        let infos: Vec<AccountInfo> = keys.iter().map(|k| account_info(*k)).collect();
+        let accounts = IncreaseAdditionalValidatorStake {
+            stake_pool: infos[0].clone(),
+            staker: infos[1].clone(),
+            withdraw_authority: infos[2].clone(),
+            validator_list: infos[3].clone(),
+            reserve_stake: infos[4].clone(),
+            ephemeral_stake_account: infos[5].clone(),
+            transient_stake_account: infos[6].clone(),
+            validator_stake_account: infos[7].clone(),
+            validator_vote_account: infos[8].clone(),
+            clock_sysvar: infos[9].clone(),
+            stake_history_sysvar: infos[10].clone(),
+            stake_config_sysvar: infos[11].clone(),
+            system_program: infos[12].clone(),
+            stake_program: infos[13].clone(),
+        };
+
+        let metas = accounts.to_account_metas(None);
+        assert_eq!(metas.iter().map(|m| m.pubkey).collect::<Vec<_>>(), keys);
+        assert_eq!(
+            flags(&metas),
+            vec![
+                (false, false),
+                (false, true),
+                (false, false),
+                (true, false),
+                (true, false),
+                (true, false),
+                (true, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+            ]
+        );
+    }
+}
@@ -1,22 +1,36 @@
-use crate::state::types::WhitelistUserStatus;
+use crate::state::types::{StatusChangeReason, TimelockedAction, WhitelistUserStatus};
 use anchor_lang::prelude::*;
 
 #[event]
 pub struct StakerInitialized {
     pub owner: Pubkey,
     pub stake_manager: Pubkey,
+    pub stake_pool: Pubkey,
 }
 
 #[event]
-pub struct StakerPaused {}
+pub struct StakerPaused {
+    pub flags: u8,
+    pub paused_flags: u8,
+}
 
 #[event]
-pub struct StakerUnpaused {}
+pub struct StakerUnpaused {
+    pub flags: u8,
+    pub paused_flags: u8,
+}
 
 #[event]
 pub struct SetPendingOwner {
     pub current_owner: Pubkey,
     pub pending_owner: Pubkey,
+    pub deadline: Option<u64>,
+}
+
+#[event]
+pub struct PendingOwnerCancelled {
+    pub current_owner: Pubkey,
+    pub pending_owner: Pubkey,
 }
 
 #[event]
@@ -28,11 +42,14 @@ pub struct ClaimedOwnership {
 #[event]
 pub struct AgentAdded {
     pub new_agent: Pubkey,
+    pub permissions: u8,
+    pub added_by: Pubkey,
 }
 
 #[event]
 pub struct AgentRemoved {
     pub removed_agent: Pubkey,
+    pub removed_by: Pubkey,
 }
 
 #[event]
@@ -40,6 +57,8 @@ pub struct WhitelistingStatusChanged {
     pub user: Pubkey,
     pub old_status: WhitelistUserStatus,
     pub new_status: WhitelistUserStatus,
+    pub reason: Option<StatusChangeReason>,
+    pub case_reference: Option<[u8; 32]>,
 }
 
 #[event]
@@ -81,3 +100,159 @@ pub struct ValidatorStakeDecreased {
     pub validator: Pubkey,
     pub amount: u64,
 }
+
+#[event]
+pub struct StakePoolSet {
+    pub old_stake_pool: Pubkey,
+    pub new_stake_pool: Pubkey,
+}
+
+#[event]
+pub struct StakeDeposited {
+    pub stake_account: Pubkey,
+    pub validator: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct Withdrawn {
+    pub user: Pubkey,
+    pub pool_tokens: u64,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct UnstakeRequested {
+    pub user: Pubkey,
+    pub pool_tokens: u64,
+    pub lamports: u64,
+    pub claimable_epoch: u64,
+}
+
+#[event]
+pub struct UnstakeTicketsFunded {
+    pub validator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct UnstakeClaimed {
+    pub user: Pubkey,
+    pub pool_tokens: u64,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct UnstakeCancelled {
+    pub user: Pubkey,
+    pub pool_tokens: u64,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct DepositCapsSet {
+    pub old_max_total_lamports: Option<u64>,
+    pub new_max_total_lamports: Option<u64>,
+    pub old_max_epoch_deposit_lamports: Option<u64>,
+    pub new_max_epoch_deposit_lamports: Option<u64>,
+}
+
+#[event]
+pub struct WhitelistExpirySet {
+    pub user: Pubkey,
+    pub expiry: Option<i64>,
+}
+
+#[event]
+pub struct WhitelistMerkleRootSet {
+    pub old_merkle_root: Option<[u8; 32]>,
+    pub new_merkle_root: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct AttesterSet {
+    pub old_attester: Option<Pubkey>,
+    pub new_attester: Option<Pubkey>,
+}
+
+#[event]
+pub struct MaxAgentsSet {
+    pub old_max_agents: Option<u32>,
+    pub new_max_agents: Option<u32>,
+}
+
+#[event]
+pub struct UserStatusClosed {
+    pub user: Pubkey,
+    pub rent_recipient: Pubkey,
+}
+
+#[event]
+pub struct RentRecipientSet {
+    pub old_rent_recipient: Option<Pubkey>,
+    pub new_rent_recipient: Option<Pubkey>,
+}
+
+#[event]
+pub struct UserMigrated {
+    pub old_user: Pubkey,
+    pub new_user: Pubkey,
+}
+
+#[event]
+pub struct DepositedFor {
+    pub payer: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ActionQueued {
+    pub id: u64,
+    pub action: TimelockedAction,
+    pub execute_after: i64,
+}
+
+#[event]
+pub struct ActionCancelled {
+    pub id: u64,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct ActionExecuted {
+    pub id: u64,
+}
+
+#[event]
+pub struct TimelockDelaySet {
+    pub old_delay: i64,
+    pub new_delay: i64,
+}
+
+#[event]
+pub struct GuardianAdded {
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct GuardianRemoved {
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct AccessMigrated {
+    pub old_version: u8,
+    pub new_version: u8,
+}
+
+#[event]
+pub struct UserStatusMigrated {
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct AgentMigrated {
+    pub agent: Pubkey,
+    pub permissions: u8,
+}
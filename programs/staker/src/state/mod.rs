@@ -1,5 +1,7 @@
 pub mod events;
+pub mod stake_pool;
 pub mod types;
 
 pub use events::*;
+pub use stake_pool::*;
 pub use types::*;
@@ -0,0 +1,284 @@
+use crate::{error::ErrorCode, STAKE_POOL_PROGRAM_ID};
+use anchor_lang::prelude::*;
+
+/// Mirrors the `AccountType` enum of the SPL Stake Pool program.
+#[derive(AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StakePoolAccountType {
+    Uninitialized,
+    StakePool,
+    ValidatorList,
+}
+
+/// Mirrors the `Fee` struct of the SPL Stake Pool program.
+#[derive(AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fee {
+    pub denominator: u64,
+    pub numerator: u64,
+}
+
+impl Fee {
+    /// Applies the fee to `amount`, rounding up as the Stake Pool program does.
+    pub fn apply(&self, amount: u64) -> Option<u128> {
+        if self.denominator == 0 {
+            return Some(0);
+        }
+        let denominator = u128::from(self.denominator);
+        u128::from(amount)
+            .checked_mul(u128::from(self.numerator))?
+            .checked_add(denominator)?
+            .checked_sub(1)?
+            .checked_div(denominator)
+    }
+}
+
+/// Mirrors the `FutureEpoch` enum of the SPL Stake Pool program.
+#[derive(AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FutureEpoch<T> {
+    None,
+    One(T),
+    Two(T),
+}
+
+/// Mirrors the `Lockup` struct of the Stake program.
+#[derive(AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lockup {
+    pub unix_timestamp: i64,
+    pub epoch: u64,
+    pub custodian: Pubkey,
+}
+
+/// Read-only view of a stake pool account owned by the SPL Stake Pool program.
+#[derive(AnchorDeserialize, Debug, Clone)]
+pub struct StakePool {
+    pub account_type: StakePoolAccountType,
+    pub manager: Pubkey,
+    pub staker: Pubkey,
+    pub stake_deposit_authority: Pubkey,
+    pub stake_withdraw_bump_seed: u8,
+    pub validator_list: Pubkey,
+    pub reserve_stake: Pubkey,
+    pub pool_mint: Pubkey,
+    pub manager_fee_account: Pubkey,
+    pub token_program_id: Pubkey,
+    pub total_lamports: u64,
+    pub pool_token_supply: u64,
+    pub last_update_epoch: u64,
+    pub lockup: Lockup,
+    pub epoch_fee: Fee,
+    pub next_epoch_fee: FutureEpoch<Fee>,
+    pub preferred_deposit_validator_vote_address: Option<Pubkey>,
+    pub preferred_withdraw_validator_vote_address: Option<Pubkey>,
+    pub stake_deposit_fee: Fee,
+    pub stake_withdrawal_fee: Fee,
+    pub next_stake_withdrawal_fee: FutureEpoch<Fee>,
+    pub stake_referral_fee: u8,
+    pub sol_deposit_authority: Option<Pubkey>,
+    pub sol_deposit_fee: Fee,
+    pub sol_referral_fee: u8,
+    pub sol_withdraw_authority: Option<Pubkey>,
+    pub sol_withdrawal_fee: Fee,
+    pub next_sol_withdrawal_fee: FutureEpoch<Fee>,
+    pub last_epoch_pool_token_supply: u64,
+    pub last_epoch_total_lamports: u64,
+}
+
+impl StakePool {
+    /// Deserializes a stake pool from an account owned by the Stake Pool program.
+    pub fn from_account_info(account: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(
+            *account.owner,
+            STAKE_POOL_PROGRAM_ID,
+            ErrorCode::InvalidStakePool
+        );
+        let data = account.try_borrow_data()?;
+        let stake_pool =
+            Self::deserialize(&mut &data[..]).map_err(|_| error!(ErrorCode::InvalidStakePool))?;
+        require!(
+            stake_pool.account_type == StakePoolAccountType::StakePool,
+            ErrorCode::InvalidStakePool
+        );
+        Ok(stake_pool)
+    }
+
+    /// Returns the pool tokens minted for depositing `lamports`, before fees.
+    pub fn calc_pool_tokens_for_deposit(&self, lamports: u64) -> Option<u64> {
+        if self.total_lamports == 0 || self.pool_token_supply == 0 {
+            return Some(lamports);
+        }
+        u64::try_from(
+            u128::from(lamports)
+                .checked_mul(u128::from(self.pool_token_supply))?
+                .checked_div(u128::from(self.total_lamports))?,
+        )
+        .ok()
+    }
+
+    /// Returns the pool tokens received by the depositor of `lamports` via `DepositSol`.
+    pub fn calc_pool_tokens_for_sol_deposit(&self, lamports: u64) -> Option<u64> {
+        let new_pool_tokens = self.calc_pool_tokens_for_deposit(lamports)?;
+        let deposit_fee = u64::try_from(self.sol_deposit_fee.apply(new_pool_tokens)?).ok()?;
+        new_pool_tokens.checked_sub(deposit_fee)
+    }
+
+    /// Returns the pool tokens received by the depositor of a stake account of `lamports` via `DepositStake`.
+    pub fn calc_pool_tokens_for_stake_deposit(&self, lamports: u64) -> Option<u64> {
+        let new_pool_tokens = self.calc_pool_tokens_for_deposit(lamports)?;
+        let deposit_fee = u64::try_from(self.stake_deposit_fee.apply(new_pool_tokens)?).ok()?;
+        new_pool_tokens.checked_sub(deposit_fee)
+    }
+
+    /// Returns the lamports received for burning `pool_tokens` via `WithdrawSol`.
+    pub fn calc_lamports_for_sol_withdrawal(&self, pool_tokens: u64) -> Option<u64> {
+        let withdrawal_fee = u64::try_from(self.sol_withdrawal_fee.apply(pool_tokens)?).ok()?;
+        let pool_tokens_burnt = pool_tokens.checked_sub(withdrawal_fee)?;
+        let numerator =
+            u128::from(pool_tokens_burnt).checked_mul(u128::from(self.total_lamports))?;
+        let denominator = u128::from(self.pool_token_supply);
+        if numerator < denominator || denominator == 0 {
+            return Some(0);
+        }
+        u64::try_from(numerator.checked_div(denominator)?).ok()
+    }
+
+    /// Returns the fewest pool tokens, up to `max_pool_tokens`, that receive at least `lamports` via `WithdrawSol`,
+    /// or `None` if `max_pool_tokens` do not.
+    pub fn calc_pool_tokens_for_sol_withdrawal(
+        &self,
+        lamports: u64,
+        max_pool_tokens: u64,
+    ) -> Option<u64> {
+        if self.calc_lamports_for_sol_withdrawal(max_pool_tokens)? < lamports {
+            return None;
+        }
+        // the lamports received never decrease with the pool tokens burnt, so search for the smallest amount
+        let (mut low, mut high) = (0, max_pool_tokens);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.calc_lamports_for_sol_withdrawal(mid)? >= lamports {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Some(low)
+    }
+
+    /// Returns the withdraw authority PDA of the stake pool at `stake_pool_address`.
+    pub fn withdraw_authority(&self, stake_pool_address: &Pubkey) -> Result<Pubkey> {
+        Pubkey::create_program_address(
+            &[
+                stake_pool_address.as_ref(),
+                b"withdraw",
+                &[self.stake_withdraw_bump_seed],
+            ],
+            &STAKE_POOL_PROGRAM_ID,
+        )
+        .map_err(|_| error!(ErrorCode::InvalidWithdrawAuthority))
+    }
+}
+
+/// Mirrors the `StakeStatus` enum of the SPL Stake Pool program.
+#[derive(AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidatorStakeStatus {
+    Active,
+    DeactivatingTransient,
+    ReadyForRemoval,
+    DeactivatingValidator,
+    DeactivatingAll,
+}
+
+/// Mirrors the `ValidatorStakeInfo` struct of the SPL Stake Pool program.
+#[derive(AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidatorStakeInfo {
+    pub active_stake_lamports: u64,
+    pub transient_stake_lamports: u64,
+    pub last_update_epoch: u64,
+    pub transient_seed_suffix: u64,
+    pub unused: u32,
+    pub validator_seed_suffix: u32,
+    pub status: ValidatorStakeStatus,
+    pub vote_account_address: Pubkey,
+}
+
+impl ValidatorStakeInfo {
+    /// Serialized size of a validator list entry.
+    pub const LEN: usize = 73;
+    /// Offset of the vote account address within a validator list entry.
+    const VOTE_ACCOUNT_OFFSET: usize = 41;
+}
+
+/// Read-only view of a validator list account owned by the SPL Stake Pool program.
+pub struct ValidatorList;
+
+impl ValidatorList {
+    /// Size of the account type and max validators header.
+    const HEADER_LEN: usize = 5;
+
+    /// Returns the entry for `vote_account_address` without deserializing the whole list.
+    pub fn find_validator(
+        account: &AccountInfo,
+        vote_account_address: &Pubkey,
+    ) -> Result<Option<ValidatorStakeInfo>> {
+        require_keys_eq!(
+            *account.owner,
+            STAKE_POOL_PROGRAM_ID,
+            ErrorCode::InvalidValidatorList
+        );
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= Self::HEADER_LEN + 4
+                && data[0] == StakePoolAccountType::ValidatorList as u8,
+            ErrorCode::InvalidValidatorList
+        );
+        let len_bytes = &data[Self::HEADER_LEN..Self::HEADER_LEN + 4];
+        let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        let entries = &data[Self::HEADER_LEN + 4..];
+        require!(
+            entries.len() >= len * ValidatorStakeInfo::LEN,
+            ErrorCode::InvalidValidatorList
+        );
+
+        entries
+            .chunks_exact(ValidatorStakeInfo::LEN)
+            .take(len)
+            .find(|entry| {
+                &entry[ValidatorStakeInfo::VOTE_ACCOUNT_OFFSET..] == vote_account_address.as_ref()
+            })
+            .map(|mut entry| {
+                ValidatorStakeInfo::deserialize(&mut entry)
+                    .map_err(|_| error!(ErrorCode::InvalidValidatorList))
+            })
+            .transpose()
+    }
+}
+
+/// Returns the address of the transient stake account of a validator in the stake pool.
+pub fn find_transient_stake_address(
+    vote_account_address: &Pubkey,
+    stake_pool_address: &Pubkey,
+    seed: u64,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"transient",
+            vote_account_address.as_ref(),
+            stake_pool_address.as_ref(),
+            &seed.to_le_bytes(),
+        ],
+        &STAKE_POOL_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Returns the address of the ephemeral stake account of the stake pool.
+pub fn find_ephemeral_stake_address(stake_pool_address: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"ephemeral",
+            stake_pool_address.as_ref(),
+            &seed.to_le_bytes(),
+        ],
+        &STAKE_POOL_PROGRAM_ID,
+    )
+    .0
+}
@@ -1,22 +1,127 @@
+use crate::constants::{ACCESS_RESERVED_SPACE, MAX_GUARDIANS};
 use anchor_lang::prelude::*;
 
 #[account]
 #[derive(InitSpace)]
 pub struct Access {
+    pub version: u8,
     pub owner: Pubkey,
     pub stake_manager: Pubkey,
-    pub is_paused: bool,
+    pub paused_flags: u8,
     pub pending_owner: Option<Pubkey>,
+    pub stake_pool: Pubkey,
+    pub pool_mint: Pubkey,
+    pub reserve_stake: Pubkey,
+    pub validator_list: Pubkey,
+    pub manager_fee_account: Pubkey,
+    pub withdraw_authority: Pubkey,
+    pub max_total_lamports: Option<u64>,
+    pub max_epoch_deposit_lamports: Option<u64>,
+    pub deposit_epoch: u64,
+    pub epoch_deposit_lamports: u64,
+    pub whitelist_merkle_root: Option<[u8; 32]>,
+    pub attester: Option<Pubkey>,
+    pub active_agents: u32,
+    pub max_agents: Option<u32>,
+    pub rent_recipient: Option<Pubkey>,
+    pub timelock_delay: i64,
+    pub next_action_id: u64,
+    #[max_len(MAX_GUARDIANS)]
+    pub guardians: Vec<Pubkey>,
+    pub pending_owner_deadline: Option<u64>,
+    pub reserved: [u8; ACCESS_RESERVED_SPACE],
+}
+
+impl Access {
+    /// Returns whether any of the given operations is paused
+    pub fn is_paused(&self, flags: u8) -> bool {
+        self.paused_flags & flags != 0
+    }
+
+    /// Returns whether the key is the owner or one of the guardians
+    pub fn is_owner_or_guardian(&self, key: &Pubkey) -> bool {
+        self.owner == *key || self.guardians.contains(key)
+    }
 }
 
 #[account]
 #[derive(InitSpace)]
-pub struct Agent {}
+pub struct Agent {
+    pub permissions: u8,
+    pub created_by: Pubkey,
+}
+
+impl Agent {
+    /// Returns whether the agent holds all of the given permission bits
+    pub fn has_permissions(&self, permissions: u8) -> bool {
+        self.permissions & permissions == permissions
+    }
+}
 
 #[account]
 #[derive(InitSpace)]
 pub struct UserStatus {
     pub status: WhitelistUserStatus,
+    pub whitelist_expiry: Option<i64>,
+    pub deposit_limits: DepositLimits,
+    pub total_deposited_lamports: u64,
+    pub deposit_epoch: u64,
+    pub epoch_deposited_lamports: u64,
+    pub status_change_reason: Option<StatusChangeReason>,
+    pub case_reference: [u8; 32],
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, Default, InitSpace, PartialEq, Eq,
+)]
+pub struct DepositLimits {
+    pub max_lifetime_lamports: Option<u64>,
+    pub max_epoch_lamports: Option<u64>,
+}
+
+impl DepositLimits {
+    /// Returns the tighter of both limits for each window, where no limit is the loosest
+    pub fn tighter(&self, other: &DepositLimits) -> DepositLimits {
+        let tighter = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        DepositLimits {
+            max_lifetime_lamports: tighter(self.max_lifetime_lamports, other.max_lifetime_lamports),
+            max_epoch_lamports: tighter(self.max_epoch_lamports, other.max_epoch_lamports),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct WhitelistProof {
+    pub deposit_limits: DepositLimits,
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct KycAttestation {
+    /// The Staker program the attestation is issued for
+    pub program_id: Pubkey,
+    /// The Staker's `Access` PDA, binding the attestation to one deployment
+    pub access: Pubkey,
+    pub user: Pubkey,
+    pub expiry: i64,
+    pub deposit_limits: DepositLimits,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct UserStatusUpdate {
+    pub user: Pubkey,
+    pub status: WhitelistUserStatus,
+    /// The deposit limits of a whitelisted user, ignored for other statuses
+    pub deposit_limits: DepositLimits,
+    /// The whitelist expiry of a whitelisted user, ignored for other statuses
+    pub expiry: Option<i64>,
+    /// The reason for blacklisting or clearing a user, required for those statuses
+    pub reason: Option<StatusChangeReason>,
+    /// The compliance case reference for blacklisting or clearing a user, required for those statuses
+    pub case_reference: Option<[u8; 32]>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, InitSpace, PartialEq, Eq)]
@@ -26,6 +131,77 @@ pub enum WhitelistUserStatus {
     Blacklisted,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, InitSpace, PartialEq, Eq)]
+pub enum StatusChangeReason {
+    Sanctions,
+    Fraud,
+    CourtOrder,
+    UserRequest,
+    Other,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct StakeManager {}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PendingAction {
+    pub id: u64,
+    pub action: TimelockedAction,
+    pub execute_after: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, InitSpace, PartialEq, Eq)]
+pub enum TimelockedAction {
+    SetStakeManager {
+        new_stake_manager: Pubkey,
+    },
+    AddValidator {
+        validator_vote_account: Pubkey,
+        validator_seed: u32,
+    },
+    RemoveValidator {
+        validator_stake_account: Pubkey,
+    },
+    Unpause {
+        flags: u8,
+    },
+    SetPendingOwner {
+        pending_owner: Pubkey,
+        deadline: Option<u64>,
+    },
+    SetTimelockDelay {
+        delay: i64,
+    },
+    SetStakePool {
+        stake_pool: Pubkey,
+    },
+}
+
+impl TimelockedAction {
+    /// Returns whether guardians can cancel the action. Guardians cannot cancel unpausing or
+    /// timelock delay changes, so that they cannot keep the contract paused or the delay unchanged.
+    pub fn is_guardian_cancellable(&self) -> bool {
+        !matches!(
+            self,
+            TimelockedAction::Unpause { .. } | TimelockedAction::SetTimelockDelay { .. }
+        )
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UnstakeQueue {
+    pub pending_lamports: u64,
+    pub funded_lamports: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UnstakeTicket {
+    pub user: Pubkey,
+    pub pool_tokens: u64,
+    pub lamports: u64,
+    pub claimable_epoch: u64,
+}
@@ -20,14 +20,17 @@ anchor.setProvider(provider);
 
 
 // A script to add a new agent
-// usage: yarn add-agent <new_agent_address>
+// usage: yarn add-agent <new_agent_address> <permissions>
 async function main() {
 
   // parse arguments
+  const usage = "Usage: yarn add-agent <new_agent_address> <permissions>\n" +
+    "permissions is the sum of 1 (whitelist), 2 (blacklist), 4 (clear) and 8 (manage agents)";
   const args = process.argv.slice(2);
-  const newAgent = args.length === 1 && new PublicKey(args[0])
-  if (!newAgent) {
-    console.error("Usage: yarn add-agent <new_agent_address>");
+  const newAgent = args.length === 2 && new PublicKey(args[0])
+  const permissions = Number(args[1]);
+  if (!newAgent || !Number.isInteger(permissions) || permissions < 1 || permissions > 15) {
+    console.error(usage);
     process.exit(1);
   }
 
@@ -35,9 +38,9 @@ async function main() {
   const program = await Program.at(staker_program_id, provider);
 
   // Add a new agent
-  console.log(`Adding agent ${newAgent.toBase58()}...`);
+  console.log(`Adding agent ${newAgent.toBase58()} with permissions ${permissions}...`);
   const addAgentIx = await program.methods
-    .addAgent(newAgent)
+    .addAgent(newAgent, permissions)
     .accounts({
       signer: owner_keypair.publicKey,
     })
@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
 import { Keypair, PublicKey } from "@solana/web3.js";
 import * as fs from "fs";
-import { getStakePool } from "../tests/helpers";
+import { getStakePool, queueAction } from "../tests/helpers";
 import { getConnection, getStakePoolProgramId, getStakerProgramId, getStakePoolAccount } from "./utils";
 
 // Get the Solana connection
@@ -22,15 +22,16 @@ const owner_keypair = Keypair.fromSecretKey(
 const provider = new AnchorProvider(connection, new Wallet(owner_keypair), { commitment: "confirmed" });
 anchor.setProvider(provider);
 
-// A script to add a validator to the stake pool
-// usage: yarn add-validator <validator_vote_account>
+// A script to add a validator to the stake pool.
+// Without a pending action, queues the timelocked action and prints its pending action account.
+// usage: yarn add-validator <validator_vote_account> [pending_action]
 async function main() {
 
   // get the address of the validator vote account to add
   const args = process.argv.slice(2);
   const validatorVoteAccount = new PublicKey(args[0])
   if (!validatorVoteAccount) {
-    console.error("Usage: yarn add-validator <validator_vote_account>");
+    console.error("Usage: yarn add-validator <validator_vote_account> [pending_action]");
     process.exit(1);
   }
 
@@ -53,9 +54,18 @@ async function main() {
   const program = await Program.at(staker_program_id, provider);
 
   const seed = 0;
+  if (!args[1]) {
+    const pendingAction = await queueAction(program as any, {
+      addValidator: { validatorVoteAccount, validatorSeed: seed },
+    });
+    console.log("AddValidator queued, pending action:", pendingAction.toBase58());
+    return;
+  }
+
   const tx = await program.methods
     .addValidator(seed)
     .accounts({
+      pendingAction: new PublicKey(args[1]),
       stakePool: stake_pool_account,
       reserveStake: stakePool.reserveStake,
       withdrawAuthority: poolWithdrawAuthority,
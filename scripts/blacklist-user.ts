@@ -2,7 +2,13 @@ import * as anchor from "@coral-xyz/anchor";
 import * as fs from "fs";
 import { Keypair, PublicKey, Transaction } from "@solana/web3.js";
 import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
-import { getConnection, getStakerProgramId } from "./utils";
+import {
+  getConnection,
+  getStakerProgramId,
+  parseCaseReference,
+  parseStatusChangeReason,
+  STATUS_CHANGE_REASON_NAMES,
+} from "./utils";
 
 
 // Get the Solana connection
@@ -24,22 +30,25 @@ const provider = new AnchorProvider(
 );
 anchor.setProvider(provider);
 
-// A script to blacklist a user
-// usage: yarn blacklist-user <user_address>
+// A script to blacklist a user, recording the reason and a case reference of up to 32 bytes
+// usage: yarn blacklist-user <user_address> <reason> <case_reference>
 async function main() {
 
   // parse argumets
+  const usage = `Usage: yarn blacklist-user <user_address> <${STATUS_CHANGE_REASON_NAMES}> <case_reference>`;
   const args = process.argv.slice(2);
-  const user = args.length === 1 && new PublicKey(args[0])
-  if (!user) {
-    console.error("Usage: yarn blacklist-user <user_address>");
+  const user = args.length === 3 && new PublicKey(args[0])
+  const reason = user && parseStatusChangeReason(args[1]);
+  const caseReference = reason && parseCaseReference(args[2]);
+  if (!caseReference) {
+    console.error(usage);
     process.exit(1);
   }
 
   // Add user to blacklist
   const program = await Program.at(staker_program_id, provider);
   const blacklistIx = await program.methods
-    .addUserToBlacklist(user)
+    .addUserToBlacklist(user, reason, caseReference)
     .accounts({
       signer: owner_keypair.publicKey,
     })
@@ -0,0 +1,68 @@
+import * as anchor from "@coral-xyz/anchor";
+import * as fs from "fs";
+import { Keypair, PublicKey, Transaction } from "@solana/web3.js";
+import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
+import {
+  getConnection,
+  getStakerProgramId,
+  parseCaseReference,
+  parseStatusChangeReason,
+  STATUS_CHANGE_REASON_NAMES,
+} from "./utils";
+
+
+// Get the Solana connection
+const connection = getConnection();
+
+// get config variables
+const staker_program_id = new PublicKey(getStakerProgramId());
+
+// load the owner keypair
+const owner_keypair = Keypair.fromSecretKey(
+  Uint8Array.from(JSON.parse(fs.readFileSync(`${process.cwd()}/accounts/owner.json`, "utf-8"))) // Replace with your keypair file
+);
+
+// configure the Anchor provider
+const provider = new AnchorProvider(
+  connection,
+  new Wallet(owner_keypair), 
+  { commitment: "confirmed" }
+);
+anchor.setProvider(provider);
+
+// A script to clear a user's whitelist status, recording the reason and a case reference of up to 32 bytes
+// usage: yarn clear-user-status <user_address> <reason> <case_reference>
+async function main() {
+
+  // parse arguments
+  const usage = `Usage: yarn clear-user-status <user_address> <${STATUS_CHANGE_REASON_NAMES}> <case_reference>`;
+  const args = process.argv.slice(2);
+  const user = args.length === 3 && new PublicKey(args[0])
+  const reason = user && parseStatusChangeReason(args[1]);
+  const caseReference = reason && parseCaseReference(args[2]);
+  if (!caseReference) {
+    console.error(usage);
+    process.exit(1);
+  }
+
+  // Clear the user's status
+  const program = await Program.at(staker_program_id, provider);
+  const clearIx = await program.methods
+    .clearUserStatus(user, reason, caseReference)
+    .accounts({
+      signer: owner_keypair.publicKey,
+    })
+    .signers([owner_keypair])
+    .instruction();
+
+  const clearTx = new Transaction().add(clearIx);
+  const tx = await provider.sendAndConfirm(clearTx, [owner_keypair]);
+
+  console.log("Clear user status tx:", tx);
+}
+
+// Run the main function
+main().catch((error) => {
+    console.error("Unexpected error:", error);
+    process.exit(1);
+});
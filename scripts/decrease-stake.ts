@@ -96,7 +96,7 @@ async function main() {
   // stake_maanger calls decrease_validator_stake
   const program = await Program.at(staker_program_id, provider);
   const tx = await program.methods
-    .decreaseValidatorStake(new BN(decreaseStakeAmount))
+    .decreaseValidatorStake(new BN(decreaseStakeAmount), new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
     .accounts({
       signer: stake_manager_keypair.publicKey,
       validatorVoteAccount: validatorVoteAccount,
@@ -142,7 +142,7 @@ async function main() {
   );
 
   // deposit to specific validator instruction
-  const depositIx = await program.methods.depositToSpecificValidator(depositAmount)
+  const depositIx = await program.methods.depositToSpecificValidator(depositAmount, new BN(transientStakeSeed), new BN(ephemeralStakeSeed), null)
     .accounts({
       user: user.publicKey,
       stakePool: stake_pool_account,
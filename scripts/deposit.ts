@@ -99,7 +99,7 @@ async function main() {
   }
 
   // deposit instruction
-  const depositIx = await program.methods.deposit(depositAmount)
+  const depositIx = await program.methods.deposit(depositAmount, null, null)
     .accounts({
       user: user.publicKey,
       userWhitelistAccount: userWhitelistPDA,
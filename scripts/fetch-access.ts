@@ -33,7 +33,7 @@ async function main() {
     console.log("owner:", access.owner.toBase58());
     console.log("pending_owner:", access.pendingOwner ? access.pendingOwner.toBase58() : "none");
     console.log("stake_manager:", access.stakeManager.toBase58());
-    console.log("is_paused:", access.isPaused);
+    console.log("paused_flags:", access.pausedFlags);
 }
 
 // Run the main function
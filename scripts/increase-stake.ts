@@ -95,7 +95,7 @@ async function main() {
   // stake_maanger calls increase_validator_stake
   const program = await Program.at(staker_program_id, provider);
   const tx = await program.methods
-    .increaseValidatorStake(new BN(increaseStakeAmount))
+    .increaseValidatorStake(new BN(increaseStakeAmount), new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
     .accounts({
       signer: stake_manager_keypair.publicKey,
       validatorVoteAccount: validatorVoteAccount,
@@ -3,13 +3,14 @@ import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
 import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
 import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
 import * as fs from "fs";
-import { getConnection, getStakerProgramId } from "./utils";
+import { getConnection, getStakePoolAccount, getStakerProgramId } from "./utils";
 
 // Get the Solana connection
 const connection = getConnection();
 
 // get config variables
 const staker_program_id = new PublicKey(getStakerProgramId());
+const stake_pool = new PublicKey(getStakePoolAccount());
 
 const owner_keypair = Keypair.fromSecretKey(
   Uint8Array.from(JSON.parse(fs.readFileSync(`${process.cwd()}/accounts/owner.json`, "utf-8")))
@@ -24,7 +25,7 @@ const provider = new AnchorProvider(
 anchor.setProvider(provider);
 
 
-// A script to initialize the Staker contract
+// A script to initialize the Staker contract bound to the STAKE_POOL_ACCOUNT of the environment
 // usage: yarn init-staker
 async function main() {
   // derive PDAs
@@ -34,7 +35,7 @@ async function main() {
     staker_program_id
   );
 
-  // Initialize the Staker setting the owner as the staker manager authority
+  // Initialize the Staker setting the owner as the staker manager authority and binding the stake pool
   const program = await Program.at(staker_program_id, provider);
   const txHash = await program.methods
     .initializeStaker()
@@ -44,6 +45,7 @@ async function main() {
       ownerAgentAccount: ownerAgentPDA,
       ownerInfo: owner_keypair.publicKey,
       stakeManagerInfo: owner_keypair.publicKey,
+      stakePool: stake_pool,
       tokenProgram: TOKEN_2022_PROGRAM_ID,
       systemProgram: SystemProgram.programId,
     })
@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { AnchorProvider, BN, Program, Wallet } from "@coral-xyz/anchor";
 import { Keypair, PublicKey } from "@solana/web3.js";
 import * as fs from "fs";
-import { getStakePool } from "../tests/helpers";
+import { getStakePool, queueAction } from "../tests/helpers";
 import { getConnection, getStakePoolProgramId, getStakerProgramId, getStakePoolAccount } from "./utils";
 
 // Get the Solana connection
@@ -26,15 +26,16 @@ const provider = new AnchorProvider(
 anchor.setProvider(provider);
 
 
-// A script to remove a validator stake account from the stake pool
-// usage: yarn remove-validator <validator_vote_account>
+// A script to remove a validator stake account from the stake pool.
+// Without a pending action, queues the timelocked action and prints its pending action account.
+// usage: yarn remove-validator <validator_vote_account> [pending_action]
 async function main() {
 
   // get the address of the validator vote account to remove
   const args = process.argv.slice(2);
-  const validatorVoteAccount = args.length === 1 && new PublicKey(args[0])
-  if (args.length !== 1 || !validatorVoteAccount) {
-    console.error("Usage: yarn remove-validator <validator_vote_account>");
+  const validatorVoteAccount = args.length >= 1 && new PublicKey(args[0])
+  if (args.length > 2 || !validatorVoteAccount) {
+    console.error("Usage: yarn remove-validator <validator_vote_account> [pending_action]");
     process.exit(1);
   }
 
@@ -68,9 +69,16 @@ async function main() {
   // owner calls remove_validator
   const program = await Program.at(staker_program_id, provider);
 
+  if (!args[1]) {
+    const pendingAction = await queueAction(program as any, { removeValidator: { validatorStakeAccount } });
+    console.log("RemoveValidator queued, pending action:", pendingAction.toBase58());
+    return;
+  }
+
   const tx = await program.methods
       .removeValidator()
       .accounts({
+        pendingAction: new PublicKey(args[1]),
         stakePool: stake_pool_account,
         withdrawAuthority: poolWithdrawAuthority,
         validatorList: stakePool.validatorList,
@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
 import { Keypair, PublicKey, Transaction } from "@solana/web3.js";
 import * as fs from "fs";
+import { queueAction } from "../tests/helpers";
 import { getConnection, getStakerProgramId } from "./utils";
 
 // get config variables
@@ -19,22 +20,32 @@ anchor.setProvider(provider);
 
 
 // A script to set the new pending owner of the staker program.
-// usage: yarn set-pending-owner <new_pending_manager>
+// Without a pending action, queues the timelocked action and prints its pending action account.
+// usage: yarn set-pending-owner <new_pending_owner> [pending_action]
 async function main() {
 
   // parse arguments
   const args = process.argv.slice(2);
 
-  const new_pending_owner = args.length === 1 && new PublicKey(args[0]);
+  const new_pending_owner = args.length >= 1 && args.length <= 2 && new PublicKey(args[0]);
   if (!new_pending_owner) {
-    console.error("Usage: yarn set-pending-owner <new_pending_owner>");
+    console.error("Usage: yarn set-pending-owner <new_pending_owner> [pending_action]");
     process.exit(1);
   }
 
-  // build set_pending_owner instruction
   const program = await Program.at(staker_program_id, provider);
+  if (!args[1]) {
+    const pendingAction = await queueAction(program as any, {
+      setPendingOwner: { pendingOwner: new_pending_owner, deadline: null },
+    });
+    console.log("SetPendingOwner queued, pending action:", pendingAction.toBase58());
+    return;
+  }
+
+  // build set_pending_owner instruction
   const setPendingOwnerIx = await program.methods
-    .setPendingOwner(new_pending_owner)
+    .setPendingOwner(new_pending_owner, null)
+    .accounts({ pendingAction: new PublicKey(args[1]) })
     .signers([owner_keypair])
     .instruction();
 
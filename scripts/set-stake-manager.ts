@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
 import { Keypair, PublicKey } from "@solana/web3.js";
 import * as fs from "fs";
+import { queueAction } from "../tests/helpers";
 import { getConnection, getStakerProgramId } from "./utils";
 
 // Get the Solana connection
@@ -25,21 +26,22 @@ anchor.setProvider(provider);
 
 // A script to set the stake manager authority of the staker program.
 // This is the account able to increase and decrease validator stake.
-// usage: yarn set-stake-manager <old_stake_manager> <new_stake_manager>
+// Without a pending action, queues the timelocked action and prints its pending action account.
+// usage: yarn set-stake-manager <old_stake_manager> <new_stake_manager> [pending_action]
 async function main() {
 
   // parse arguments
   const args = process.argv.slice(2);
 
-  const old_stake_manager = args.length === 2 && new PublicKey(args[0]);
+  const old_stake_manager = args.length >= 2 && new PublicKey(args[0]);
   if (!old_stake_manager) {
-    console.error("Usage: yarn set-stake-manager <old_stake_manager> <new_stake_manager>");
+    console.error("Usage: yarn set-stake-manager <old_stake_manager> <new_stake_manager> [pending_action]");
     process.exit(1);
   }
 
-  const new_stake_manager = args.length === 2 && new PublicKey(args[1]);
+  const new_stake_manager = args.length >= 2 && new PublicKey(args[1]);
   if (!new_stake_manager) {
-    console.error("Usage: yarn set-stake-manager <old_stake_manager> <new_stake_manager>");
+    console.error("Usage: yarn set-stake-manager <old_stake_manager> <new_stake_manager> [pending_action]");
     process.exit(1);
   }
 
@@ -57,10 +59,17 @@ async function main() {
 
   // call set_stake_manager
   const program = await Program.at(staker_program_id, provider);
+  if (!args[2]) {
+    const pendingAction = await queueAction(program as any, { setStakeManager: { newStakeManager: new_stake_manager } });
+    console.log("SetStakeManager queued, pending action:", pendingAction.toBase58());
+    return;
+  }
+
   const tx = await program.methods
     .setStakeManager()
     .accounts({
-      signer: owner_keypair.publicKey,
+      owner: owner_keypair.publicKey,
+      pendingAction: new PublicKey(args[2]),
       newStakeManager: new_stake_manager,
       oldStakeManager: old_stake_manager,
     })
@@ -0,0 +1,65 @@
+import * as anchor from "@coral-xyz/anchor";
+import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import * as fs from "fs";
+import { queueAction } from "../tests/helpers";
+import { getConnection, getStakePoolAccount, getStakerProgramId } from "./utils";
+
+// Get the Solana connection
+const connection = getConnection();
+
+// get config variables
+const staker_program_id = new PublicKey(getStakerProgramId());
+const stake_pool = new PublicKey(getStakePoolAccount());
+
+// load the owner keypair
+const owner_keypair = Keypair.fromSecretKey(
+  Uint8Array.from(JSON.parse(fs.readFileSync(`${process.cwd()}/accounts/owner.json`, "utf-8")))
+);
+
+// configure the Anchor provider
+const provider = new AnchorProvider(
+  connection,
+  new Wallet(owner_keypair),
+  { commitment: "confirmed" }
+);
+anchor.setProvider(provider);
+
+// A script to set the stake pool of the staker program to the STAKE_POOL_ACCOUNT of the environment.
+// Without a pending action, queues the timelocked action and prints its pending action account.
+// usage: yarn set-stake-pool [pending_action]
+async function main() {
+
+  // parse arguments
+  const args = process.argv.slice(2);
+  if (args.length > 1) {
+    console.error("Usage: yarn set-stake-pool [pending_action]");
+    process.exit(1);
+  }
+
+  // call set_stake_pool
+  const program = await Program.at(staker_program_id, provider);
+  if (!args[0]) {
+    const pendingAction = await queueAction(program as any, { setStakePool: { stakePool: stake_pool } });
+    console.log("SetStakePool queued, pending action:", pendingAction.toBase58());
+    return;
+  }
+
+  const tx = await program.methods
+    .setStakePool()
+    .accounts({
+      owner: owner_keypair.publicKey,
+      pendingAction: new PublicKey(args[0]),
+      stakePool: stake_pool,
+    })
+    .signers([owner_keypair])
+    .rpc();
+
+  console.log("SetStakePool tx:", tx);
+}
+
+// Run the main function
+main().catch((error) => {
+    console.error("Unexpected error:", error);
+    process.exit(1);
+});
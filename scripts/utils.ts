@@ -32,4 +32,28 @@ export const getStakePoolAccount = () => {
       throw new Error('STAKE_POOL_ACCOUNT not set in environment');
   }
   return process.env.STAKE_POOL_ACCOUNT;
-}  
+}
+
+// the reasons for blacklisting or clearing a user, keyed by their command line name
+const STATUS_CHANGE_REASONS: { [name: string]: object } = {
+  "sanctions": { sanctions: {} },
+  "fraud": { fraud: {} },
+  "court-order": { courtOrder: {} },
+  "user-request": { userRequest: {} },
+  "other": { other: {} },
+};
+export const STATUS_CHANGE_REASON_NAMES = Object.keys(STATUS_CHANGE_REASONS).join("|");
+
+// returns the status change reason with the given command line name, or null if it is unknown
+export const parseStatusChangeReason = (name: string): object | null => {
+  return STATUS_CHANGE_REASONS[name] ?? null;
+}
+
+// returns the 32 bytes case reference encoding the given text, or null if it is too long
+export const parseCaseReference = (text: string): number[] | null => {
+  const bytes = Buffer.from(text, "utf-8");
+  if (bytes.length > 32) {
+    return null;
+  }
+  return Array.from(Buffer.concat([bytes, Buffer.alloc(32 - bytes.length)]));
+}
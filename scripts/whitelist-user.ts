@@ -1,6 +1,6 @@
 import * as anchor from "@coral-xyz/anchor";
-import { AnchorProvider, Program, Wallet } from "@coral-xyz/anchor";
-import { Keypair, PublicKey } from "@solana/web3.js";
+import { AnchorProvider, BN, Program, Wallet } from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 import * as fs from "fs";
 import { getConnection, getStakerProgramId } from "./utils";
 
@@ -23,22 +23,42 @@ const provider = new AnchorProvider(
 );
 anchor.setProvider(provider);
 
-// A script to whitelist a user
-// usage: yarn whitelist-user <user_address>
+// A script to whitelist a user, optionally with deposit limits in SOL and an expiry as a unix timestamp.
+// Pass "-" to leave a limit or the expiry unset.
+// usage: yarn whitelist-user <user_address> [max_lifetime_sol] [max_epoch_sol] [expiry]
 async function main() {
 
   // parse arguments
+  const usage = "Usage: yarn whitelist-user <user_address> [max_lifetime_sol] [max_epoch_sol] [expiry]";
   const args = process.argv.slice(2);
-  const user = args.length === 1 && new PublicKey(args[0])
+  const user = args.length >= 1 && args.length <= 4 && new PublicKey(args[0])
   if (!user) {
-    console.error("Usage: yarn whitelist-user <user_address>");
+    console.error(usage);
     process.exit(1);
   }
 
+  const optionalArg = (index: number, toBN: (value: number) => BN): BN | null => {
+    const value = args[index];
+    if (value === undefined || value === "-") {
+      return null;
+    }
+    if (isNaN(Number(value))) {
+      console.error(usage);
+      process.exit(1);
+    }
+    return toBN(Number(value));
+  };
+  const solToLamports = (sol: number) => new BN(Math.round(sol * LAMPORTS_PER_SOL));
+  const depositLimits = {
+    maxLifetimeLamports: optionalArg(1, solToLamports),
+    maxEpochLamports: optionalArg(2, solToLamports),
+  };
+  const expiry = optionalArg(3, (timestamp) => new BN(timestamp));
+
   // Add user to whitelist
   const program = await Program.at(staker_program_id, provider);
   const tx = await program.methods
-    .addUserToWhitelist(user)
+    .addUserToWhitelist(user, depositLimits, expiry)
     .accounts({
       signer: owner_keypair.publicKey,
     })
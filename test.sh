@@ -10,8 +10,8 @@ set -e
 export ANCHOR_PROVIDER_URL="http://127.0.0.1:8899"
 export ANCHOR_WALLET="$HOME/.config/solana/id.json"
 
-# Build the Staker program
-RUSTUP_TOOLCHAIN="nightly-2024-11-19" anchor build --provider.cluster devnet --program-name staker
+# Build the Staker program without the minimum timelock delay
+RUSTUP_TOOLCHAIN="nightly-2024-11-19" anchor build --provider.cluster devnet --program-name staker -- --features localnet
 
 # Output the program ID from the staker keypair
 PROGRAM_ID=`solana address -k accounts/staker-program.json`
@@ -0,0 +1,268 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  PublicKey,
+  StakeProgram,
+  Authorized,
+  LAMPORTS_PER_SOL,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { Staker } from "../target/types/staker";
+import {
+  STAKE_POOL_PROGRAM_ID,
+  initStaker,
+  requestAirdrop,
+  createStakePool,
+  addUserToWhitelist,
+  moveEpochForwardAndUpdatePool,
+  getEvent,
+  queueAction,
+} from "./helpers";
+import { CreateStakePoolResponse } from "./stake_pool/types";
+import {
+  getAssociatedTokenAddress,
+  createAssociatedTokenAccountInstruction,
+  getAccount,
+} from "@solana/spl-token";
+
+import { assert } from "chai";
+
+describe("deposit stake", () => {
+  const provider = anchor.AnchorProvider.env();
+  const connection = provider.connection;
+
+  let program: anchor.Program<Staker>;
+
+  let owner: anchor.Wallet;
+  let user: Keypair;
+  let manager: Keypair; // the manager authority of the stake pool program
+  let stakeManager: Keypair; // the stake manager authority of the staker program
+  let staker: Keypair; // the staker pool staker authority
+  let validatorVoteAccount: PublicKey;
+  let validatorStakeAccount: PublicKey;
+  let userPoolTokenATA: PublicKey;
+  let stakerAuthorityPDA: PublicKey;
+
+  let stakePoolInfo: CreateStakePoolResponse;
+
+  // creates a stake account owned by the user, optionally delegated to the validator
+  async function createUserStakeAccount(lamports: number, delegate: boolean): Promise<Keypair> {
+    const stakeAccount = Keypair.generate();
+    const tx = new Transaction().add(
+      StakeProgram.createAccount({
+        fromPubkey: user.publicKey,
+        stakePubkey: stakeAccount.publicKey,
+        authorized: new Authorized(user.publicKey, user.publicKey),
+        lamports,
+      })
+    );
+    if (delegate) {
+      tx.add(
+        StakeProgram.delegate({
+          stakePubkey: stakeAccount.publicKey,
+          authorizedPubkey: user.publicKey,
+          votePubkey: validatorVoteAccount,
+        })
+      );
+    }
+    await provider.sendAndConfirm(tx, [user, stakeAccount]);
+    return stakeAccount;
+  }
+
+  function depositStakeAccounts(stakeAccount: PublicKey) {
+    return {
+      user: user.publicKey,
+      stakePool: stakePoolInfo.accounts.stakePoolAccount,
+      validatorList: stakePoolInfo.accounts.validatorListAccount,
+      depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+      withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+      stakeAccount: stakeAccount,
+      validatorStakeAccount: validatorStakeAccount,
+      poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+      userPoolTokenAccount: userPoolTokenATA,
+      feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount, // Same as fee for simplicity
+      poolMint: stakePoolInfo.accounts.poolMintAccount,
+    };
+  }
+
+  before(async () => {
+    // get local validator account
+    const voteAccountsAll = await connection.getVoteAccounts();
+    let voteAccounts = voteAccountsAll?.current;
+    if (voteAccounts?.length == 0) {
+      throw new Error("No vote accounts found");
+    }
+    validatorVoteAccount = new anchor.web3.PublicKey(
+      voteAccounts[0].votePubkey
+    );
+
+    // create the required keypairs
+    owner = provider.wallet as anchor.Wallet;
+    user = Keypair.generate();
+    manager = Keypair.generate();
+    stakeManager = Keypair.generate();
+    staker = Keypair.generate();
+
+    program = anchor.workspace.Staker as anchor.Program<Staker>;
+
+    // derive the staker authority PDA
+    [stakerAuthorityPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("staker")],
+      program.programId
+    );
+
+    // create the stake pool with the manager and staker authorities
+    stakePoolInfo = await createStakePool(
+      program.programId,
+      manager, // Manager authority
+      staker // Staker authority
+    );
+
+    // initialize the staker bound to the new stake pool
+    program = await initStaker(provider.wallet.publicKey, stakeManager.publicKey, stakePoolInfo.accounts.stakePoolAccount);
+
+    // sets stakerAuthorityPDA as the new staker authority of the pool
+    const setStakerIx = new TransactionInstruction({
+      programId: STAKE_POOL_PROGRAM_ID,
+      keys: [
+        {
+          pubkey: stakePoolInfo.accounts.stakePoolAccount,
+          isSigner: false,
+          isWritable: true,
+        }, // Stake pool
+        { pubkey: manager.publicKey, isSigner: true, isWritable: false }, // Manager
+        { pubkey: stakerAuthorityPDA, isSigner: false, isWritable: false }, // The new pool staker authority
+      ],
+      data: Buffer.from(Uint8Array.of(13)), // SetStaker instruction index
+    });
+    await provider.sendAndConfirm(new Transaction().add(setStakerIx), [manager]);
+
+    // derive the validator stake account and add the validator to the pool
+    [validatorStakeAccount] = PublicKey.findProgramAddressSync(
+      [
+        validatorVoteAccount.toBuffer(),
+        stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+      ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    const validatorSeed = 0;
+    const pendingAction = await queueAction(program, { addValidator: { validatorVoteAccount, validatorSeed } });
+    await program.methods
+      .addValidator(validatorSeed)
+      .accounts({
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        reserveStake: stakePoolInfo.accounts.reserveStakeAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        validatorList: stakePoolInfo.accounts.validatorListAccount,
+        validatorStakeAccount: validatorStakeAccount,
+        validatorVoteAccount: validatorVoteAccount,
+        pendingAction,
+      })
+      .signers([owner.payer])
+      .rpc();
+
+    // airdrop some SOL to the user
+    await requestAirdrop(connection, user.publicKey, 100);
+
+    // the user creates an associated token account to hold the stake pool tokens
+    userPoolTokenATA = await getAssociatedTokenAddress(
+      stakePoolInfo.accounts.poolMintAccount,
+      user.publicKey
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          user.publicKey, // payer
+          userPoolTokenATA, // the associated token account for the stake pool token of the user
+          user.publicKey, // the user owning the new account
+          stakePoolInfo.accounts.poolMintAccount // the stake pool token mint
+        )
+      ),
+      [user]
+    );
+  });
+
+  it("Deposit stake for a non-whitelisted user should fail", async () => {
+    const stakeAccount = await createUserStakeAccount(10 * LAMPORTS_PER_SOL, true);
+    try {
+      await program.methods
+        .depositStake(null)
+        .accounts(depositStakeAccounts(stakeAccount.publicKey))
+        .signers([user])
+        .rpc();
+
+      throw new Error("Non-whitelisted user should not be able to deposit stake");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserNotWhitelisted");
+    }
+  });
+
+  it("Deposit an undelegated stake account should fail", async () => {
+    await addUserToWhitelist(program, user.publicKey);
+
+    const stakeAccount = await createUserStakeAccount(10 * LAMPORTS_PER_SOL, false);
+    try {
+      await program.methods
+        .depositStake(null)
+        .accounts(depositStakeAccounts(stakeAccount.publicKey))
+        .signers([user])
+        .rpc();
+
+      throw new Error("Deposit of an undelegated stake account should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "StakeAccountNotDelegated");
+    }
+  });
+
+  it("Deposit stake with a minimum pool tokens out above the expected output should fail", async () => {
+    const lamports = 10 * LAMPORTS_PER_SOL;
+    const stakeAccount = await createUserStakeAccount(lamports, true);
+    try {
+      await program.methods
+        .depositStake(new BN(2 * lamports))
+        .accounts(depositStakeAccounts(stakeAccount.publicKey))
+        .signers([user])
+        .rpc();
+
+      throw new Error("Deposit stake with a minimum pool tokens out above the expected output should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "SlippageExceeded");
+    }
+  });
+
+  it("Deposit a delegated stake account", async () => {
+    const lamports = 10 * LAMPORTS_PER_SOL;
+    const stakeAccount = await createUserStakeAccount(lamports, true);
+
+    // wait for the stake to activate and update the pool
+    await moveEpochForwardAndUpdatePool(connection, stakePoolInfo.accounts, validatorVoteAccount);
+
+    // deposit with a minimum pool tokens out well below the expected output
+    const tx = await program.methods
+      .depositStake(new BN(lamports / 2))
+      .accounts(depositStakeAccounts(stakeAccount.publicKey))
+      .transaction();
+
+    const txHash = await provider.sendAndConfirm(tx, [user], {
+      commitment: "confirmed",
+    });
+
+    // verify the stake account was merged into the validator stake account
+    assert.isNull(await connection.getAccountInfo(stakeAccount.publicKey));
+
+    // verify the user received the stake pool tokens
+    const userPoolTokenAccount = await getAccount(connection, userPoolTokenATA);
+    assert(Number(userPoolTokenAccount.amount) > 0);
+
+    // verify the StakeDeposited event was emitted with the correct data
+    const event = await getEvent(program, txHash, "stakeDeposited");
+    assert.ok(event);
+    assert.strictEqual(event.data.stakeAccount.toBase58(), stakeAccount.publicKey.toBase58());
+    assert.strictEqual(event.data.validator.toBase58(), validatorVoteAccount.toBase58());
+    assert.strictEqual(event.data.lamports.toNumber(), lamports);
+  });
+});
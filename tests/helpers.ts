@@ -13,10 +13,26 @@ import {
 } from "@solana/spl-token";
 import { StakePoolSchema, FundingType, StakePool, ValidatorListHeaderSchema, ValidatorListHeader, ValidatorStakeInfoSchema, ValidatorStakeInfo, ValidatorList, InitializeData, Fee, InitializeSchema, CreateStakePoolResponse, StakePoolAccounts, increaseAdditionalValidatorStakeData, IncreaseStakeSchema, StakeStatus} from "./stake_pool/types";
 import { assert } from "chai";
+import { createHash } from "crypto";
 
 // Constants
 export const STAKE_POOL_PROGRAM_ID = new PublicKey( "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
 
+// Agent permission bits
+export const AGENT_CAN_WHITELIST = 1 << 0;
+export const AGENT_CAN_BLACKLIST = 1 << 1;
+export const AGENT_CAN_CLEAR = 1 << 2;
+export const AGENT_CAN_MANAGE_AGENTS = 1 << 3;
+
+// Pause flags
+export const PAUSE_DEPOSITS = 1 << 0;
+export const PAUSE_VALIDATOR_DEPOSITS = 1 << 1;
+export const PAUSE_WITHDRAWALS = 1 << 2;
+export const PAUSE_REBALANCING = 1 << 3;
+export const PAUSE_VALIDATOR_CHANGES = 1 << 4;
+export const PAUSE_ALL =
+  PAUSE_DEPOSITS | PAUSE_VALIDATOR_DEPOSITS | PAUSE_WITHDRAWALS | PAUSE_REBALANCING | PAUSE_VALIDATOR_CHANGES;
+
 export async function fetchEvent(
   program: anchor.Program<Staker>,
   tx: anchor.web3.Transaction,
@@ -71,6 +87,36 @@ export async function getEvent(
     .find(event => event !== null && event.name === eventName);
 }
 
+export async function getEvents(
+  program: anchor.Program<Staker>,
+  txHash: string,
+  eventName: string
+): Promise<anchor.Event[]> {
+
+  // fetch the transaction
+  const txResult = await program.provider.connection.getTransaction(
+    txHash,
+    {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    }
+  );
+
+  if (!txResult || !txResult.meta || !txResult.meta.innerInstructions) {
+    throw new Error(`Transaction ${txHash} not found or invalid.`);
+  }
+
+  // decode and return all events with the given name in the transaction metadata
+  return txResult.meta.innerInstructions
+    .flatMap(({ instructions }) => instructions)
+    .map(instruction => {
+      const ixData = anchor.utils.bytes.bs58.decode(instruction.data);
+      const eventData = anchor.utils.bytes.base64.encode(ixData.subarray(8));
+      return program.coder.events.decode(eventData);
+    })
+    .filter(event => event !== null && event.name === eventName);
+}
+
 // Request airdrop for the given user and wait for it to be confirmed
 export async function requestAirdrop(connection: anchor.web3.Connection, user: PublicKey, amount: number) {
     const signature = await connection.requestAirdrop(user, amount * LAMPORTS_PER_SOL);
@@ -83,12 +129,22 @@ export async function requestAirdrop(connection: anchor.web3.Connection, user: P
     });
 }
 
-export async function initStaker(owner: PublicKey, stakeManager: PublicKey): Promise<anchor.Program<Staker>> {
+// Initializes the Staker bound to the given stake pool, or to a new stake pool for tests that do not use one
+export async function initStaker(
+  owner: PublicKey,
+  stakeManager: PublicKey,
+  stakePool?: PublicKey
+): Promise<anchor.Program<Staker>> {
   let program = anchor.workspace.Staker as anchor.Program<Staker>;
-  
+  if (!stakePool) {
+    const stakePoolInfo = await createStakePool(program.programId, Keypair.generate(), Keypair.generate());
+    stakePool = stakePoolInfo.accounts.stakePoolAccount;
+  }
+
   await program.methods.initializeStaker().accounts({
     ownerInfo: owner,
     stakeManagerInfo: stakeManager,
+    stakePool,
   }).rpc();
 
   return program;
@@ -175,6 +231,12 @@ export async function createStakePool(
     STAKE_POOL_PROGRAM_ID
   );
 
+  // derive the SOL withdraw authority (PDA)
+  const [solWithdrawAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from("withdraw")],
+    stakerProgramId
+  );
+
   // create the pool token mint
   const poolMint = await createMint(
     connection,
@@ -310,7 +372,7 @@ export async function createStakePool(
     keys: [
       { pubkey: stakePoolKeypair.publicKey, isSigner: false, isWritable: true }, // Stake pool account
       { pubkey: managerKeypair.publicKey, isSigner: true, isWritable: false }, // Manager
-      { pubkey: poolWithdrawAuthority, isSigner: false, isWritable: false }, // Withdraw authority
+      { pubkey: solWithdrawAuthority, isSigner: false, isWritable: false }, // SOL withdraw authority
     ],
     data: Buffer.concat([
       Buffer.from(Uint8Array.of(15)), // Instruction index for SetFundingAuthority
@@ -343,6 +405,7 @@ export async function createStakePool(
       feesTokenAccount: feesTokenAccount.address,
       depositAuthorityAccount: poolDepositAuthority,
       withdrawAuthorityAccount: poolWithdrawAuthority,
+      solWithdrawAuthorityAccount: solWithdrawAuthority,
     }
 
   } as CreateStakePoolResponse;
@@ -407,15 +470,93 @@ export async function addValidatorToStakePool(
 }
 
 
-export async function addUserToWhitelist(program: anchor.Program<Staker>, user: PublicKey) {
+// Queues a timelocked owner action and returns the address of its pending action account
+export async function queueAction(
+  program: anchor.Program<Staker>,
+  action: any,
+  owner?: Keypair
+): Promise<PublicKey> {
+  const [accessAddress] = PublicKey.findProgramAddressSync([Buffer.from("access")], program.programId);
+  const access = await program.account.access.fetch(accessAddress);
+  const [pendingAction] = PublicKey.findProgramAddressSync(
+    [Buffer.from("pending_action"), access.nextActionId.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const builder = program.methods
+    .queueAction(action)
+    .accountsPartial({
+      owner: owner ? owner.publicKey : program.provider.publicKey,
+      pendingAction,
+    });
+  await (owner ? builder.signers([owner]) : builder).rpc({ commitment: "confirmed" });
+
+  return pendingAction;
+}
+
+export async function addUserToWhitelist(
+  program: anchor.Program<Staker>,
+  user: PublicKey,
+  depositLimits: { maxLifetimeLamports: BN | null; maxEpochLamports: BN | null } = { maxLifetimeLamports: null, maxEpochLamports: null },
+  expiry: BN | null = null
+) {
   const tx = await program.methods
-    .addUserToWhitelist(user)
+    .addUserToWhitelist(user, depositLimits, expiry)
     .rpc();
 
   assert.ok(tx);
 
 }
 
+// encodes an Option<u64> with borsh
+function encodeOptionU64(value: BN | null): Buffer {
+  if (value === null) {
+    return Buffer.from([0]);
+  }
+  return Buffer.concat([Buffer.from([1]), value.toArrayLike(Buffer, "le", 8)]);
+}
+
+// returns the leaf of the whitelist Merkle tree for the user and deposit limits
+export function whitelistLeaf(
+  user: PublicKey,
+  depositLimits: { maxLifetimeLamports: BN | null; maxEpochLamports: BN | null }
+): Buffer {
+  return createHash("sha256")
+    .update(Buffer.from([0]))
+    .update(user.toBuffer())
+    .update(encodeOptionU64(depositLimits.maxLifetimeLamports))
+    .update(encodeOptionU64(depositLimits.maxEpochLamports))
+    .digest();
+}
+
+// returns the parent of two nodes of the whitelist Merkle tree
+export function whitelistNode(a: Buffer, b: Buffer): Buffer {
+  const [left, right] = Buffer.compare(a, b) <= 0 ? [a, b] : [b, a];
+  return createHash("sha256")
+    .update(Buffer.from([1]))
+    .update(left)
+    .update(right)
+    .digest();
+}
+
+// returns the borsh encoding of a KYC attestation, the message signed by the attester
+export function kycAttestationMessage(attestation: {
+  programId: PublicKey;
+  access: PublicKey;
+  user: PublicKey;
+  expiry: BN;
+  depositLimits: { maxLifetimeLamports: BN | null; maxEpochLamports: BN | null };
+}): Buffer {
+  return Buffer.concat([
+    attestation.programId.toBuffer(),
+    attestation.access.toBuffer(),
+    attestation.user.toBuffer(),
+    attestation.expiry.toArrayLike(Buffer, "le", 8),
+    encodeOptionU64(attestation.depositLimits.maxLifetimeLamports),
+    encodeOptionU64(attestation.depositLimits.maxEpochLamports),
+  ]);
+}
+
 export async function getStakePoolSharePrice(
   connection: Connection,
   stakePoolPubkey: PublicKey
@@ -654,7 +795,7 @@ export async function deposit(
   }
 
   // send the deposit transaction
-  const tx = await program.methods.deposit(depositAmount)
+  const tx = await program.methods.deposit(depositAmount, null, null)
     .accounts({
       user: sender.publicKey,
       stakePool: accounts.stakePoolAccount,
@@ -28,7 +28,7 @@ describe("TruSOL", () => {
       throw new Error("No vote accounts found");
     }
 
-    program = await initStaker(provider.wallet.publicKey, stakeManager.publicKey);
+    program = anchor.workspace.Staker as anchor.Program<Staker>;
     const staker = Keypair.generate();
 
     // create the stake pool with the manager and staker authorities
@@ -37,6 +37,9 @@ describe("TruSOL", () => {
       manager, // Manager authority
       staker, // Staker authority
     );
+
+    // initialize the staker bound to the new stake pool
+    program = await initStaker(provider.wallet.publicKey, stakeManager.publicKey, stakePoolInfo.accounts.stakePoolAccount);
   });
 
   it("Creates TruSOL metadata", async () => {
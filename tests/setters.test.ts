@@ -1,5 +1,5 @@
 import * as anchor from "@coral-xyz/anchor";
-import { initStaker, fetchEvent, getEvent, requestAirdrop } from "./helpers";
+import { initStaker, fetchEvent, getEvent, requestAirdrop, queueAction, PAUSE_ALL, PAUSE_DEPOSITS, PAUSE_REBALANCING } from "./helpers";
 import { assert } from "chai";
 import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 import { Staker } from "../target/types/staker";
@@ -30,9 +30,9 @@ describe("Setters", () => {
 
   it("Can pause the staker", async () => {
     const tx = await program.methods
-      .pause()
+      .pause(PAUSE_ALL)
       .accountsPartial({
-        owner: provider.wallet.publicKey,
+        signer: provider.wallet.publicKey,
       })
       .transaction();
 
@@ -41,15 +41,15 @@ describe("Setters", () => {
 
     // check staker is now paused:
     const access = await program.account.access.fetch(accessAddress);
-    assert.strictEqual(access.isPaused, true);
+    assert.strictEqual(access.pausedFlags, PAUSE_ALL);
   });
 
   it("Pausing a paused staker fails", async () => {
     try {
       await program.methods
-        .pause()
+        .pause(PAUSE_ALL)
         .accountsPartial({
-          owner: provider.wallet.publicKey,
+          signer: provider.wallet.publicKey,
         })
         .rpc();
       throw new Error("Pausing should fail");
@@ -66,9 +66,9 @@ describe("Setters", () => {
       );
 
       await program.methods
-        .pause()
+        .pause(PAUSE_ALL)
         .accountsPartial({
-          owner: user.publicKey,
+          signer: user.publicKey,
         })
         .signers([user])
         .rpc();
@@ -79,10 +79,12 @@ describe("Setters", () => {
   });
 
   it("Can unpause the staker", async () => {
+    const pendingAction = await queueAction(program, { unpause: { flags: PAUSE_ALL } });
     const tx = await program.methods
-      .unpause()
+      .unpause(PAUSE_ALL)
       .accountsPartial({
         owner: provider.wallet.publicKey,
+        pendingAction,
       })
       .transaction();
 
@@ -91,15 +93,17 @@ describe("Setters", () => {
 
     // check staker is now unpaused:
     const access = await program.account.access.fetch(accessAddress);
-    assert.strictEqual(access.isPaused, false);
+    assert.strictEqual(access.pausedFlags, 0);
   });
 
   it("Unpausing an unpaused staker fails", async () => {
     try {
+      const pendingAction = await queueAction(program, { unpause: { flags: PAUSE_ALL } });
       await program.methods
-        .unpause()
+        .unpause(PAUSE_ALL)
         .accountsPartial({
           owner: provider.wallet.publicKey,
+          pendingAction,
         })
         .rpc();
       throw new Error("Unpausing should fail");
@@ -110,10 +114,12 @@ describe("Setters", () => {
 
   it("Non-owner unpausing the staker fails", async () => {
     try {
+      const pendingAction = await queueAction(program, { unpause: { flags: PAUSE_ALL } });
       await program.methods
-        .unpause()
+        .unpause(PAUSE_ALL)
         .accountsPartial({
           owner: user.publicKey,
+          pendingAction,
         })
         .signers([user])
         .rpc();
@@ -123,10 +129,45 @@ describe("Setters", () => {
     }
   });
 
+  it("Pausing with invalid flags fails", async () => {
+    for (const flags of [0, PAUSE_ALL + 1]) {
+      try {
+        await program.methods.pause(flags).rpc();
+        throw new Error("Pausing should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "InvalidPauseFlags");
+      }
+    }
+  });
+
+  it("Can pause and unpause operations separately", async () => {
+    // pause rebalancing, then deposits
+    await program.methods.pause(PAUSE_REBALANCING).rpc();
+    const txHash = await program.methods.pause(PAUSE_DEPOSITS).rpc({ commitment: "confirmed" });
+
+    const event = await getEvent(program, txHash, "stakerPaused");
+    assert.ok(event);
+    assert.strictEqual(event.data.flags, PAUSE_DEPOSITS);
+    assert.strictEqual(event.data.pausedFlags, PAUSE_REBALANCING | PAUSE_DEPOSITS);
+
+    // unpausing deposits leaves rebalancing paused
+    let pendingAction = await queueAction(program, { unpause: { flags: PAUSE_DEPOSITS } });
+    await program.methods.unpause(PAUSE_DEPOSITS).accounts({ pendingAction }).rpc();
+    let access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.pausedFlags, PAUSE_REBALANCING);
+
+    pendingAction = await queueAction(program, { unpause: { flags: PAUSE_REBALANCING } });
+    await program.methods.unpause(PAUSE_REBALANCING).accounts({ pendingAction }).rpc();
+    access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.pausedFlags, 0);
+  });
+
   it("Can set a pending owner", async () => {
     const pending_owner = Keypair.generate();
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: pending_owner.publicKey, deadline: null } });
     const tx = await program.methods
-      .setPendingOwner(pending_owner.publicKey)
+      .setPendingOwner(pending_owner.publicKey, null)
+      .accounts({ pendingAction })
       .transaction();
 
     const event = await fetchEvent(program, tx, 0);
@@ -148,7 +189,8 @@ describe("Setters", () => {
   });
 
   it("Can set pending owner twice", async () => {
-    await program.methods.setPendingOwner(user.publicKey).rpc();
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline: null } });
+    await program.methods.setPendingOwner(user.publicKey, null).accounts({ pendingAction }).rpc();
 
     const access_account = await program.account.access.fetch(accessAddress);
     assert.strictEqual(
@@ -159,10 +201,13 @@ describe("Setters", () => {
 
   it("Non-owner setting the pending owner fails", async () => {
     try {
+      const pendingOwner = Keypair.generate().publicKey;
+      const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner, deadline: null } });
       await program.methods
-        .setPendingOwner(Keypair.generate().publicKey)
+        .setPendingOwner(pendingOwner, null)
         .accountsPartial({
           owner: user.publicKey,
+          pendingAction,
         })
         .signers([user])
         .rpc();
@@ -221,10 +266,16 @@ describe("Setters", () => {
   it("Claiming ownership with non pending owner fails", async () => {
     try {
       // set a new pending owner
+      const pendingAction = await queueAction(
+        program,
+        { setPendingOwner: { pendingOwner: provider.wallet.publicKey, deadline: null } },
+        user
+      );
       await program.methods
-        .setPendingOwner(provider.wallet.publicKey)
+        .setPendingOwner(provider.wallet.publicKey, null)
         .accountsPartial({
           owner: user.publicKey,
+          pendingAction,
         })
         .signers([user])
         .rpc();
@@ -252,13 +303,116 @@ describe("Setters", () => {
       .rpc();
   });
 
+  it("Non-owner cancelling the pending owner fails", async () => {
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline: null } });
+    await program.methods.setPendingOwner(user.publicKey, null).accounts({ pendingAction }).rpc();
+
+    try {
+      await program.methods
+        .cancelPendingOwner()
+        .accountsPartial({
+          owner: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Cancelling the pending owner should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Can cancel the pending owner", async () => {
+    const txHash = await program.methods
+      .cancelPendingOwner()
+      .rpc({ commitment: "confirmed" });
+
+    // verify the PendingOwnerCancelled event
+    const event = await getEvent(program, txHash, "pendingOwnerCancelled");
+    assert.ok(event);
+    assert.strictEqual(event.data.currentOwner.toString(), provider.wallet.publicKey.toString());
+    assert.strictEqual(event.data.pendingOwner.toString(), user.publicKey.toString());
+
+    const access_account = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access_account.pendingOwner, null);
+
+    // the cancelled pending owner can no longer claim ownership
+    try {
+      await program.methods
+        .claimOwnership()
+        .accounts({
+          pendingOwner: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Claiming ownership should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "PendingOwnerNotSet");
+    }
+  });
+
+  it("Cancelling with no pending owner fails", async () => {
+    try {
+      await program.methods.cancelPendingOwner().rpc();
+      throw new Error("Cancelling the pending owner should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "PendingOwnerNotSet");
+    }
+  });
+
+  it("Setting a pending owner with a past deadline fails", async () => {
+    const deadline = new anchor.BN(await provider.connection.getSlot());
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline } });
+    try {
+      await program.methods.setPendingOwner(user.publicKey, deadline).accounts({ pendingAction }).rpc();
+      throw new Error("Setting the pending owner should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidPendingOwnerDeadline");
+    }
+  });
+
+  it("Claiming an expired pending ownership fails", async () => {
+    const deadline = new anchor.BN((await provider.connection.getSlot()) + 5);
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline } });
+    const txHash = await program.methods
+      .setPendingOwner(user.publicKey, deadline)
+      .accounts({ pendingAction })
+      .rpc({ commitment: "confirmed" });
+
+    const event = await getEvent(program, txHash, "setPendingOwner");
+    assert.strictEqual(event.data.deadline.toNumber(), deadline.toNumber());
+    const access_account = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access_account.pendingOwnerDeadline.toNumber(), deadline.toNumber());
+
+    // wait for the deadline to pass
+    while ((await provider.connection.getSlot()) <= deadline.toNumber() + 1) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+
+    try {
+      await program.methods
+        .claimOwnership()
+        .accounts({
+          pendingOwner: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Claiming ownership should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "PendingOwnershipExpired");
+    }
+
+    await program.methods.cancelPendingOwner().rpc();
+  });
+
   it("Non-owner setting the new staker manager fails", async () => {
     try {
       const newStakeManager = Keypair.generate();
+      const pendingAction = await queueAction(program, { setStakeManager: { newStakeManager: newStakeManager.publicKey } });
       await program.methods
         .setStakeManager()
         .accountsPartial({
           owner: user.publicKey,
+          pendingAction,
           newStakeManager: newStakeManager.publicKey,
           oldStakeManager: stakeManager.publicKey,
         })
@@ -279,9 +433,11 @@ describe("Setters", () => {
       await requestAirdrop(provider.connection, invalidOldStakeManager.publicKey, 1);
       assert.ok(await provider.connection.getAccountInfo(invalidOldStakeManager.publicKey))
 
+      const pendingAction = await queueAction(program, { setStakeManager: { newStakeManager: newStakeManager.publicKey } });
       await program.methods
         .setStakeManager()
         .accounts({
+          pendingAction,
           newStakeManager: newStakeManager.publicKey,
           oldStakeManager: invalidOldStakeManager.publicKey,
         })
@@ -298,9 +454,11 @@ describe("Setters", () => {
   it("Can set the staker manager", async () => {
     const newStakeManager = Keypair.generate();
 
+    const pendingAction = await queueAction(program, { setStakeManager: { newStakeManager: newStakeManager.publicKey } });
     const tx = await program.methods
       .setStakeManager()
       .accounts({
+        pendingAction,
         newStakeManager: newStakeManager.publicKey,
         oldStakeManager: stakeManager.publicKey,
       })
@@ -350,4 +508,224 @@ describe("Setters", () => {
     }
   });
 
-});
\ No newline at end of file
+  it("Non-owner setting the deposit caps fails", async () => {
+    try {
+      await program.methods
+        .setDepositCaps(new anchor.BN(1000 * LAMPORTS_PER_SOL), null)
+        .accountsPartial({
+          owner: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Setting the deposit caps should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Can set the deposit caps", async () => {
+    const maxTotalLamports = new anchor.BN(1000 * LAMPORTS_PER_SOL);
+    const maxEpochDepositLamports = new anchor.BN(100 * LAMPORTS_PER_SOL);
+
+    const txHash = await program.methods
+      .setDepositCaps(maxTotalLamports, maxEpochDepositLamports)
+      .rpc({ commitment: "confirmed" });
+
+    // verify the DepositCapsSet event
+    const event = await getEvent(program, txHash, "depositCapsSet");
+    assert.ok(event);
+    assert.strictEqual(event.data.oldMaxTotalLamports, null);
+    assert.strictEqual(event.data.newMaxTotalLamports.toNumber(), maxTotalLamports.toNumber());
+    assert.strictEqual(event.data.oldMaxEpochDepositLamports, null);
+    assert.strictEqual(event.data.newMaxEpochDepositLamports.toNumber(), maxEpochDepositLamports.toNumber());
+
+    // check the caps are set
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.maxTotalLamports.toNumber(), maxTotalLamports.toNumber());
+    assert.strictEqual(access.maxEpochDepositLamports.toNumber(), maxEpochDepositLamports.toNumber());
+  });
+
+  it("Can remove the deposit caps", async () => {
+    await program.methods.setDepositCaps(null, null).rpc();
+
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.maxTotalLamports, null);
+    assert.strictEqual(access.maxEpochDepositLamports, null);
+  });
+
+  it("Non-owner setting the attester fails", async () => {
+    try {
+      await program.methods
+        .setAttester(Keypair.generate().publicKey)
+        .accountsPartial({
+          owner: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Setting the attester should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Can set the attester", async () => {
+    const attester = Keypair.generate().publicKey;
+
+    const txHash = await program.methods
+      .setAttester(attester)
+      .rpc({ commitment: "confirmed" });
+
+    // verify the AttesterSet event
+    const event = await getEvent(program, txHash, "attesterSet");
+    assert.ok(event);
+    assert.strictEqual(event.data.oldAttester, null);
+    assert.strictEqual(event.data.newAttester.toBase58(), attester.toBase58());
+
+    // check the attester is set
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.attester.toBase58(), attester.toBase58());
+  });
+
+  it("Can remove the attester", async () => {
+    await program.methods.setAttester(null).rpc();
+
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.attester, null);
+  });
+
+  describe("Guardians", () => {
+    let guardian: Keypair;
+
+    before(async () => {
+      guardian = Keypair.generate();
+    });
+
+    it("Non-owner adding a guardian fails", async () => {
+      try {
+        await program.methods
+          .addGuardian(guardian.publicKey)
+          .accountsPartial({
+            owner: user.publicKey,
+          })
+          .signers([user])
+          .rpc();
+        throw new Error("Adding a guardian should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+      }
+    });
+
+    it("Can add a guardian", async () => {
+      const txHash = await program.methods
+        .addGuardian(guardian.publicKey)
+        .rpc({ commitment: "confirmed" });
+
+      // verify the GuardianAdded event
+      const event = await getEvent(program, txHash, "guardianAdded");
+      assert.ok(event);
+      assert.strictEqual(event.data.guardian.toBase58(), guardian.publicKey.toBase58());
+
+      const access = await program.account.access.fetch(accessAddress);
+      assert.deepEqual(access.guardians.map((key) => key.toBase58()), [guardian.publicKey.toBase58()]);
+    });
+
+    it("Adding an existing guardian fails", async () => {
+      try {
+        await program.methods.addGuardian(guardian.publicKey).rpc();
+        throw new Error("Adding a guardian should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "GuardianAlreadyExists");
+      }
+    });
+
+    it("Guardian can pause the staker", async () => {
+      const txHash = await program.methods
+        .pause(PAUSE_ALL)
+        .accountsPartial({
+          signer: guardian.publicKey,
+        })
+        .signers([guardian])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await getEvent(program, txHash, "stakerPaused");
+      assert.ok(event);
+
+      const access = await program.account.access.fetch(accessAddress);
+      assert.strictEqual(access.pausedFlags, PAUSE_ALL);
+    });
+
+    it("Guardian unpausing the staker fails", async () => {
+      try {
+        const pendingAction = await queueAction(program, { unpause: { flags: PAUSE_ALL } });
+        await program.methods
+          .unpause(PAUSE_ALL)
+          .accountsPartial({
+            owner: guardian.publicKey,
+            pendingAction,
+          })
+          .signers([guardian])
+          .rpc();
+        throw new Error("Unpausing should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+      }
+
+      // the owner unpauses the staker
+      const pendingAction = await queueAction(program, { unpause: { flags: PAUSE_ALL } });
+      await program.methods.unpause(PAUSE_ALL).accounts({ pendingAction }).rpc();
+    });
+
+    it("Non-owner removing a guardian fails", async () => {
+      try {
+        await program.methods
+          .removeGuardian(guardian.publicKey)
+          .accountsPartial({
+            owner: user.publicKey,
+          })
+          .signers([user])
+          .rpc();
+        throw new Error("Removing a guardian should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+      }
+    });
+
+    it("Can remove a guardian", async () => {
+      const txHash = await program.methods
+        .removeGuardian(guardian.publicKey)
+        .rpc({ commitment: "confirmed" });
+
+      // verify the GuardianRemoved event
+      const event = await getEvent(program, txHash, "guardianRemoved");
+      assert.ok(event);
+      assert.strictEqual(event.data.guardian.toBase58(), guardian.publicKey.toBase58());
+
+      const access = await program.account.access.fetch(accessAddress);
+      assert.strictEqual(access.guardians.length, 0);
+    });
+
+    it("Removed guardian pausing the staker fails", async () => {
+      try {
+        await program.methods
+          .pause(PAUSE_ALL)
+          .accountsPartial({
+            signer: guardian.publicKey,
+          })
+          .signers([guardian])
+          .rpc();
+        throw new Error("Pausing should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+      }
+    });
+
+    it("Removing an unknown guardian fails", async () => {
+      try {
+        await program.methods.removeGuardian(guardian.publicKey).rpc();
+        throw new Error("Removing a guardian should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "GuardianNotFound");
+      }
+    });
+  });
+});
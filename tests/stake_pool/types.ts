@@ -238,6 +238,7 @@ export type StakePoolAccounts = {
     feesTokenAccount: PublicKey;
     depositAuthorityAccount: PublicKey;
     withdrawAuthorityAccount: PublicKey;
+    solWithdrawAuthorityAccount: PublicKey;
   };
 
 export type CreateStakePoolResponse = {
@@ -1,4 +1,4 @@
-import { getEvent } from './helpers';
+import { createStakePool, getEvent } from './helpers';
 import * as anchor from "@coral-xyz/anchor";
 import { assert } from "chai";
 import { Keypair, PublicKey} from '@solana/web3.js';
@@ -15,9 +15,11 @@ describe("Staker init", () => {
     const default_validator = Keypair.generate();
 
     const stakeManager = Keypair.generate();
+    const stakePoolInfo = await createStakePool(program.programId, Keypair.generate(), Keypair.generate());
     let tx = await program.methods.initializeStaker().accounts({
       ownerInfo: provider.wallet.publicKey,
       stakeManagerInfo: stakeManager.publicKey,
+      stakePool: stakePoolInfo.accounts.stakePoolAccount,
     }).transaction();
 
     const txHash = await program.provider.sendAndConfirm(tx, [], {
@@ -27,12 +29,22 @@ describe("Staker init", () => {
     const event = (await getEvent(program, txHash, "stakerInitialized")).data as StakerInitializedEvent;
     assert.strictEqual((event.owner).toString(), program.provider.publicKey.toString());
     assert.strictEqual((event.stakeManager).toString(), stakeManager.publicKey.toString());
+    assert.strictEqual((event.stakePool).toString(), stakePoolInfo.accounts.stakePoolAccount.toString());
 
     const [accessAddress] = PublicKey.findProgramAddressSync([
       Buffer.from("access")], program.programId);
     const access = await program.account.access.fetch(accessAddress);
     assert.strictEqual((access.owner).toString(), provider.wallet.publicKey.toString());
-    assert.strictEqual(access.isPaused, false);
+    assert.strictEqual(access.pausedFlags, 0);
+    assert.strictEqual(access.version, 1);
+
+    // verify the stake pool and its accounts were recorded
+    assert.strictEqual(access.stakePool.toString(), stakePoolInfo.accounts.stakePoolAccount.toString());
+    assert.strictEqual(access.poolMint.toString(), stakePoolInfo.accounts.poolMintAccount.toString());
+    assert.strictEqual(access.reserveStake.toString(), stakePoolInfo.accounts.reserveStakeAccount.toString());
+    assert.strictEqual(access.validatorList.toString(), stakePoolInfo.accounts.validatorListAccount.toString());
+    assert.strictEqual(access.managerFeeAccount.toString(), stakePoolInfo.accounts.feesTokenAccount.toString());
+    assert.strictEqual(access.withdrawAuthority.toString(), stakePoolInfo.accounts.withdrawAuthorityAccount.toString());
 
     // verify stake manager PDA exists
     const [stakeManagerPDA] = PublicKey.findProgramAddressSync([
@@ -42,11 +54,21 @@ describe("Staker init", () => {
     assert.ok(stakeManagerData, "Stake manager should exist");
 
   });
+
+  it("Migrating an access account at the latest version fails", async () => {
+    try {
+      await program.methods.migrateAccess().rpc();
+      throw new Error("Migrating the access account should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "AccessAlreadyMigrated");
+    }
+  });
 });
 
 interface StakerInitializedEvent {
   owner: PublicKey;
   stakeManager: PublicKey;
+  stakePool: PublicKey;
   treasury: PublicKey;
   defaultValidator: PublicKey;
   fee: anchor.BN;
@@ -10,6 +10,7 @@ import {
   TransactionInstruction,
   DeactivateStakeParams,
   WithdrawStakeParams,
+  Ed25519Program,
 } from "@solana/web3.js";
 import { Staker } from "../target/types/staker";
 import {
@@ -27,6 +28,10 @@ import {
   increaseAdditionalValidatorStake,
   decodeValidatorListAccount,
   getStakePool,
+  whitelistLeaf,
+  whitelistNode,
+  kycAttestationMessage,
+  queueAction,
 } from "./helpers";
 import { CreateStakePoolResponse } from "./stake_pool/types";
 import {
@@ -75,10 +80,7 @@ describe("staking", () => {
     stakeManager = Keypair.generate();
     staker = Keypair.generate();
 
-    program = await initStaker(
-      provider.wallet.publicKey,
-      stakeManager.publicKey
-    );
+    program = anchor.workspace.Staker as anchor.Program<Staker>;
 
     // derive the staker authority PDA
     [stakerAuthorityPDA] = PublicKey.findProgramAddressSync(
@@ -93,6 +95,9 @@ describe("staking", () => {
       staker // Staker authority
     );
 
+    // initialize the staker bound to the new stake pool
+    program = await initStaker(provider.wallet.publicKey, stakeManager.publicKey, stakePoolInfo.accounts.stakePoolAccount);
+
     // sets stakerAuthorityPDA as the new staker authority of the pool
     const setStakerIx = new TransactionInstruction({
       programId: STAKE_POOL_PROGRAM_ID,
@@ -122,6 +127,7 @@ describe("staking", () => {
     );
 
     const validatorSeed = 0;
+    const pendingAction = await queueAction(program, { addValidator: { validatorVoteAccount, validatorSeed } });
     const addValidatorTx = await program.methods
       .addValidator(validatorSeed)
       .accounts({
@@ -131,6 +137,7 @@ describe("staking", () => {
         validatorList: stakePoolInfo.accounts.validatorListAccount,
         validatorStakeAccount: validatorStakeAccount,
         validatorVoteAccount: validatorVoteAccount,
+        pendingAction,
       })
       .signers([owner.payer])
       .rpc();
@@ -166,7 +173,7 @@ describe("staking", () => {
     const depositAmount = new BN(20 * LAMPORTS_PER_SOL);
     try {
       await program.methods
-        .deposit(depositAmount)
+        .deposit(depositAmount, null, null)
         .accounts({
           user: user.publicKey,
           stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -227,7 +234,7 @@ describe("staking", () => {
     // deposit 20 SOL
     const depositAmount = new BN(20 * LAMPORTS_PER_SOL);
     const tx = await program.methods
-      .deposit(depositAmount)
+      .deposit(depositAmount, null, null)
       .accounts({
         user: user.publicKey,
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -265,10 +272,95 @@ describe("staking", () => {
     assert(Number(userPollTokenAccount.amount) > 0);
   });
 
+  it("Deposit with an invalid fee token account should fail", async () => {
+    const depositAmount = new BN(1 * LAMPORTS_PER_SOL);
+    try {
+      await program.methods
+        .deposit(depositAmount, null, null)
+        .accounts({
+          user: user.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: userPoolTokenATA,
+          feeTokenAccount: userPoolTokenATA,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Deposit with an invalid fee token account should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidFeeTokenAccount");
+    }
+  });
+
+  it("Deposit with a minimum pool tokens out above the expected output should fail", async () => {
+    const depositAmount = new BN(1 * LAMPORTS_PER_SOL);
+    try {
+      await program.methods
+        .deposit(depositAmount, depositAmount.muln(2), null)
+        .accounts({
+          user: user.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: userPoolTokenATA,
+          feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Deposit with a minimum pool tokens out above the expected output should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "SlippageExceeded");
+    }
+  });
+
+  it("Deposit with a minimum pool tokens out", async () => {
+    const userPoolTokenAccountPre = await getAccount(
+      provider.connection,
+      userPoolTokenATA
+    );
+
+    const depositAmount = new BN(1 * LAMPORTS_PER_SOL);
+    const minPoolTokensOut = depositAmount.divn(2);
+    await program.methods
+      .deposit(depositAmount, minPoolTokensOut, null)
+      .accounts({
+        user: user.publicKey,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+        userPoolTokenAccount: userPoolTokenATA,
+        feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      })
+      .signers([user])
+      .rpc();
+
+    // verify the user received at least the minimum pool tokens out
+    const userPoolTokenAccount = await getAccount(
+      provider.connection,
+      userPoolTokenATA
+    );
+    assert(
+      Number(userPoolTokenAccount.amount) - Number(userPoolTokenAccountPre.amount) >=
+        minPoolTokensOut.toNumber()
+    );
+  });
+
   it("Emits Deposited event", async () => {
     const depositAmount = new BN(1 * LAMPORTS_PER_SOL);
     const tx = await program.methods
-      .deposit(depositAmount)
+      .deposit(depositAmount, null, null)
       .accounts({
         user: user.publicKey,
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -292,6 +384,750 @@ describe("staking", () => {
     assert.strictEqual(event.data.amount.toNumber(), depositAmount.toNumber());
   });
 
+  it("Deposit exceeding the per-epoch deposit cap should fail", async () => {
+    await program.methods.setDepositCaps(null, new BN(1 * LAMPORTS_PER_SOL)).rpc();
+    try {
+      await program.methods
+        .deposit(new BN(2 * LAMPORTS_PER_SOL), null, null)
+        .accounts({
+          user: user.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: userPoolTokenATA,
+          feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Deposit exceeding the per-epoch deposit cap should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "DepositCapExceeded");
+    } finally {
+      await program.methods.setDepositCaps(null, null).rpc();
+    }
+  });
+
+  it("Deposit exceeding the total pool lamports cap should fail", async () => {
+    const stakePool = await getStakePool(connection, stakePoolInfo.accounts.stakePoolAccount);
+    const maxTotalLamports = new BN(stakePool.totalLamports.toString()).add(new BN(1 * LAMPORTS_PER_SOL));
+    await program.methods.setDepositCaps(maxTotalLamports, null).rpc();
+    try {
+      await program.methods
+        .deposit(new BN(2 * LAMPORTS_PER_SOL), null, null)
+        .accounts({
+          user: user.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: userPoolTokenATA,
+          feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Deposit exceeding the total pool lamports cap should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "DepositCapExceeded");
+    } finally {
+      await program.methods.setDepositCaps(null, null).rpc();
+    }
+  });
+
+  it("Deposit exceeding the user's per-epoch deposit limit should fail", async () => {
+    const limitedUser = Keypair.generate();
+    await requestAirdrop(connection, limitedUser.publicKey, 10);
+    await addUserToWhitelist(program, limitedUser.publicKey, {
+      maxLifetimeLamports: null,
+      maxEpochLamports: new BN(1 * LAMPORTS_PER_SOL),
+    });
+
+    try {
+      await program.methods
+        .deposit(new BN(2 * LAMPORTS_PER_SOL), null, null)
+        .accounts({
+          user: limitedUser.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: await getAssociatedTokenAddress(
+            stakePoolInfo.accounts.poolMintAccount,
+            limitedUser.publicKey
+          ),
+          feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .signers([limitedUser])
+        .rpc();
+
+      throw new Error("Deposit exceeding the user's per-epoch deposit limit should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserDepositLimitExceeded");
+    }
+  });
+
+  it("Deposit for a user whose whitelisting has expired should fail", async () => {
+    const expiringUser = Keypair.generate();
+    await requestAirdrop(connection, expiringUser.publicKey, 10);
+    const now = await connection.getBlockTime(await connection.getSlot());
+    await addUserToWhitelist(program, expiringUser.publicKey, undefined, new BN(now + 2));
+
+    // wait for the whitelisting to lapse
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+
+    try {
+      await program.methods
+        .deposit(new BN(1 * LAMPORTS_PER_SOL), null, null)
+        .accounts({
+          user: expiringUser.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: await getAssociatedTokenAddress(
+            stakePoolInfo.accounts.poolMintAccount,
+            expiringUser.publicKey
+          ),
+          feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .signers([expiringUser])
+        .rpc();
+
+      throw new Error("Deposit for a user whose whitelisting has expired should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "WhitelistExpired");
+    }
+  });
+
+  it("Deposit records the user's cumulative deposits", async () => {
+    const userStatusPre = await program.account.userStatus.fetch(userWhitelistPDA);
+
+    const depositAmount = new BN(1 * LAMPORTS_PER_SOL);
+    await program.methods
+      .deposit(depositAmount, null, null)
+      .accounts({
+        user: user.publicKey,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+        userPoolTokenAccount: userPoolTokenATA,
+        feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      })
+      .signers([user])
+      .rpc();
+
+    const userStatus = await program.account.userStatus.fetch(userWhitelistPDA);
+    assert.strictEqual(
+      userStatus.totalDepositedLamports.toNumber(),
+      userStatusPre.totalDepositedLamports.toNumber() + depositAmount.toNumber()
+    );
+  });
+
+  it("Set the whitelist Merkle root by a non-agent should fail", async () => {
+    try {
+      await program.methods
+        .setWhitelistMerkleRoot(Array(32).fill(1))
+        .accounts({
+          signer: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Non-agent should not be able to set the whitelist Merkle root");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "AccountNotInitialized");
+    }
+  });
+
+  describe("Merkle whitelist", () => {
+    const merkleUser = Keypair.generate();
+    const blacklistedUser = Keypair.generate();
+    const limitedUser = Keypair.generate();
+    const merkleUserLimits = {
+      maxLifetimeLamports: new BN(5 * LAMPORTS_PER_SOL),
+      maxEpochLamports: null,
+    };
+    const noDepositLimits = { maxLifetimeLamports: null, maxEpochLamports: null };
+
+    let leaves: Buffer[];
+    let root: Buffer;
+
+    // returns the deposit accounts for a user depositing into their associated token account
+    async function depositAccounts(depositor: PublicKey) {
+      return {
+        user: depositor,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+        userPoolTokenAccount: await getAssociatedTokenAddress(
+          stakePoolInfo.accounts.poolMintAccount,
+          depositor
+        ),
+        feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      };
+    }
+
+    before(async () => {
+      for (const depositor of [merkleUser, blacklistedUser, limitedUser]) {
+        await requestAirdrop(connection, depositor.publicKey, 10);
+        await provider.sendAndConfirm(
+          new Transaction().add(
+            createAssociatedTokenAccountInstruction(
+              depositor.publicKey,
+              await getAssociatedTokenAddress(
+                stakePoolInfo.accounts.poolMintAccount,
+                depositor.publicKey
+              ),
+              depositor.publicKey,
+              stakePoolInfo.accounts.poolMintAccount
+            )
+          ),
+          [depositor]
+        );
+      }
+      await program.methods.addUserToBlacklist(blacklistedUser.publicKey, { other: {} }, Array(32).fill(0)).rpc();
+
+      // build a tree of four approved wallets
+      leaves = [
+        whitelistLeaf(merkleUser.publicKey, merkleUserLimits),
+        whitelistLeaf(blacklistedUser.publicKey, noDepositLimits),
+        whitelistLeaf(limitedUser.publicKey, noDepositLimits),
+        whitelistLeaf(Keypair.generate().publicKey, noDepositLimits),
+      ];
+      root = whitelistNode(
+        whitelistNode(leaves[0], leaves[1]),
+        whitelistNode(leaves[2], leaves[3])
+      );
+    });
+
+    it("Set the whitelist Merkle root", async () => {
+      const tx = await program.methods
+        .setWhitelistMerkleRoot(Array.from(root))
+        .transaction();
+
+      const txHash = await provider.sendAndConfirm(tx, [], {
+        commitment: "confirmed",
+      });
+
+      const access = await program.account.access.fetch(
+        PublicKey.findProgramAddressSync([Buffer.from("access")], program.programId)[0]
+      );
+      assert.deepEqual(Buffer.from(access.whitelistMerkleRoot), root);
+
+      const event = await getEvent(program, txHash, "whitelistMerkleRootSet");
+      assert.ok(event);
+      assert.isNull(event.data.oldMerkleRoot);
+      assert.deepEqual(Buffer.from(event.data.newMerkleRoot), root);
+    });
+
+    it("Deposit with an invalid whitelist proof should fail", async () => {
+      try {
+        await program.methods
+          .deposit(new BN(1 * LAMPORTS_PER_SOL), null, {
+            depositLimits: noDepositLimits,
+            proof: [Array.from(leaves[1]), Array.from(whitelistNode(leaves[2], leaves[3]))],
+          })
+          .accounts(await depositAccounts(merkleUser.publicKey))
+          .signers([merkleUser])
+          .rpc();
+
+        throw new Error("Deposit with an invalid whitelist proof should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "InvalidWhitelistProof");
+      }
+    });
+
+    it("Deposit by a blacklisted user with a valid whitelist proof should fail", async () => {
+      try {
+        await program.methods
+          .deposit(new BN(1 * LAMPORTS_PER_SOL), null, {
+            depositLimits: noDepositLimits,
+            proof: [Array.from(leaves[0]), Array.from(whitelistNode(leaves[2], leaves[3]))],
+          })
+          .accounts(await depositAccounts(blacklistedUser.publicKey))
+          .signers([blacklistedUser])
+          .rpc();
+
+        throw new Error("Blacklisted user should not be able to deposit");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "UserNotWhitelisted");
+      }
+    });
+
+    it("Deposit with a whitelist proof keeps the deposit limits set by an agent", async () => {
+      // an agent limits the user and then clears their status, leaving the limits on the record
+      const agentLimits = { maxLifetimeLamports: new BN(1 * LAMPORTS_PER_SOL), maxEpochLamports: null };
+      await program.methods.addUserToWhitelist(limitedUser.publicKey, agentLimits, null).rpc();
+      await program.methods.clearUserStatus(limitedUser.publicKey, { userRequest: {} }, Array(32).fill(0)).rpc();
+
+      // the proof commits to no limits, which must not loosen the agent's limits
+      const whitelistProof = {
+        depositLimits: noDepositLimits,
+        proof: [Array.from(leaves[3]), Array.from(whitelistNode(leaves[0], leaves[1]))],
+      };
+      try {
+        await program.methods
+          .deposit(new BN(2 * LAMPORTS_PER_SOL), null, whitelistProof)
+          .accounts(await depositAccounts(limitedUser.publicKey))
+          .signers([limitedUser])
+          .rpc();
+
+        throw new Error("Deposit above the agent's limit should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "UserDepositLimitExceeded");
+      }
+
+      await program.methods
+        .deposit(new BN(1 * LAMPORTS_PER_SOL), null, whitelistProof)
+        .accounts(await depositAccounts(limitedUser.publicKey))
+        .signers([limitedUser])
+        .rpc();
+      const [limitedUserPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user"), limitedUser.publicKey.toBuffer()],
+        program.programId
+      );
+      const userStatus = await program.account.userStatus.fetch(limitedUserPDA);
+      assert.strictEqual(
+        userStatus.depositLimits.maxLifetimeLamports.toNumber(),
+        agentLimits.maxLifetimeLamports.toNumber()
+      );
+    });
+
+    it("Deposit with a valid whitelist proof records the deposit limits of the proof", async () => {
+      const depositAmount = new BN(1 * LAMPORTS_PER_SOL);
+      const whitelistProof = {
+        depositLimits: merkleUserLimits,
+        proof: [Array.from(leaves[1]), Array.from(whitelistNode(leaves[2], leaves[3]))],
+      };
+      await program.methods
+        .deposit(depositAmount, null, whitelistProof)
+        .accounts(await depositAccounts(merkleUser.publicKey))
+        .signers([merkleUser])
+        .rpc();
+
+      // verify the limits committed to in the tree were recorded without whitelisting the user
+      const [merkleUserPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user"), merkleUser.publicKey.toBuffer()],
+        program.programId
+      );
+      const userStatus = await program.account.userStatus.fetch(merkleUserPDA);
+      assert.deepEqual(userStatus.status, { none: {} });
+      assert.strictEqual(
+        userStatus.depositLimits.maxLifetimeLamports.toNumber(),
+        merkleUserLimits.maxLifetimeLamports.toNumber()
+      );
+      assert.strictEqual(userStatus.totalDepositedLamports.toNumber(), depositAmount.toNumber());
+
+      // later deposits still need a proof
+      try {
+        await program.methods
+          .deposit(depositAmount, null, null)
+          .accounts(await depositAccounts(merkleUser.publicKey))
+          .signers([merkleUser])
+          .rpc();
+
+        throw new Error("Deposit without a proof should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "UserNotWhitelisted");
+      }
+      await program.methods
+        .deposit(depositAmount, null, whitelistProof)
+        .accounts(await depositAccounts(merkleUser.publicKey))
+        .signers([merkleUser])
+        .rpc();
+
+      // removing the root revokes the user's access
+      await program.methods.setWhitelistMerkleRoot(null).rpc();
+      try {
+        await program.methods
+          .deposit(depositAmount, null, whitelistProof)
+          .accounts(await depositAccounts(merkleUser.publicKey))
+          .signers([merkleUser])
+          .rpc();
+
+        throw new Error("Deposit after the root was removed should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "UserNotWhitelisted");
+      }
+    });
+
+    after(async () => {
+      await program.methods.setWhitelistMerkleRoot(null).rpc();
+    });
+  });
+
+  it("Deposit minting pool tokens to another owner's token account should fail", async () => {
+    try {
+      await program.methods
+        .deposit(new BN(1 * LAMPORTS_PER_SOL), null, null)
+        .accounts({
+          user: user.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Deposit to another owner's token account should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidPoolTokenAccount");
+    }
+  });
+
+  it("Deposit creates the user's associated token account if it does not exist", async () => {
+    const newUser = Keypair.generate();
+    await requestAirdrop(connection, newUser.publicKey, 10);
+    await addUserToWhitelist(program, newUser.publicKey);
+
+    const newUserPoolTokenATA = await getAssociatedTokenAddress(
+      stakePoolInfo.accounts.poolMintAccount,
+      newUser.publicKey
+    );
+    assert.isNull(await connection.getAccountInfo(newUserPoolTokenATA));
+
+    await program.methods
+      .deposit(new BN(1 * LAMPORTS_PER_SOL), null, null)
+      .accounts({
+        user: newUser.publicKey,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+        userPoolTokenAccount: newUserPoolTokenATA,
+        feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      })
+      .signers([newUser])
+      .rpc();
+
+    const newUserPoolTokenAccount = await getAccount(connection, newUserPoolTokenATA);
+    assert.strictEqual(newUserPoolTokenAccount.owner.toBase58(), newUser.publicKey.toBase58());
+    assert(Number(newUserPoolTokenAccount.amount) > 0);
+  });
+
+  describe("Deposit for a beneficiary", () => {
+    const beneficiary = Keypair.generate();
+    let beneficiaryPoolTokenATA: PublicKey;
+
+    function depositForAccounts(beneficiaryInfo: PublicKey, beneficiaryPoolTokenAccount: PublicKey) {
+      return {
+        payer: user.publicKey,
+        beneficiaryInfo,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+        beneficiaryPoolTokenAccount: beneficiaryPoolTokenAccount,
+        feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      };
+    }
+
+    before(async () => {
+      // the beneficiary's associated token account does not exist until the first deposit for them
+      beneficiaryPoolTokenATA = await getAssociatedTokenAddress(
+        stakePoolInfo.accounts.poolMintAccount,
+        beneficiary.publicKey
+      );
+    });
+
+    it("Deposit for a blacklisted beneficiary should fail", async () => {
+      const blacklistedBeneficiary = Keypair.generate();
+      await program.methods
+        .addUserToBlacklist(blacklistedBeneficiary.publicKey, { other: {} }, Array(32).fill(0))
+        .rpc();
+
+      try {
+        await program.methods
+          .depositFor(blacklistedBeneficiary.publicKey, new BN(1 * LAMPORTS_PER_SOL), null)
+          .accounts(depositForAccounts(blacklistedBeneficiary.publicKey, beneficiaryPoolTokenATA))
+          .signers([user])
+          .rpc();
+
+        throw new Error("Deposit for a blacklisted beneficiary should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "UserNotWhitelisted");
+      }
+    });
+
+    it("Deposit for a beneficiary to a token account they do not own should fail", async () => {
+      await addUserToWhitelist(program, beneficiary.publicKey);
+
+      try {
+        await program.methods
+          .depositFor(beneficiary.publicKey, new BN(1 * LAMPORTS_PER_SOL), null)
+          .accounts(depositForAccounts(beneficiary.publicKey, userPoolTokenATA))
+          .signers([user])
+          .rpc();
+
+        throw new Error("Deposit for a beneficiary to the payer's token account should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "InvalidPoolTokenAccount");
+      }
+    });
+
+    it("Deposit SOL for a whitelisted beneficiary creates their token account", async () => {
+      assert.isNull(await connection.getAccountInfo(beneficiaryPoolTokenATA));
+
+      const depositAmount = new BN(1 * LAMPORTS_PER_SOL);
+      const userPoolTokensPre = (await getAccount(connection, userPoolTokenATA)).amount;
+      const tx = await program.methods
+        .depositFor(beneficiary.publicKey, depositAmount, null)
+        .accounts(depositForAccounts(beneficiary.publicKey, beneficiaryPoolTokenATA))
+        .transaction();
+
+      const txHash = await provider.sendAndConfirm(tx, [user], {
+        commitment: "confirmed",
+      });
+
+      // verify the payer created the beneficiary's token account, which received the pool tokens while the payer's did not
+      const beneficiaryPoolTokenAccount = await getAccount(connection, beneficiaryPoolTokenATA);
+      assert(Number(beneficiaryPoolTokenAccount.amount) > 0);
+      assert.strictEqual((await getAccount(connection, userPoolTokenATA)).amount, userPoolTokensPre);
+
+      // verify the deposit counts towards the beneficiary's deposits
+      const [beneficiaryPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user"), beneficiary.publicKey.toBuffer()],
+        program.programId
+      );
+      const beneficiaryStatus = await program.account.userStatus.fetch(beneficiaryPDA);
+      assert.strictEqual(beneficiaryStatus.totalDepositedLamports.toNumber(), depositAmount.toNumber());
+
+      // verify the DepositedFor event records both parties
+      const event = await getEvent(program, txHash, "depositedFor");
+      assert.ok(event);
+      assert.strictEqual(event.data.payer.toBase58(), user.publicKey.toBase58());
+      assert.strictEqual(event.data.beneficiary.toBase58(), beneficiary.publicKey.toBase58());
+      assert.strictEqual(event.data.amount.toNumber(), depositAmount.toNumber());
+    });
+  });
+
+  describe("KYC attestations", () => {
+    const attester = Keypair.generate();
+    const attestedUser = Keypair.generate();
+    const attestedUserLimits = {
+      maxLifetimeLamports: new BN(5 * LAMPORTS_PER_SOL),
+      maxEpochLamports: null,
+    };
+
+    // returns a deposit transaction carrying an Ed25519 instruction that verifies the attestation signed by the signer
+    async function depositWithAttestationTx(
+      attestation: {
+        programId: PublicKey;
+        access: PublicKey;
+        user: PublicKey;
+        expiry: BN;
+        depositLimits: typeof attestedUserLimits;
+      },
+      signer: Keypair
+    ) {
+      const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: signer.secretKey,
+        message: kycAttestationMessage(attestation),
+      });
+      const depositIx = await program.methods
+        .depositWithAttestation(new BN(1 * LAMPORTS_PER_SOL), null, attestation)
+        .accounts({
+          user: attestedUser.publicKey,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+          userPoolTokenAccount: await getAssociatedTokenAddress(
+            stakePoolInfo.accounts.poolMintAccount,
+            attestedUser.publicKey
+          ),
+          feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+          poolMint: stakePoolInfo.accounts.poolMintAccount,
+          referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        })
+        .instruction();
+      return new Transaction().add(ed25519Ix, depositIx);
+    }
+
+    async function attestation(expiresIn: number = 3600) {
+      const now = await connection.getBlockTime(await connection.getSlot());
+      return {
+        programId: program.programId,
+        access: PublicKey.findProgramAddressSync([Buffer.from("access")], program.programId)[0],
+        user: attestedUser.publicKey,
+        expiry: new BN(now + expiresIn),
+        depositLimits: attestedUserLimits,
+      };
+    }
+
+    before(async () => {
+      await requestAirdrop(connection, attestedUser.publicKey, 10);
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          createAssociatedTokenAccountInstruction(
+            attestedUser.publicKey,
+            await getAssociatedTokenAddress(
+              stakePoolInfo.accounts.poolMintAccount,
+              attestedUser.publicKey
+            ),
+            attestedUser.publicKey,
+            stakePoolInfo.accounts.poolMintAccount
+          )
+        ),
+        [attestedUser]
+      );
+      await program.methods.setAttester(attester.publicKey).rpc();
+    });
+
+    it("Deposit with an attestation signed by another key should fail", async () => {
+      try {
+        await provider.sendAndConfirm(
+          await depositWithAttestationTx(await attestation(), Keypair.generate()),
+          [attestedUser]
+        );
+
+        throw new Error("Deposit with an attestation signed by another key should fail");
+      } catch (e) {
+        assert.ok(e.logs.some((log: string) => log.includes("InvalidAttestation")));
+      }
+    });
+
+    it("Deposit with an attestation issued for another program should fail", async () => {
+      try {
+        await provider.sendAndConfirm(
+          await depositWithAttestationTx(
+            { ...(await attestation()), programId: Keypair.generate().publicKey },
+            attester
+          ),
+          [attestedUser]
+        );
+
+        throw new Error("Deposit with an attestation issued for another program should fail");
+      } catch (e) {
+        assert.ok(e.logs.some((log: string) => log.includes("InvalidAttestation")));
+      }
+    });
+
+    it("Deposit with an expired attestation should fail", async () => {
+      try {
+        await provider.sendAndConfirm(
+          await depositWithAttestationTx(await attestation(-60), attester),
+          [attestedUser]
+        );
+
+        throw new Error("Deposit with an expired attestation should fail");
+      } catch (e) {
+        assert.ok(e.logs.some((log: string) => log.includes("AttestationExpired")));
+      }
+    });
+
+    it("Deposit with an attestation whitelists the user", async () => {
+      const kycAttestation = await attestation();
+      const txHash = await provider.sendAndConfirm(
+        await depositWithAttestationTx(kycAttestation, attester),
+        [attestedUser],
+        { commitment: "confirmed" }
+      );
+
+      // verify the user was whitelisted with the attested expiry and limits
+      const [attestedUserPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user"), attestedUser.publicKey.toBuffer()],
+        program.programId
+      );
+      const userStatus = await program.account.userStatus.fetch(attestedUserPDA);
+      assert.deepEqual(userStatus.status, { whitelisted: {} });
+      assert.strictEqual(userStatus.whitelistExpiry.toNumber(), kycAttestation.expiry.toNumber());
+      assert.strictEqual(
+        userStatus.depositLimits.maxLifetimeLamports.toNumber(),
+        attestedUserLimits.maxLifetimeLamports.toNumber()
+      );
+      assert.strictEqual(userStatus.totalDepositedLamports.toNumber(), 1 * LAMPORTS_PER_SOL);
+
+      // verify the WhitelistingStatusChanged event was emitted
+      const event = await getEvent(program, txHash, "whitelistingStatusChanged");
+      assert.ok(event);
+      assert.strictEqual(event.data.user.toBase58(), attestedUser.publicKey.toBase58());
+      assert.deepEqual(event.data.newStatus, { whitelisted: {} });
+    });
+
+    it("Deposit with a new attestation refreshes the expiry of a whitelisted user and keeps their limits", async () => {
+      const newLimits = { maxLifetimeLamports: new BN(8 * LAMPORTS_PER_SOL), maxEpochLamports: null };
+      const kycAttestation = { ...(await attestation(7200)), depositLimits: newLimits };
+      const txHash = await provider.sendAndConfirm(
+        await depositWithAttestationTx(kycAttestation, attester),
+        [attestedUser],
+        { commitment: "confirmed" }
+      );
+
+      const [attestedUserPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user"), attestedUser.publicKey.toBuffer()],
+        program.programId
+      );
+      const userStatus = await program.account.userStatus.fetch(attestedUserPDA);
+      assert.strictEqual(userStatus.whitelistExpiry.toNumber(), kycAttestation.expiry.toNumber());
+      assert.strictEqual(
+        userStatus.depositLimits.maxLifetimeLamports.toNumber(),
+        attestedUserLimits.maxLifetimeLamports.toNumber()
+      );
+
+      const event = await getEvent(program, txHash, "whitelistExpirySet");
+      assert.ok(event);
+      assert.strictEqual(event.data.expiry.toNumber(), kycAttestation.expiry.toNumber());
+    });
+
+    it("Deposit by a cleared user with a valid attestation should fail", async () => {
+      await program.methods.clearUserStatus(attestedUser.publicKey, { userRequest: {} }, Array(32).fill(0)).rpc();
+      try {
+        await provider.sendAndConfirm(
+          await depositWithAttestationTx(await attestation(), attester),
+          [attestedUser]
+        );
+
+        throw new Error("Cleared user should not be restored by an attestation");
+      } catch (e) {
+        assert.ok(e.logs.some((log: string) => log.includes("UserStatusCleared")));
+      }
+    });
+
+    it("Deposit by a blacklisted user with a valid attestation should fail", async () => {
+      await program.methods.addUserToBlacklist(attestedUser.publicKey, { other: {} }, Array(32).fill(0)).rpc();
+      try {
+        await provider.sendAndConfirm(
+          await depositWithAttestationTx(await attestation(), attester),
+          [attestedUser]
+        );
+
+        throw new Error("Blacklisted user should not be able to deposit");
+      } catch (e) {
+        assert.ok(e.logs.some((log: string) => log.includes("UserBlacklisted")));
+      }
+    });
+
+    after(async () => {
+      await program.methods.setAttester(null).rpc();
+    });
+  });
+
   it("Deposit SOL directly to the stake pool should fail", async () => {
     const depositLamports = 3 * LAMPORTS_PER_SOL;
 
@@ -691,7 +1527,7 @@ describe("staking", () => {
     assert.equal(transientStakeAccountBalancePre, 0);
 
     const tx = await program.methods
-      .depositToSpecificValidator(depositAmount)
+      .depositToSpecificValidator(depositAmount, new BN(transientStakeSeed), new BN(ephemeralStakeSeed), null)
       .accounts({
         user: user.publicKey,
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -800,7 +1636,7 @@ describe("staking", () => {
     const [transientStakeAccount] = PublicKey.findProgramAddressSync(
       [
         Buffer.from("transient"),
-        firstValidator.vote_account_address.toBuffer(),
+        validator.publicKey.toBuffer(),
         stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
         new BN(transientStakeSeed).toArrayLike(Buffer, "le", 8),
       ],
@@ -834,7 +1670,7 @@ describe("staking", () => {
 
     try {
       const tx = await program.methods
-        .depositToSpecificValidator(depositAmount)
+        .depositToSpecificValidator(depositAmount, new BN(transientStakeSeed), new BN(ephemeralStakeSeed), null)
         .accounts({
           user: user.publicKey,
           stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -0,0 +1,244 @@
+import * as anchor from "@coral-xyz/anchor";
+import { initStaker, getEvent, queueAction, PAUSE_ALL } from "./helpers";
+import { assert } from "chai";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { Staker } from "../target/types/staker";
+
+describe("Timelock", () => {
+
+  const provider = anchor.AnchorProvider.env();
+
+  let program: anchor.Program<Staker>;
+  let accessAddress: PublicKey;
+  let user: Keypair;
+
+  before(async () => {
+    anchor.setProvider(provider);
+    user = Keypair.generate();
+    program = await initStaker(provider.wallet.publicKey, Keypair.generate().publicKey);
+
+    [accessAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("access")],
+      program.programId
+    );
+
+    await provider.connection.requestAirdrop(user.publicKey, 2 * LAMPORTS_PER_SOL);
+  });
+
+  it("Can queue an action", async () => {
+    const pendingOwner = Keypair.generate().publicKey;
+    const accessBefore = await program.account.access.fetch(accessAddress);
+    const [pendingActionAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pending_action"), accessBefore.nextActionId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const txHash = await program.methods
+      .queueAction({ setPendingOwner: { pendingOwner, deadline: null } })
+      .accountsPartial({
+        pendingAction: pendingActionAddress,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    // verify the ActionQueued event
+    const event = await getEvent(program, txHash, "actionQueued");
+    assert.ok(event);
+    assert.strictEqual(event.data.id.toNumber(), accessBefore.nextActionId.toNumber());
+    assert.strictEqual(event.data.action.setPendingOwner.pendingOwner.toString(), pendingOwner.toString());
+
+    // check the pending action is stored and the next action ID is incremented
+    const pendingAction = await program.account.pendingAction.fetch(pendingActionAddress);
+    assert.strictEqual(pendingAction.id.toNumber(), accessBefore.nextActionId.toNumber());
+    assert.strictEqual(pendingAction.executeAfter.toNumber(), event.data.executeAfter.toNumber());
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.nextActionId.toNumber(), accessBefore.nextActionId.toNumber() + 1);
+  });
+
+  it("Non-owner queueing an action fails", async () => {
+    try {
+      await queueAction(program, { unpause: { flags: PAUSE_ALL } }, user);
+      throw new Error("Queueing an action should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Queueing an invalid timelock delay fails", async () => {
+    try {
+      await queueAction(program, { setTimelockDelay: { delay: new anchor.BN(-1) } });
+      throw new Error("Queueing an action should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidTimelockDelay");
+    }
+  });
+
+  it("Executing a different action than the queued one fails", async () => {
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline: null } });
+    try {
+      await program.methods
+        .setPendingOwner(Keypair.generate().publicKey, null)
+        .accounts({ pendingAction })
+        .rpc();
+      throw new Error("Setting the pending owner should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "PendingActionMismatch");
+    }
+  });
+
+  it("Can cancel a pending action", async () => {
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline: null } });
+    const { id } = await program.account.pendingAction.fetch(pendingAction);
+
+    const txHash = await program.methods
+      .cancelAction()
+      .accountsPartial({
+        owner: provider.wallet.publicKey,
+        pendingAction,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    // verify the ActionCancelled event
+    const event = await getEvent(program, txHash, "actionCancelled");
+    assert.ok(event);
+    assert.strictEqual(event.data.id.toNumber(), id.toNumber());
+    assert.strictEqual(event.data.cancelledBy.toString(), provider.wallet.publicKey.toString());
+
+    // check the pending action is closed and can no longer be executed
+    assert.isNull(await provider.connection.getAccountInfo(pendingAction));
+    try {
+      await program.methods
+        .setPendingOwner(user.publicKey, null)
+        .accounts({ pendingAction })
+        .rpc();
+      throw new Error("Setting the pending owner should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "AccountNotInitialized");
+    }
+  });
+
+  it("Non-owner cancelling a pending action fails", async () => {
+    const pendingAction = await queueAction(program, { unpause: { flags: PAUSE_ALL } });
+    try {
+      await program.methods
+        .cancelAction()
+        .accountsPartial({
+          signer: user.publicKey,
+          owner: provider.wallet.publicKey,
+          pendingAction,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Cancelling the action should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Guardian can cancel a pending action", async () => {
+    const guardian = Keypair.generate();
+    await program.methods.addGuardian(guardian.publicKey).rpc();
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline: null } });
+
+    const txHash = await program.methods
+      .cancelAction()
+      .accountsPartial({
+        signer: guardian.publicKey,
+        owner: provider.wallet.publicKey,
+        pendingAction,
+      })
+      .signers([guardian])
+      .rpc({ commitment: "confirmed" });
+
+    const event = await getEvent(program, txHash, "actionCancelled");
+    assert.ok(event);
+    assert.strictEqual(event.data.cancelledBy.toString(), guardian.publicKey.toString());
+    assert.isNull(await provider.connection.getAccountInfo(pendingAction));
+
+    await program.methods.removeGuardian(guardian.publicKey).rpc();
+  });
+
+  it("Guardian cancelling an unpause or a timelock delay change fails", async () => {
+    const guardian = Keypair.generate();
+    await program.methods.addGuardian(guardian.publicKey).rpc();
+
+    for (const action of [{ unpause: { flags: PAUSE_ALL } }, { setTimelockDelay: { delay: new anchor.BN(3) } }]) {
+      const pendingAction = await queueAction(program, action);
+      try {
+        await program.methods
+          .cancelAction()
+          .accountsPartial({
+            signer: guardian.publicKey,
+            owner: provider.wallet.publicKey,
+            pendingAction,
+          })
+          .signers([guardian])
+          .rpc();
+        throw new Error("Cancelling the action should fail");
+      } catch (e) {
+        assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+      }
+
+      // the owner can still cancel it
+      await program.methods
+        .cancelAction()
+        .accountsPartial({
+          owner: provider.wallet.publicKey,
+          pendingAction,
+        })
+        .rpc();
+    }
+
+    await program.methods.removeGuardian(guardian.publicKey).rpc();
+  });
+
+  it("Can set the timelock delay", async () => {
+    const delay = new anchor.BN(3);
+    const pendingAction = await queueAction(program, { setTimelockDelay: { delay } });
+
+    const txHash = await program.methods
+      .setTimelockDelay(delay)
+      .accounts({ pendingAction })
+      .rpc({ commitment: "confirmed" });
+
+    // verify the TimelockDelaySet and ActionExecuted events
+    const event = await getEvent(program, txHash, "timelockDelaySet");
+    assert.ok(event);
+    assert.strictEqual(event.data.oldDelay.toNumber(), 0);
+    assert.strictEqual(event.data.newDelay.toNumber(), delay.toNumber());
+    assert.ok(await getEvent(program, txHash, "actionExecuted"));
+
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.timelockDelay.toNumber(), delay.toNumber());
+
+    // the executed action is closed
+    assert.isNull(await provider.connection.getAccountInfo(pendingAction));
+  });
+
+  it("Executing an action before its timelock elapses fails", async () => {
+    const pendingAction = await queueAction(program, { setPendingOwner: { pendingOwner: user.publicKey, deadline: null } });
+    try {
+      await program.methods
+        .setPendingOwner(user.publicKey, null)
+        .accounts({ pendingAction })
+        .rpc();
+      throw new Error("Setting the pending owner should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "TimelockNotElapsed");
+    }
+  });
+
+  it("Can execute an action once its timelock elapses", async () => {
+    const pendingAction = await queueAction(program, { setTimelockDelay: { delay: new anchor.BN(0) } });
+
+    // wait for the timelock delay to elapse
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+
+    await program.methods
+      .setTimelockDelay(new anchor.BN(0))
+      .accounts({ pendingAction })
+      .rpc();
+
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.timelockDelay.toNumber(), 0);
+  });
+});
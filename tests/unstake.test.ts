@@ -0,0 +1,458 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Staker } from "../target/types/staker";
+import {
+  STAKE_POOL_PROGRAM_ID,
+  initStaker,
+  createStakePool,
+  addUserToWhitelist,
+  requestAirdrop,
+  getEvent,
+  moveEpochForwardAndUpdatePool,
+  queueAction,
+} from "./helpers";
+import { CreateStakePoolResponse } from "./stake_pool/types";
+
+import { assert } from "chai";
+import { createAssociatedTokenAccountInstruction, getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
+
+describe("unstake tickets", () => {
+
+  const provider = anchor.AnchorProvider.env();
+  const connection = provider.connection;
+
+  let program: anchor.Program<Staker>;
+  let owner: anchor.Wallet;
+  let manager: Keypair;
+  let stakeManager: Keypair;
+  let user: Keypair;
+
+  let stakerAuthorityPDA: PublicKey;
+  let unstakeTicketPDA: PublicKey;
+  let unstakeEscrowPDA: PublicKey;
+  let unstakeQueuePDA: PublicKey;
+  let userPoolTokenATA: PublicKey;
+  let validatorStakeAccount: PublicKey;
+  let stakePoolInfo: CreateStakePoolResponse;
+
+  let validatorVoteAccount: PublicKey;
+
+  // derives the transient and ephemeral stake accounts for the given seeds
+  function stakeAccountsForSeeds(transientStakeSeed: number, ephemeralStakeSeed: number) {
+    const [transientStakeAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("transient"),
+        validatorVoteAccount.toBuffer(),
+        stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+        new BN(transientStakeSeed).toArrayLike(Buffer, "le", 8),
+      ],
+      STAKE_POOL_PROGRAM_ID
+    );
+    const [ephemeralStakeAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("ephemeral"),
+        stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+        new BN(ephemeralStakeSeed).toArrayLike(Buffer, "le", 8),
+      ],
+      STAKE_POOL_PROGRAM_ID
+    );
+    return { transientStakeAccount, ephemeralStakeAccount };
+  }
+
+  function validatorStakeAccounts(transientStakeSeed: number, ephemeralStakeSeed: number) {
+    return {
+      signer: stakeManager.publicKey,
+      validatorVoteAccount: validatorVoteAccount,
+      stakePool: stakePoolInfo.accounts.stakePoolAccount,
+      reserveStake: stakePoolInfo.accounts.reserveStakeAccount,
+      withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+      validatorList: stakePoolInfo.accounts.validatorListAccount,
+      validatorStakeAccount: validatorStakeAccount,
+      ...stakeAccountsForSeeds(transientStakeSeed, ephemeralStakeSeed),
+    };
+  }
+
+  function claimUnstakeAccounts() {
+    return {
+      user: user.publicKey,
+      userPoolTokenAccount: userPoolTokenATA,
+      stakePool: stakePoolInfo.accounts.stakePoolAccount,
+      withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+      poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+      feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      poolMint: stakePoolInfo.accounts.poolMintAccount,
+    };
+  }
+
+  before(async () => {
+
+    owner = provider.wallet as anchor.Wallet
+    manager = Keypair.generate();
+    stakeManager = Keypair.generate();
+    user = Keypair.generate();
+
+    // airdrop some SOL to the user and the stake manager
+    await requestAirdrop(connection, user.publicKey, 20);
+    await requestAirdrop(connection, stakeManager.publicKey, 1);
+
+    // get local validator account
+    const voteAccountsAll = await connection.getVoteAccounts();
+    let voteAccounts = voteAccountsAll?.current;
+    if (voteAccounts?.length == 0) {
+      throw new Error("No vote account found");
+    }
+    validatorVoteAccount = new anchor.web3.PublicKey(voteAccounts[0].votePubkey);
+    program = anchor.workspace.Staker as anchor.Program<Staker>;
+
+    // create the stake pool
+    const staker = Keypair.generate(); // initial staker authority
+    stakePoolInfo = await createStakePool(
+      program.programId,
+      manager,
+      staker,
+    );
+
+    // initialize the staker bound to the new stake pool
+    program = await initStaker(owner.publicKey, stakeManager.publicKey, stakePoolInfo.accounts.stakePoolAccount);
+
+    // derive the staker authority and unstake PDAs
+    [stakerAuthorityPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("staker")],
+      program.programId
+    );
+    [unstakeTicketPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("unstake_ticket"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    [unstakeEscrowPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("unstake_escrow")],
+      program.programId
+    );
+    [unstakeQueuePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("unstake_queue")],
+      program.programId
+    );
+
+    // sets stakerAuthorityPDA as the new staker authority of the pool
+    const setStakerIx = new TransactionInstruction({
+      programId: STAKE_POOL_PROGRAM_ID,
+      keys: [
+        { pubkey: stakePoolInfo.accounts.stakePoolAccount, isSigner: false, isWritable: true }, // Stake pool
+        { pubkey: manager.publicKey, isSigner: true, isWritable: false }, // Manager
+        { pubkey: stakerAuthorityPDA, isSigner: false, isWritable: false }, // The new pool staker authority
+      ],
+      data: Buffer.from(Uint8Array.of(13)), // SetStaker instruction index
+    });
+    await provider.sendAndConfirm(new Transaction().add(setStakerIx), [manager]);
+
+    // derive the validator stake account and add the validator to the pool
+    [validatorStakeAccount] = PublicKey.findProgramAddressSync([
+      validatorVoteAccount.toBuffer(),
+      stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+    ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    const validatorSeed = 0;
+    const pendingAction = await queueAction(program, { addValidator: { validatorVoteAccount, validatorSeed } });
+    await program.methods.addValidator(validatorSeed)
+      .accounts({
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        reserveStake: stakePoolInfo.accounts.reserveStakeAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        validatorList: stakePoolInfo.accounts.validatorListAccount,
+        validatorStakeAccount: validatorStakeAccount,
+        validatorVoteAccount: validatorVoteAccount,
+        pendingAction,
+      })
+      .signers([owner.payer])
+      .rpc();
+
+    // whitelist the user
+    await addUserToWhitelist(program, user.publicKey);
+
+    // user creates an associated token account to hold the stake pool tokens
+    userPoolTokenATA = await getAssociatedTokenAddress(
+      stakePoolInfo.accounts.poolMintAccount,
+      user.publicKey // Owner (user)
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          user.publicKey,
+          userPoolTokenATA,
+          user.publicKey,
+          stakePoolInfo.accounts.poolMintAccount
+        )
+      ),
+      [user]
+    );
+
+    // user deposits 10 SOL to the pool reserve account
+    await program.methods
+      .deposit(new BN(10 * LAMPORTS_PER_SOL), null, null)
+      .accounts({
+        user: user.publicKey,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        depositAuthority: stakePoolInfo.accounts.depositAuthorityAccount,
+        withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+        poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+        userPoolTokenAccount: userPoolTokenATA,
+        feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        referralFeeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      })
+      .signers([user])
+      .rpc();
+
+    // the stake manager stakes most of the reserve on the validator
+    await program.methods
+      .increaseValidatorStake(new BN(8 * LAMPORTS_PER_SOL), new BN(0), new BN(0))
+      .accounts(validatorStakeAccounts(0, 0))
+      .signers([stakeManager])
+      .rpc();
+
+    // wait for the stake to activate and update the pool
+    await moveEpochForwardAndUpdatePool(connection, stakePoolInfo.accounts, validatorVoteAccount);
+  });
+
+  it("Requests an unstake", async () => {
+    const poolTokens = new BN(5 * LAMPORTS_PER_SOL);
+    const userTokenAccountPre = await getAccount(connection, userPoolTokenATA);
+
+    const tx = await program.methods
+      .requestUnstake(poolTokens)
+      .accounts({
+        user: user.publicKey,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        userPoolTokenAccount: userPoolTokenATA,
+      })
+      .transaction();
+
+    const txHash = await provider.sendAndConfirm(tx, [user], {
+      commitment: "confirmed",
+    });
+
+    // verify the pool tokens were escrowed
+    const userTokenAccount = await getAccount(connection, userPoolTokenATA);
+    assert.equal(
+      Number(userTokenAccount.amount),
+      Number(userTokenAccountPre.amount) - poolTokens.toNumber()
+    );
+    const escrowAccount = await getAccount(connection, unstakeEscrowPDA);
+    assert.equal(Number(escrowAccount.amount), poolTokens.toNumber());
+
+    // verify the ticket records the SOL owed and the claimable epoch
+    const epoch = (await connection.getEpochInfo()).epoch;
+    const ticket = await program.account.unstakeTicket.fetch(unstakeTicketPDA);
+    assert.strictEqual(ticket.user.toBase58(), user.publicKey.toBase58());
+    assert.strictEqual(ticket.poolTokens.toNumber(), poolTokens.toNumber());
+    assert(ticket.lamports.toNumber() > 0);
+    assert.strictEqual(ticket.claimableEpoch.toNumber(), epoch + 1);
+
+    const queue = await program.account.unstakeQueue.fetch(unstakeQueuePDA);
+    assert.strictEqual(queue.pendingLamports.toNumber(), ticket.lamports.toNumber());
+
+    // verify the UnstakeRequested event was emitted with the correct data
+    const event = await getEvent(program, txHash, "unstakeRequested");
+    assert.ok(event);
+    assert.strictEqual(event.data.user.toBase58(), user.publicKey.toBase58());
+    assert.strictEqual(event.data.poolTokens.toNumber(), poolTokens.toNumber());
+    assert.strictEqual(event.data.lamports.toNumber(), ticket.lamports.toNumber());
+    assert.strictEqual(event.data.claimableEpoch.toNumber(), epoch + 1);
+  });
+
+  it("Claiming an unstake ticket before the claimable epoch fails", async () => {
+    try {
+      await program.methods
+        .claimUnstake()
+        .accounts(claimUnstakeAccounts())
+        .signers([user])
+        .rpc();
+
+      throw new Error("Claiming an unstake ticket before the claimable epoch should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UnstakeTicketNotClaimable");
+    }
+  });
+
+  it("Non stake-manager funding unstake tickets fails", async () => {
+    try {
+      await program.methods
+        .fundUnstakeTickets(new BN(1 * LAMPORTS_PER_SOL), new BN(1), new BN(1))
+        .accounts({ ...validatorStakeAccounts(1, 1), signer: user.publicKey })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Non stake-manager funding unstake tickets should fail");
+    } catch (e) {
+      // the stake_manager pda derived with this user, who is not the stake manager, does not exist.
+      assert.strictEqual(e.error.errorCode.code, "AccountNotInitialized");
+    }
+  });
+
+  it("Funding more than the SOL owed to unstake tickets fails", async () => {
+    const queue = await program.account.unstakeQueue.fetch(unstakeQueuePDA);
+    try {
+      await program.methods
+        .fundUnstakeTickets(queue.pendingLamports.addn(1), new BN(1), new BN(1))
+        .accounts(validatorStakeAccounts(1, 1))
+        .signers([stakeManager])
+        .rpc();
+
+      throw new Error("Funding more than the SOL owed to unstake tickets should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UnstakeFundingExceeded");
+    }
+  });
+
+  it("Stake manager funds unstake tickets", async () => {
+    const queuePre = await program.account.unstakeQueue.fetch(unstakeQueuePDA);
+
+    const tx = await program.methods
+      .fundUnstakeTickets(queuePre.pendingLamports, new BN(1), new BN(1))
+      .accounts(validatorStakeAccounts(1, 1))
+      .signers([stakeManager])
+      .transaction();
+
+    const txHash = await provider.sendAndConfirm(tx, [stakeManager], {
+      commitment: "confirmed",
+    });
+
+    const queue = await program.account.unstakeQueue.fetch(unstakeQueuePDA);
+    assert.strictEqual(queue.fundedLamports.toNumber(), queuePre.pendingLamports.toNumber());
+
+    // verify the UnstakeTicketsFunded event was emitted with the correct data
+    const event = await getEvent(program, txHash, "unstakeTicketsFunded");
+    assert.ok(event);
+    assert.strictEqual(event.data.validator.toBase58(), validatorVoteAccount.toBase58());
+    assert.strictEqual(event.data.amount.toNumber(), queuePre.pendingLamports.toNumber());
+  });
+
+  it("Increasing validator stake with the SOL funded for unstake tickets fails", async () => {
+    // wait for the decreased stake to deactivate and be merged into the reserve
+    await moveEpochForwardAndUpdatePool(connection, stakePoolInfo.accounts, validatorVoteAccount);
+
+    // staking all of the reserve beyond the funded SOL would leave less than its rent and the funded SOL
+    const queue = await program.account.unstakeQueue.fetch(unstakeQueuePDA);
+    const reserveBalance = await connection.getBalance(stakePoolInfo.accounts.reserveStakeAccount);
+    const amount = new BN(reserveBalance).sub(queue.fundedLamports);
+    try {
+      await program.methods
+        .increaseValidatorStake(amount, new BN(2), new BN(2))
+        .accounts(validatorStakeAccounts(2, 2))
+        .signers([stakeManager])
+        .rpc();
+
+      throw new Error("Increasing validator stake with the SOL funded for unstake tickets should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "ReserveFundedForUnstakeTickets");
+    }
+  });
+
+  it("Claims an unstake ticket once the epoch has passed", async () => {
+    const ticket = await program.account.unstakeTicket.fetch(unstakeTicketPDA);
+    const userBalancePre = await connection.getBalance(user.publicKey);
+    const userTokenAccountPre = await getAccount(connection, userPoolTokenATA);
+
+    const tx = await program.methods
+      .claimUnstake()
+      .accounts(claimUnstakeAccounts())
+      .transaction();
+
+    const txHash = await provider.sendAndConfirm(tx, [user], {
+      commitment: "confirmed",
+    });
+
+    // verify the user received at least the SOL owed, plus the rent of the closed ticket
+    const userBalance = await connection.getBalance(user.publicKey);
+    assert(userBalance - userBalancePre >= ticket.lamports.toNumber());
+
+    // verify the ticket was closed and the escrowed pool tokens were burned or returned to the user
+    const event = await getEvent(program, txHash, "unstakeClaimed");
+    assert.ok(event);
+    assert(event.data.poolTokens.toNumber() <= ticket.poolTokens.toNumber());
+    assert.isNull(await connection.getAccountInfo(unstakeTicketPDA));
+    const escrowAccount = await getAccount(connection, unstakeEscrowPDA);
+    assert.equal(Number(escrowAccount.amount), 0);
+    const userTokenAccount = await getAccount(connection, userPoolTokenATA);
+    assert.equal(
+      Number(userTokenAccount.amount),
+      Number(userTokenAccountPre.amount) + ticket.poolTokens.toNumber() - event.data.poolTokens.toNumber()
+    );
+
+    const queue = await program.account.unstakeQueue.fetch(unstakeQueuePDA);
+    assert.strictEqual(queue.pendingLamports.toNumber(), 0);
+    assert.strictEqual(queue.fundedLamports.toNumber(), 0);
+
+    // verify the UnstakeClaimed event was emitted with the correct data
+    assert.strictEqual(event.data.user.toBase58(), user.publicKey.toBase58());
+    assert(event.data.lamports.toNumber() >= ticket.lamports.toNumber());
+  });
+
+  it("Claiming an unfunded unstake ticket fails", async () => {
+    await program.methods
+      .requestUnstake(new BN(1 * LAMPORTS_PER_SOL))
+      .accounts({
+        user: user.publicKey,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        poolMint: stakePoolInfo.accounts.poolMintAccount,
+        userPoolTokenAccount: userPoolTokenATA,
+      })
+      .signers([user])
+      .rpc();
+    await moveEpochForwardAndUpdatePool(connection, stakePoolInfo.accounts, validatorVoteAccount);
+
+    try {
+      await program.methods
+        .claimUnstake()
+        .accounts(claimUnstakeAccounts())
+        .signers([user])
+        .rpc();
+
+      throw new Error("Claiming an unfunded unstake ticket should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UnstakeTicketNotFunded");
+    }
+  });
+
+  it("Cancels an unstake ticket", async () => {
+    const ticket = await program.account.unstakeTicket.fetch(unstakeTicketPDA);
+    const userTokenAccountPre = await getAccount(connection, userPoolTokenATA);
+
+    const tx = await program.methods
+      .cancelUnstake()
+      .accounts({
+        user: user.publicKey,
+        userPoolTokenAccount: userPoolTokenATA,
+      })
+      .transaction();
+
+    const txHash = await provider.sendAndConfirm(tx, [user], {
+      commitment: "confirmed",
+    });
+
+    // verify the escrowed pool tokens were returned and the ticket was closed
+    const userTokenAccount = await getAccount(connection, userPoolTokenATA);
+    assert.equal(
+      Number(userTokenAccount.amount),
+      Number(userTokenAccountPre.amount) + ticket.poolTokens.toNumber()
+    );
+    const escrowAccount = await getAccount(connection, unstakeEscrowPDA);
+    assert.equal(Number(escrowAccount.amount), 0);
+    assert.isNull(await connection.getAccountInfo(unstakeTicketPDA));
+
+    const queue = await program.account.unstakeQueue.fetch(unstakeQueuePDA);
+    assert.strictEqual(queue.pendingLamports.toNumber(), 0);
+    assert.strictEqual(queue.fundedLamports.toNumber(), 0);
+
+    // verify the UnstakeCancelled event was emitted with the correct data
+    const event = await getEvent(program, txHash, "unstakeCancelled");
+    assert.ok(event);
+    assert.strictEqual(event.data.user.toBase58(), user.publicKey.toBase58());
+    assert.strictEqual(event.data.poolTokens.toNumber(), ticket.poolTokens.toNumber());
+    assert.strictEqual(event.data.lamports.toNumber(), ticket.lamports.toNumber());
+  });
+});
@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { BN } from "@coral-xyz/anchor";
 import { Keypair, LAMPORTS_PER_SOL, PublicKey, StakeProgram, Transaction, TransactionInstruction } from '@solana/web3.js';
 import { Staker } from "../target/types/staker";
-import { STAKE_POOL_PROGRAM_ID, initStaker, createStakePool, addUserToWhitelist, requestAirdrop, getEvent, moveEpochForwardAndUpdatePool } from "./helpers";
+import { STAKE_POOL_PROGRAM_ID, initStaker, createStakePool, addUserToWhitelist, requestAirdrop, getEvent, moveEpochForwardAndUpdatePool, queueAction } from "./helpers";
 import { CreateStakePoolResponse } from "./stake_pool/types";
 
 import { assert } from "chai";
@@ -41,7 +41,7 @@ describe("validator stake", () => {
       throw new Error("No vote account found");
     }
     validatorVoteAccount = new anchor.web3.PublicKey(voteAccounts[0].votePubkey);
-    program = await initStaker(owner.publicKey, stakeManager.publicKey);
+    program = anchor.workspace.Staker as anchor.Program<Staker>;
 
     // create the stake pool
     const staker = Keypair.generate(); // initial staker authority
@@ -51,6 +51,9 @@ describe("validator stake", () => {
       staker,
     );
 
+    // initialize the staker bound to the new stake pool
+    program = await initStaker(owner.publicKey, stakeManager.publicKey, stakePoolInfo.accounts.stakePoolAccount);
+
     // derive the staker authority PDA
     [stakerAuthorityPDA] = PublicKey.findProgramAddressSync(
       [Buffer.from("staker")],
@@ -82,6 +85,7 @@ describe("validator stake", () => {
 
     // add the validator to the pool
     const validatorSeed = 0;
+    const pendingAction = await queueAction(program, { addValidator: { validatorVoteAccount, validatorSeed } });
     const addValidatorTx = await program.methods.addValidator(validatorSeed)
       .accounts({
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -90,6 +94,7 @@ describe("validator stake", () => {
         validatorList: stakePoolInfo.accounts.validatorListAccount,
         validatorStakeAccount: validatorStakeAccount,
         validatorVoteAccount: validatorVoteAccount,
+        pendingAction,
       })
       .signers([owner.payer])
       .rpc();
@@ -117,7 +122,7 @@ describe("validator stake", () => {
     // user deposits 10 SOL to the pool reserve account
     const depositAmount = new BN(10 * LAMPORTS_PER_SOL);
     const depositTx = await program.methods
-      .deposit(depositAmount)
+      .deposit(depositAmount, null, null)
       .accounts({
         user: user.publicKey,
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -171,7 +176,7 @@ describe("validator stake", () => {
     );
 
     try {
-      await program.methods.increaseValidatorStake(new BN(1 * LAMPORTS_PER_SOL))
+      await program.methods.increaseValidatorStake(new BN(1 * LAMPORTS_PER_SOL), new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
       .accounts({
         signer: user.publicKey,
         validatorVoteAccount: validatorVoteAccount,
@@ -235,7 +240,7 @@ describe("validator stake", () => {
 
     // increase the validator stake by 3 SOL
     const stakeIncreaseAmount = new BN(3 * LAMPORTS_PER_SOL);
-    const tx = await program.methods.increaseValidatorStake(stakeIncreaseAmount)
+    const tx = await program.methods.increaseValidatorStake(stakeIncreaseAmount, new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
       .accounts({
         signer: stakeManager.publicKey,
         validatorVoteAccount: validatorVoteAccount,
@@ -271,6 +276,114 @@ describe("validator stake", () => {
     assert.equal(poolReserveBalance, poolReserveBalancePre - stakeIncreaseAmount.toNumber() - stakeAccountRent);
   });
 
+  it("Increasing validator stake with a mismatched transient stake seed fails", async () => {
+    // derive the transient stake account PDA for seed 0
+    const [transientStakeAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("transient"),
+        validatorVoteAccount.toBuffer(),
+        stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+        new BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    // derive the ephemeral stake account PDA
+    const ephemeralStakeSeed = 0;
+    const [ephemeralStakeAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("ephemeral"),
+        stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+        new BN(ephemeralStakeSeed).toArrayLike(Buffer, "le", 8),
+      ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    // derive the validator stake account PDA
+    const [validatorStakeAccount] = PublicKey.findProgramAddressSync([
+      validatorVoteAccount.toBuffer(),
+      stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+    ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    try {
+      // use transient stake seed 1 with the transient stake account of seed 0
+      await program.methods.increaseValidatorStake(new BN(1 * LAMPORTS_PER_SOL), new BN(1), new BN(ephemeralStakeSeed))
+        .accounts({
+          signer: stakeManager.publicKey,
+          validatorVoteAccount: validatorVoteAccount,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          reserveStake: stakePoolInfo.accounts.reserveStakeAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          validatorList: stakePoolInfo.accounts.validatorListAccount,
+          validatorStakeAccount: validatorStakeAccount,
+          transientStakeAccount: transientStakeAccount,
+          ephemeralStakeAccount: ephemeralStakeAccount,
+        })
+        .signers([stakeManager])
+        .rpc();
+
+      throw new Error("Increase validator stake should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidTransientStakeAccount");
+    }
+  });
+
+  it("Increasing validator stake with a mismatched ephemeral stake seed fails", async () => {
+    // derive the transient stake account PDA
+    const transientStakeSeed = 0;
+    const [transientStakeAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("transient"),
+        validatorVoteAccount.toBuffer(),
+        stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+        new BN(transientStakeSeed).toArrayLike(Buffer, "le", 8),
+      ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    // derive the ephemeral stake account PDA for seed 0
+    const [ephemeralStakeAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("ephemeral"),
+        stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+        new BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    // derive the validator stake account PDA
+    const [validatorStakeAccount] = PublicKey.findProgramAddressSync([
+      validatorVoteAccount.toBuffer(),
+      stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+    ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    try {
+      // use ephemeral stake seed 1 with the ephemeral stake account of seed 0
+      await program.methods.increaseValidatorStake(new BN(1 * LAMPORTS_PER_SOL), new BN(transientStakeSeed), new BN(1))
+        .accounts({
+          signer: stakeManager.publicKey,
+          validatorVoteAccount: validatorVoteAccount,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          reserveStake: stakePoolInfo.accounts.reserveStakeAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          validatorList: stakePoolInfo.accounts.validatorListAccount,
+          validatorStakeAccount: validatorStakeAccount,
+          transientStakeAccount: transientStakeAccount,
+          ephemeralStakeAccount: ephemeralStakeAccount,
+        })
+        .signers([stakeManager])
+        .rpc();
+
+      throw new Error("Increase validator stake should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidEphemeralStakeAccount");
+    }
+  });
+
   it("Increases validator stake again in the same epoch", async () => {
     // derive the transient stake account PDA
     const transientStakeSeed = 0;
@@ -311,7 +424,7 @@ describe("validator stake", () => {
 
     // increase the validator stake by 2 SOL
     const stakeIncreaseAmount = new BN(2 * LAMPORTS_PER_SOL);
-    const tx = await program.methods.increaseValidatorStake(stakeIncreaseAmount)
+    const tx = await program.methods.increaseValidatorStake(stakeIncreaseAmount, new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
       .accounts({
         signer: stakeManager.publicKey,
         validatorVoteAccount: validatorVoteAccount,
@@ -419,7 +532,7 @@ describe("validator stake", () => {
     );
 
     try {
-      await program.methods.decreaseValidatorStake(new BN(2 * LAMPORTS_PER_SOL))
+      await program.methods.decreaseValidatorStake(new BN(2 * LAMPORTS_PER_SOL), new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
         .accounts({
           signer: user.publicKey,
           validatorVoteAccount: validatorVoteAccount,
@@ -478,7 +591,7 @@ describe("validator stake", () => {
 
     // decrease the validator stake by 2 SOL
     const stakeDecreaseAmount = new BN(2 * LAMPORTS_PER_SOL);
-    const tx = await program.methods.decreaseValidatorStake(stakeDecreaseAmount)
+    const tx = await program.methods.decreaseValidatorStake(stakeDecreaseAmount, new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
       .accounts({
         signer: stakeManager.publicKey,
         validatorVoteAccount: validatorVoteAccount,
@@ -547,7 +660,7 @@ describe("validator stake", () => {
 
     try {
       // try to decrease the validator stake by 1 SOL
-      await program.methods.decreaseValidatorStake(new BN(1 * LAMPORTS_PER_SOL))
+      await program.methods.decreaseValidatorStake(new BN(1 * LAMPORTS_PER_SOL), new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
         .accounts({
           signer: stakeManager.publicKey,
           validatorVoteAccount: validatorVoteAccount,
@@ -611,7 +724,7 @@ describe("validator stake", () => {
     const maxDecreaseAmount = new BN(stakeAccountBalancePre - validatorMinStake - stakeAccountRent);
 
     // decrease the validator stake by the maxDecreaseAmount
-    const tx = await program.methods.decreaseValidatorStake(maxDecreaseAmount)
+    const tx = await program.methods.decreaseValidatorStake(maxDecreaseAmount, new BN(transientStakeSeed), new BN(ephemeralStakeSeed))
       .accounts({
         signer: stakeManager.publicKey,
         validatorVoteAccount: validatorVoteAccount,
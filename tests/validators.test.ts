@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { BN } from "@coral-xyz/anchor";
 import { Keypair, PublicKey, Transaction, TransactionInstruction } from '@solana/web3.js';
 import { Staker } from "../target/types/staker";
-import { STAKE_POOL_PROGRAM_ID, initStaker, createStakePool, updateValidatorListBalance, getStakePool, decodeValidatorListAccount, getEvent } from "./helpers";
+import { STAKE_POOL_PROGRAM_ID, initStaker, createStakePool, updateValidatorListBalance, getStakePool, decodeValidatorListAccount, getEvent, queueAction } from "./helpers";
 import { CreateStakePoolResponse, StakeStatus } from "./stake_pool/types";
 
 import { assert } from "chai";
@@ -53,6 +53,100 @@ describe("validators", () => {
   });
 
 
+  it("Non-owner setting the stake pool fails", async () => {
+    const user = Keypair.generate();
+    const pendingAction = await queueAction(program, {
+      setStakePool: { stakePool: stakePoolInfo.accounts.stakePoolAccount },
+    });
+    try {
+      await program.methods.setStakePool()
+        .accountsPartial({
+          owner: user.publicKey,
+          pendingAction,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        })
+        .signers([user])
+        .rpc();
+
+      throw new Error("Set stake pool should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+
+  it("Setting an account that is not a stake pool fails", async () => {
+    const pendingAction = await queueAction(program, {
+      setStakePool: { stakePool: stakePoolInfo.accounts.validatorListAccount },
+    });
+    try {
+      await program.methods.setStakePool()
+        .accounts({
+          pendingAction,
+          stakePool: stakePoolInfo.accounts.validatorListAccount,
+        })
+        .rpc();
+
+      throw new Error("Set stake pool should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidStakePool");
+    }
+  });
+
+
+  it("Setting the stake pool without a matching pending action fails", async () => {
+    const pendingAction = await queueAction(program, {
+      setStakePool: { stakePool: Keypair.generate().publicKey },
+    });
+    try {
+      await program.methods.setStakePool()
+        .accounts({
+          pendingAction,
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+        })
+        .rpc();
+
+      throw new Error("Set stake pool should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "PendingActionMismatch");
+    }
+  });
+
+
+  it("Sets the stake pool", async () => {
+    const [accessAddress] = PublicKey.findProgramAddressSync([Buffer.from("access")], program.programId);
+    const accessBefore = await program.account.access.fetch(accessAddress);
+    const pendingAction = await queueAction(program, {
+      setStakePool: { stakePool: stakePoolInfo.accounts.stakePoolAccount },
+    });
+    const tx = await program.methods.setStakePool()
+      .accounts({
+        pendingAction,
+        stakePool: stakePoolInfo.accounts.stakePoolAccount,
+      })
+      .transaction();
+
+    const txHash = await program.provider.sendAndConfirm(tx, [], {
+      commitment: "confirmed",
+    });
+
+    // verify the StakePoolSet event was emitted with the correct data
+    const event = await getEvent(program, txHash, "stakePoolSet");
+    assert.ok(event);
+    assert.strictEqual(event.data.oldStakePool.toBase58(), accessBefore.stakePool.toBase58());
+    assert.strictEqual(event.data.newStakePool.toBase58(), stakePoolInfo.accounts.stakePoolAccount.toBase58());
+
+    // verify the pool accounts were recorded
+    const access = await program.account.access.fetch(accessAddress);
+    assert.strictEqual(access.stakePool.toBase58(), stakePoolInfo.accounts.stakePoolAccount.toBase58());
+    assert.strictEqual(access.poolMint.toBase58(), stakePoolInfo.accounts.poolMintAccount.toBase58());
+    assert.strictEqual(access.reserveStake.toBase58(), stakePoolInfo.accounts.reserveStakeAccount.toBase58());
+    assert.strictEqual(access.validatorList.toBase58(), stakePoolInfo.accounts.validatorListAccount.toBase58());
+    assert.strictEqual(access.managerFeeAccount.toBase58(), stakePoolInfo.accounts.feesTokenAccount.toBase58());
+    assert.strictEqual(access.withdrawAuthority.toBase58(), stakePoolInfo.accounts.withdrawAuthorityAccount.toBase58());
+  });
+
+
   it("Sets staker PDA as the new staker authorithy", async () => {
     // build the SetStaker instruction
     const setStakerIx = new TransactionInstruction({
@@ -87,6 +181,7 @@ describe("validators", () => {
 
     try {
       const validatorSeed = 0;
+      const pendingAction = await queueAction(program, { addValidator: { validatorVoteAccount, validatorSeed } });
       await program.methods.addValidator(validatorSeed)
         .accountsPartial({
           owner: user.publicKey,
@@ -96,6 +191,7 @@ describe("validators", () => {
           validatorList: stakePoolInfo.accounts.validatorListAccount,
           validatorStakeAccount: validatorStakeAccount,
           validatorVoteAccount: validatorVoteAccount,
+          pendingAction,
         })
         .signers([user])
         .rpc();
@@ -107,6 +203,37 @@ describe("validators", () => {
   });
 
 
+  it("Adding a validator with an invalid validator list fails", async () => {
+    const [validatorStakeAccount] = PublicKey.findProgramAddressSync([
+      validatorVoteAccount.toBuffer(),
+      stakePoolInfo.accounts.stakePoolAccount.toBuffer(),
+    ],
+      STAKE_POOL_PROGRAM_ID
+    );
+
+    try {
+      const validatorSeed = 0;
+      const pendingAction = await queueAction(program, { addValidator: { validatorVoteAccount, validatorSeed } });
+      await program.methods.addValidator(validatorSeed)
+        .accounts({
+          stakePool: stakePoolInfo.accounts.stakePoolAccount,
+          reserveStake: stakePoolInfo.accounts.reserveStakeAccount,
+          withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+          validatorList: Keypair.generate().publicKey,
+          validatorStakeAccount: validatorStakeAccount,
+          validatorVoteAccount: validatorVoteAccount,
+          pendingAction,
+        })
+        .signers([owner.payer])
+        .rpc();
+
+      throw new Error("Add validator should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidValidatorList");
+    }
+  });
+
+
   it("Adds a validator to the pool", async () => {
     // check that the pool has no validators
     const pool = await getStakePool(connection, stakePoolInfo.accounts.stakePoolAccount);
@@ -122,6 +249,7 @@ describe("validators", () => {
 
     // send the addValidator transaction
     const validatorSeed = 0; // optional non-zero u32 seed used for generating the validator
+    const pendingAction = await queueAction(program, { addValidator: { validatorVoteAccount, validatorSeed } });
     const tx = await program.methods.addValidator(validatorSeed)
       .accounts({
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -130,6 +258,7 @@ describe("validators", () => {
         validatorList: stakePoolInfo.accounts.validatorListAccount,
         validatorStakeAccount: validatorStakeAccount,
         validatorVoteAccount: validatorVoteAccount,
+        pendingAction,
       })
       .signers([owner.payer])
       .transaction();
@@ -176,6 +305,7 @@ describe("validators", () => {
     );
 
     try {
+      const pendingAction = await queueAction(program, { removeValidator: { validatorStakeAccount } });
       await program.methods.removeValidator()
       .accountsPartial({
         owner: user.publicKey,
@@ -184,6 +314,7 @@ describe("validators", () => {
         validatorList: stakePoolInfo.accounts.validatorListAccount,
         validatorStakeAccount: validatorStakeAccount,
         transientStakeAccount: transientStakeAccount,
+        pendingAction,
       })
       .signers([user])
       .rpc();
@@ -216,6 +347,7 @@ describe("validators", () => {
     );
 
     // send the removeValidator transaction
+    const pendingAction = await queueAction(program, { removeValidator: { validatorStakeAccount } });
     const tx = await program.methods.removeValidator()
       .accounts({
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -223,6 +355,7 @@ describe("validators", () => {
         validatorList: stakePoolInfo.accounts.validatorListAccount,
         validatorStakeAccount: validatorStakeAccount,
         transientStakeAccount: transientStakeAccount,
+        pendingAction,
       })
       .signers([owner.payer])
       .transaction();
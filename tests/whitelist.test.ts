@@ -1,7 +1,17 @@
 import * as anchor from "@coral-xyz/anchor";
-import { fetchEvent, initStaker } from "./helpers";
+import {
+  AGENT_CAN_BLACKLIST,
+  AGENT_CAN_CLEAR,
+  AGENT_CAN_MANAGE_AGENTS,
+  AGENT_CAN_WHITELIST,
+  fetchEvent,
+  getEvent,
+  getEvents,
+  initStaker,
+  requestAirdrop,
+} from "./helpers";
 import { assert } from "chai";
-import { Keypair, PublicKey } from "@solana/web3.js";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 import { Staker } from "../target/types/staker";
 
 describe("Whitelist", () => {
@@ -12,6 +22,19 @@ describe("Whitelist", () => {
   let user: Keypair;
   let agent: Keypair;
 
+  const noDepositLimits = { maxLifetimeLamports: null, maxEpochLamports: null };
+  const userManagementPermissions = AGENT_CAN_WHITELIST | AGENT_CAN_BLACKLIST | AGENT_CAN_CLEAR;
+  const caseReference = Array.from(Buffer.concat([Buffer.from("CASE-2024-0042"), Buffer.alloc(18)]));
+  // blacklisting and clearing users in a batch requires a reason and a case reference
+  const batchUpdate = (user: PublicKey, status: object) => ({
+    user,
+    status,
+    depositLimits: noDepositLimits,
+    expiry: null,
+    reason: "whitelisted" in status ? null : { sanctions: {} },
+    caseReference: "whitelisted" in status ? null : caseReference,
+  });
+
   before(async () => {
     anchor.setProvider(provider);
     user = Keypair.generate();
@@ -21,7 +44,7 @@ describe("Whitelist", () => {
 
 
   it("Can add an agent", async () => {
-    const tx = await program.methods.addAgent(agent.publicKey).transaction();
+    const tx = await program.methods.addAgent(agent.publicKey, userManagementPermissions).transaction();
 
     const event = await fetchEvent(program, tx, 1);
 
@@ -30,6 +53,8 @@ describe("Whitelist", () => {
       event.data.newAgent.toString(),
       agent.publicKey.toString()
     );
+    assert.strictEqual(event.data.permissions, userManagementPermissions);
+    assert.strictEqual(event.data.addedBy.toString(), provider.wallet.publicKey.toString());
 
     // check agent is now added
     const [agent_address] = PublicKey.findProgramAddressSync(
@@ -38,11 +63,13 @@ describe("Whitelist", () => {
     );
     const new_agent = await program.account.agent.fetch(agent_address);
     assert.ok(new_agent, "Agent account should exist");
+    assert.strictEqual(new_agent.permissions, userManagementPermissions);
+    assert.strictEqual(new_agent.createdBy.toString(), provider.wallet.publicKey.toString());
   });
 
   it("Adding an existing agent fails", async () => {
     try {
-      await program.methods.addAgent(agent.publicKey).rpc();
+      await program.methods.addAgent(agent.publicKey, userManagementPermissions).rpc();
       throw new Error("Adding an existing agent should fail");
     } catch (e) {
       // 0x00 code reflects that an account already exists
@@ -56,7 +83,7 @@ describe("Whitelist", () => {
   it("Non-agent adding an existing agent fails", async () => {
     try {
       await program.methods
-        .addAgent(agent.publicKey)
+        .addAgent(agent.publicKey, userManagementPermissions)
         .accounts({
           signer: user.publicKey,
         })
@@ -78,6 +105,7 @@ describe("Whitelist", () => {
       event.data.removedAgent.toString(),
       agent.publicKey.toString()
     );
+    assert.strictEqual(event.data.removedBy.toString(), provider.wallet.publicKey.toString());
 
     // check agent does not exist
     const [agent_address] = PublicKey.findProgramAddressSync(
@@ -106,7 +134,7 @@ describe("Whitelist", () => {
   });
 
   it("Non-agent removing an agent fails", async () => {
-    await program.methods.addAgent(agent.publicKey).rpc();
+    await program.methods.addAgent(agent.publicKey, userManagementPermissions).rpc();
 
     try {
       await program.methods
@@ -137,9 +165,177 @@ describe("Whitelist", () => {
     }
   });
 
+  it("Adding an agent with unknown permissions fails", async () => {
+    try {
+      await program.methods.addAgent(Keypair.generate().publicKey, 1 << 7).rpc();
+      throw new Error("Adding an agent with unknown permissions should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidAgentPermissions");
+    }
+  });
+
+  it("Agent without the manage agents permission adding an agent fails", async () => {
+    await requestAirdrop(provider.connection, agent.publicKey, 1);
+    try {
+      await program.methods
+        .addAgent(Keypair.generate().publicKey, AGENT_CAN_WHITELIST)
+        .accounts({
+          signer: agent.publicKey,
+        })
+        .signers([agent])
+        .rpc();
+      throw new Error("Agent without the manage agents permission should not add agents");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "MissingAgentPermission");
+    }
+  });
+
+  it("Only the owner can grant and revoke the manage agents permission", async () => {
+    const agentManager = Keypair.generate();
+    await requestAirdrop(provider.connection, agentManager.publicKey, 1);
+    await program.methods.addAgent(agentManager.publicKey, AGENT_CAN_MANAGE_AGENTS).rpc();
+
+    // an agent manager can add agents without the manage agents permission
+    const onboardingAgent = Keypair.generate();
+    await program.methods
+      .addAgent(onboardingAgent.publicKey, AGENT_CAN_WHITELIST)
+      .accounts({
+        signer: agentManager.publicKey,
+      })
+      .signers([agentManager])
+      .rpc();
+    const [onboardingAgentAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("agent"), onboardingAgent.publicKey.toBuffer()],
+      program.programId
+    );
+    const onboardingAgentAccount = await program.account.agent.fetch(onboardingAgentAddress);
+    assert.strictEqual(onboardingAgentAccount.createdBy.toString(), agentManager.publicKey.toString());
+
+    // but cannot grant the manage agents permission
+    try {
+      await program.methods
+        .addAgent(Keypair.generate().publicKey, AGENT_CAN_MANAGE_AGENTS)
+        .accounts({
+          signer: agentManager.publicKey,
+        })
+        .signers([agentManager])
+        .rpc();
+      throw new Error("Agent manager should not grant the manage agents permission");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+
+    // nor remove other agent managers
+    const otherAgentManager = Keypair.generate();
+    await program.methods.addAgent(otherAgentManager.publicKey, AGENT_CAN_MANAGE_AGENTS).rpc();
+    try {
+      await program.methods
+        .removeAgent(otherAgentManager.publicKey)
+        .accounts({
+          signer: agentManager.publicKey,
+        })
+        .signers([agentManager])
+        .rpc();
+      throw new Error("Agent manager should not remove other agent managers");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+
+    // an agent without the whitelist permission cannot whitelist users
+    try {
+      await program.methods
+        .addUserToWhitelist(Keypair.generate().publicKey, noDepositLimits, null)
+        .accounts({
+          signer: agentManager.publicKey,
+        })
+        .signers([agentManager])
+        .rpc();
+      throw new Error("Agent without the whitelist permission should not whitelist users");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "MissingAgentPermission");
+    }
+  });
+
+  it("Adding and removing agents updates the active agent count", async () => {
+    const [accessAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("access")],
+      program.programId
+    );
+    const activeAgentsPre = (await program.account.access.fetch(accessAddress)).activeAgents;
+
+    const newAgent = Keypair.generate();
+    await program.methods.addAgent(newAgent.publicKey, AGENT_CAN_WHITELIST).rpc();
+    assert.strictEqual((await program.account.access.fetch(accessAddress)).activeAgents, activeAgentsPre + 1);
+
+    await program.methods.removeAgent(newAgent.publicKey).rpc();
+    assert.strictEqual((await program.account.access.fetch(accessAddress)).activeAgents, activeAgentsPre);
+  });
+
+  it("Migrating an agent at the latest layout fails", async () => {
+    try {
+      await program.methods.migrateAgent(agent.publicKey, AGENT_CAN_WHITELIST).rpc();
+      throw new Error("Migrating an agent at the latest layout should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "AgentAlreadyMigrated");
+    }
+  });
+
+  it("Non-owner migrating an agent fails", async () => {
+    try {
+      await program.methods
+        .migrateAgent(agent.publicKey, AGENT_CAN_WHITELIST)
+        .accounts({
+          owner: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Non-owner migrating an agent should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Non-owner setting the maximum number of agents fails", async () => {
+    try {
+      await program.methods
+        .setMaxAgents(10)
+        .accountsPartial({
+          owner: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Non-owner setting the maximum number of agents should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Adding agents beyond the maximum fails", async () => {
+    const [accessAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("access")],
+      program.programId
+    );
+    const activeAgents = (await program.account.access.fetch(accessAddress)).activeAgents;
+
+    const txHash = await program.methods.setMaxAgents(activeAgents).rpc({ commitment: "confirmed" });
+    const event = await getEvent(program, txHash, "maxAgentsSet");
+    assert.ok(event);
+    assert.strictEqual(event.data.oldMaxAgents, null);
+    assert.strictEqual(event.data.newMaxAgents, activeAgents);
+
+    try {
+      await program.methods.addAgent(Keypair.generate().publicKey, AGENT_CAN_WHITELIST).rpc();
+      throw new Error("Adding agents beyond the maximum should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "MaxAgentsReached");
+    }
+
+    await program.methods.setMaxAgents(null).rpc();
+  });
+
   it("Can add a user to the whitelist", async () => {
     const tx = await program.methods
-      .addUserToWhitelist(user.publicKey)
+      .addUserToWhitelist(user.publicKey, noDepositLimits, null)
       .transaction();
 
     const event = await fetchEvent(program, tx, 1);
@@ -164,9 +360,96 @@ describe("Whitelist", () => {
     );
   });
 
+  it("Can add a user to the whitelist with deposit limits", async () => {
+    const limitedUser = Keypair.generate();
+    const depositLimits = {
+      maxLifetimeLamports: new anchor.BN(100 * LAMPORTS_PER_SOL),
+      maxEpochLamports: new anchor.BN(10 * LAMPORTS_PER_SOL),
+    };
+    await program.methods.addUserToWhitelist(limitedUser.publicKey, depositLimits, null).rpc();
+
+    // check the user's deposit limits are set
+    const [user_address] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), limitedUser.publicKey.toBuffer()],
+      program.programId
+    );
+    const whitelist_user = await program.account.userStatus.fetch(user_address);
+    assert.strictEqual(
+      whitelist_user.depositLimits.maxLifetimeLamports.toNumber(),
+      depositLimits.maxLifetimeLamports.toNumber()
+    );
+    assert.strictEqual(
+      whitelist_user.depositLimits.maxEpochLamports.toNumber(),
+      depositLimits.maxEpochLamports.toNumber()
+    );
+    assert.strictEqual(whitelist_user.totalDepositedLamports.toNumber(), 0);
+  });
+
+  it("Whitelisting a user with an expiry in the past fails", async () => {
+    const expiringUser = Keypair.generate();
+    const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+    try {
+      await program.methods
+        .addUserToWhitelist(expiringUser.publicKey, noDepositLimits, new anchor.BN(now - 60))
+        .rpc();
+      throw new Error("Whitelisting with an expiry in the past should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidWhitelistExpiry");
+    }
+  });
+
+  it("Can whitelist a user with an expiry and renew it", async () => {
+    const expiringUser = Keypair.generate();
+    const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+    const expiry = new anchor.BN(now + 3600);
+
+    const txHash = await program.methods
+      .addUserToWhitelist(expiringUser.publicKey, noDepositLimits, expiry)
+      .rpc({ commitment: "confirmed" });
+
+    // verify the WhitelistExpirySet event
+    let event = await getEvent(program, txHash, "whitelistExpirySet");
+    assert.ok(event);
+    assert.strictEqual(event.data.user.toString(), expiringUser.publicKey.toString());
+    assert.strictEqual(event.data.expiry.toNumber(), expiry.toNumber());
+
+    // renew the whitelisting
+    const newExpiry = expiry.addn(3600);
+    const renewTxHash = await program.methods
+      .renewWhitelist(expiringUser.publicKey, newExpiry)
+      .rpc({ commitment: "confirmed" });
+
+    event = await getEvent(program, renewTxHash, "whitelistExpirySet");
+    assert.ok(event);
+    assert.strictEqual(event.data.expiry.toNumber(), newExpiry.toNumber());
+
+    // check the new expiry is set
+    const [user_address] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), expiringUser.publicKey.toBuffer()],
+      program.programId
+    );
+    const whitelist_user = await program.account.userStatus.fetch(user_address);
+    assert.strictEqual(whitelist_user.whitelistExpiry.toNumber(), newExpiry.toNumber());
+  });
+
+  it("Non-agent renewing a whitelisting fails", async () => {
+    const nonAgent = Keypair.generate();
+    try {
+      await program.methods
+        .renewWhitelist(user.publicKey, null)
+        .accounts({ signer: nonAgent.publicKey })
+        .signers([nonAgent])
+        .rpc();
+      throw new Error("Non-agent renewing a whitelisting should fail");
+    } catch (e) {
+      // the agent pda derived with this signer, who is not an agent, does not exist.
+      assert.strictEqual(e.error.errorCode.code, "AccountNotInitialized");
+    }
+  });
+
   it("Adding a whitelisted user to the whitelist fails", async () => {
     try {
-      await program.methods.addUserToWhitelist(user.publicKey).rpc();
+      await program.methods.addUserToWhitelist(user.publicKey, noDepositLimits, null).rpc();
       throw new Error("Readding user to whitelist should fail");
     } catch (e) {
       assert.strictEqual(e.error.errorCode.code, "AlreadyWhitelisted");
@@ -175,7 +458,7 @@ describe("Whitelist", () => {
 
   it("Can add a user to the blacklist", async () => {
     const tx = await program.methods
-      .addUserToBlacklist(user.publicKey)
+      .addUserToBlacklist(user.publicKey, { fraud: {} }, caseReference)
       .transaction();
 
     const event = await fetchEvent(program, tx, 0);
@@ -186,6 +469,8 @@ describe("Whitelist", () => {
 
     assert.ok("whitelisted" in event.data.oldStatus);
     assert.ok("blacklisted" in event.data.newStatus);
+    assert.ok("fraud" in event.data.reason);
+    assert.deepEqual(event.data.caseReference, caseReference);
 
     // check user is now blacklisted
     const [user_address] = PublicKey.findProgramAddressSync(
@@ -198,11 +483,13 @@ describe("Whitelist", () => {
       JSON.stringify(blacklist_user.status),
       JSON.stringify({ blacklisted: {} })
     );
+    assert.ok("fraud" in blacklist_user.statusChangeReason);
+    assert.deepEqual(blacklist_user.caseReference, caseReference);
   });
 
   it("Adding a blacklisted user to the blacklist fails", async () => {
     try {
-      await program.methods.addUserToBlacklist(user.publicKey).rpc();
+      await program.methods.addUserToBlacklist(user.publicKey, { fraud: {} }, caseReference).rpc();
       throw new Error("Readding user to blacklist should fail");
     } catch (e) {
       assert.strictEqual(e.error.errorCode.code, "AlreadyBlacklisted");
@@ -212,7 +499,7 @@ describe("Whitelist", () => {
   it("Non-agent adding a user to the blacklist fails", async () => {
     try {
       await program.methods
-        .addUserToBlacklist(agent.publicKey)
+        .addUserToBlacklist(agent.publicKey, { fraud: {} }, caseReference)
         .accounts({
           signer: user.publicKey,
         })
@@ -226,7 +513,7 @@ describe("Whitelist", () => {
 
   it("Can clear a user's status", async () => {
     const tx = await program.methods
-      .clearUserStatus(user.publicKey)
+      .clearUserStatus(user.publicKey, { userRequest: {} }, caseReference)
       .transaction();
 
     const event = await fetchEvent(program, tx, 0);
@@ -237,6 +524,8 @@ describe("Whitelist", () => {
 
     assert.ok("blacklisted" in event.data.oldStatus);
     assert.ok("none" in event.data.newStatus);
+    assert.ok("userRequest" in event.data.reason);
+    assert.deepEqual(event.data.caseReference, caseReference);
 
     // check user is now blacklisted
     const [user_address] = PublicKey.findProgramAddressSync(
@@ -249,11 +538,12 @@ describe("Whitelist", () => {
       JSON.stringify(cleared_user.status),
       JSON.stringify({ none: {} })
     );
+    assert.ok("userRequest" in cleared_user.statusChangeReason);
   });
 
   it("Clearing the status of a cleared user fails", async () => {
     try {
-      await program.methods.clearUserStatus(user.publicKey).rpc();
+      await program.methods.clearUserStatus(user.publicKey, { userRequest: {} }, caseReference).rpc();
       throw new Error("Re-clearing user should fail");
     } catch (e) {
       assert.strictEqual(e.error.errorCode.code, "AlreadyCleared");
@@ -262,18 +552,25 @@ describe("Whitelist", () => {
 
   it("Clearing the status of a new user fails", async () => {
     try {
-      await program.methods.clearUserStatus(agent.publicKey).rpc();
-      throw new Error("Re-clearing user should fail");
+      await program.methods.clearUserStatus(agent.publicKey, { userRequest: {} }, caseReference).rpc();
+      throw new Error("Clearing a user without a record should fail");
     } catch (e) {
-      assert.strictEqual(e.error.errorCode.code, "AlreadyCleared");
+      assert.strictEqual(e.error.errorCode.code, "AccountNotInitialized");
     }
+
+    // check no record was created for the user
+    const [user_address] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), agent.publicKey.toBuffer()],
+      program.programId
+    );
+    assert.isNull(await provider.connection.getAccountInfo(user_address));
   });
 
   it("Non-agent clearing a user's status fails", async () => {
     try {
-      await program.methods.addUserToWhitelist(agent.publicKey).rpc();
+      await program.methods.addUserToWhitelist(agent.publicKey, noDepositLimits, null).rpc();
       await program.methods
-        .clearUserStatus(agent.publicKey)
+        .clearUserStatus(agent.publicKey, { userRequest: {} }, caseReference)
         .accounts({
           signer: user.publicKey,
         })
@@ -284,4 +581,306 @@ describe("Whitelist", () => {
       assert.strictEqual(e.error.errorCode.code, "AccountNotInitialized");
     }
   });
+
+  it("Closing the record of a user whose status is not cleared fails", async () => {
+    try {
+      await program.methods
+        .closeUserStatus(agent.publicKey)
+        .accounts({
+          rentRecipient: provider.wallet.publicKey,
+        })
+        .rpc();
+      throw new Error("Closing the record of a whitelisted user should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserStatusNotCleared");
+    }
+  });
+
+  it("Closing a user record with the wrong rent recipient fails", async () => {
+    try {
+      await program.methods
+        .closeUserStatus(user.publicKey)
+        .accounts({
+          rentRecipient: user.publicKey,
+        })
+        .rpc();
+      throw new Error("Closing a user record with the wrong rent recipient should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "InvalidRentRecipient");
+    }
+  });
+
+  it("Closing the record of a cleared user with deposit limits fails", async () => {
+    const limitedUser = Keypair.generate().publicKey;
+    const depositLimits = { maxLifetimeLamports: new anchor.BN(10 * LAMPORTS_PER_SOL), maxEpochLamports: null };
+    await program.methods.addUserToWhitelist(limitedUser, depositLimits, null).rpc();
+    await program.methods.clearUserStatus(limitedUser, { userRequest: {} }, caseReference).rpc();
+
+    try {
+      await program.methods
+        .closeUserStatus(limitedUser)
+        .accounts({
+          rentRecipient: provider.wallet.publicKey,
+        })
+        .rpc();
+      throw new Error("Closing the record of a user with deposit limits should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserStatusHasDepositHistory");
+    }
+  });
+
+  it("Can close the record of a cleared user", async () => {
+    const rentRecipient = Keypair.generate().publicKey;
+    await program.methods.setRentRecipient(rentRecipient).rpc();
+
+    const [user_address] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const rent = await provider.connection.getBalance(user_address);
+
+    const txHash = await program.methods
+      .closeUserStatus(user.publicKey)
+      .accounts({
+        rentRecipient: rentRecipient,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    // check the record is closed and its rent returned to the rent recipient
+    assert.isNull(await provider.connection.getAccountInfo(user_address));
+    assert.strictEqual(await provider.connection.getBalance(rentRecipient), rent);
+
+    const event = await getEvent(program, txHash, "userStatusClosed");
+    assert.ok(event);
+    assert.strictEqual(event.data.user.toString(), user.publicKey.toString());
+    assert.strictEqual(event.data.rentRecipient.toString(), rentRecipient.toString());
+
+    await program.methods.setRentRecipient(null).rpc();
+  });
+
+  it("Can migrate a whitelisted user to a new wallet", async () => {
+    const oldWallet = Keypair.generate();
+    const newWallet = Keypair.generate();
+    const depositLimits = {
+      maxLifetimeLamports: new anchor.BN(100 * LAMPORTS_PER_SOL),
+      maxEpochLamports: null,
+    };
+    const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+    const expiry = new anchor.BN(now + 3600);
+    await program.methods.addUserToWhitelist(oldWallet.publicKey, depositLimits, expiry).rpc();
+
+    const txHash = await program.methods
+      .migrateUser(oldWallet.publicKey)
+      .accounts({
+        newUser: newWallet.publicKey,
+      })
+      .signers([newWallet])
+      .rpc({ commitment: "confirmed" });
+
+    // check the record moved to the new wallet
+    const [oldAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), oldWallet.publicKey.toBuffer()],
+      program.programId
+    );
+    const [newAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), newWallet.publicKey.toBuffer()],
+      program.programId
+    );
+    assert.isNull(await provider.connection.getAccountInfo(oldAddress));
+    const migratedUser = await program.account.userStatus.fetch(newAddress);
+    assert.deepEqual(migratedUser.status, { whitelisted: {} });
+    assert.strictEqual(migratedUser.whitelistExpiry.toNumber(), expiry.toNumber());
+    assert.strictEqual(
+      migratedUser.depositLimits.maxLifetimeLamports.toNumber(),
+      depositLimits.maxLifetimeLamports.toNumber()
+    );
+
+    const event = await getEvent(program, txHash, "userMigrated");
+    assert.ok(event);
+    assert.strictEqual(event.data.oldUser.toString(), oldWallet.publicKey.toString());
+    assert.strictEqual(event.data.newUser.toString(), newWallet.publicKey.toString());
+  });
+
+  it("Migrating a user that is not whitelisted fails", async () => {
+    const oldWallet = Keypair.generate();
+    const newWallet = Keypair.generate();
+    await program.methods.addUserToBlacklist(oldWallet.publicKey, { fraud: {} }, caseReference).rpc();
+
+    try {
+      await program.methods
+        .migrateUser(oldWallet.publicKey)
+        .accounts({
+          newUser: newWallet.publicKey,
+        })
+        .signers([newWallet])
+        .rpc();
+      throw new Error("Migrating a blacklisted user should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserNotWhitelisted");
+    }
+  });
+
+  it("Migrating a user status record at the latest layout fails", async () => {
+    const migratedUser = Keypair.generate();
+    await program.methods.addUserToWhitelist(migratedUser.publicKey, noDepositLimits, null).rpc();
+
+    try {
+      await program.methods.migrateUserStatus(migratedUser.publicKey).rpc();
+      throw new Error("Migrating a user status record at the latest layout should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserStatusAlreadyMigrated");
+    }
+  });
+
+  it("Non-agent migrating a user status record fails", async () => {
+    const migratedUser = Keypair.generate();
+    await program.methods.addUserToWhitelist(migratedUser.publicKey, noDepositLimits, null).rpc();
+
+    try {
+      await program.methods
+        .migrateUserStatus(migratedUser.publicKey)
+        .accounts({
+          signer: user.publicKey,
+          agentAccount: null,
+        })
+        .signers([user])
+        .rpc();
+      throw new Error("Non-agent migrating a user status record should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "NotAuthorized");
+    }
+  });
+
+  it("Batch setting user statuses with mismatched accounts fails", async () => {
+    const batchUser = Keypair.generate();
+    try {
+      await program.methods
+        .batchSetUserStatus([batchUpdate(batchUser.publicKey, { whitelisted: {} })])
+        .remainingAccounts([])
+        .rpc();
+      throw new Error("Batch setting user statuses with mismatched accounts should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserStatusAccountsMismatch");
+    }
+  });
+
+  it("Can batch set user statuses, skipping unchanged entries", async () => {
+    const batchUsers = [Keypair.generate(), Keypair.generate()];
+    const userStatusAddresses = batchUsers.map(({ publicKey }) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("user"), publicKey.toBuffer()],
+        program.programId
+      )[0]
+    );
+    const remainingAccounts = userStatusAddresses.map((pubkey) => ({
+      pubkey,
+      isSigner: false,
+      isWritable: true,
+    }));
+
+    // whitelist the first user with deposit limits and an expiry, and blacklist the second one
+    const depositLimits = { maxLifetimeLamports: new anchor.BN(10 * LAMPORTS_PER_SOL), maxEpochLamports: null };
+    const expiry = new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
+    let txHash = await program.methods
+      .batchSetUserStatus([
+        { ...batchUpdate(batchUsers[0].publicKey, { whitelisted: {} }), depositLimits, expiry },
+        batchUpdate(batchUsers[1].publicKey, { blacklisted: {} }),
+      ])
+      .remainingAccounts(remainingAccounts)
+      .rpc({ commitment: "confirmed" });
+
+    let events = await getEvents(program, txHash, "whitelistingStatusChanged");
+    assert.strictEqual(events.length, 2);
+    assert.strictEqual(events[0].data.user.toString(), batchUsers[0].publicKey.toString());
+    assert.ok("whitelisted" in events[0].data.newStatus);
+    assert.strictEqual(events[1].data.user.toString(), batchUsers[1].publicKey.toString());
+    assert.ok("blacklisted" in events[1].data.newStatus);
+    assert.ok("sanctions" in events[1].data.reason);
+    assert.deepEqual(events[1].data.caseReference, caseReference);
+    const expiryEvent = await getEvent(program, txHash, "whitelistExpirySet");
+    assert.strictEqual(expiryEvent.data.user.toString(), batchUsers[0].publicKey.toString());
+    assert.strictEqual(expiryEvent.data.expiry.toNumber(), expiry.toNumber());
+
+    // re-whitelisting the first user is skipped while the second user is cleared
+    txHash = await program.methods
+      .batchSetUserStatus([
+        batchUpdate(batchUsers[0].publicKey, { whitelisted: {} }),
+        batchUpdate(batchUsers[1].publicKey, { none: {} }),
+      ])
+      .remainingAccounts(remainingAccounts)
+      .rpc({ commitment: "confirmed" });
+
+    events = await getEvents(program, txHash, "whitelistingStatusChanged");
+    assert.strictEqual(events.length, 1);
+    assert.strictEqual(events[0].data.user.toString(), batchUsers[1].publicKey.toString());
+    assert.ok("blacklisted" in events[0].data.oldStatus);
+    assert.ok("none" in events[0].data.newStatus);
+
+    // check the stored statuses
+    const firstStatus = await program.account.userStatus.fetch(userStatusAddresses[0]);
+    assert.equal(JSON.stringify(firstStatus.status), JSON.stringify({ whitelisted: {} }));
+    assert.strictEqual(firstStatus.whitelistExpiry.toNumber(), expiry.toNumber());
+    assert.strictEqual(
+      firstStatus.depositLimits.maxLifetimeLamports.toNumber(),
+      depositLimits.maxLifetimeLamports.toNumber()
+    );
+    const secondStatus = await program.account.userStatus.fetch(userStatusAddresses[1]);
+    assert.equal(JSON.stringify(secondStatus.status), JSON.stringify({ none: {} }));
+    assert.ok("sanctions" in secondStatus.statusChangeReason);
+    assert.deepEqual(secondStatus.caseReference, caseReference);
+  });
+
+  it("Batch blacklisting a user without a reason fails", async () => {
+    const batchUser = Keypair.generate();
+    const [userStatusAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), batchUser.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .batchSetUserStatus([{ ...batchUpdate(batchUser.publicKey, { blacklisted: {} }), reason: null }])
+        .remainingAccounts([{ pubkey: userStatusAddress, isSigner: false, isWritable: true }])
+        .rpc();
+      throw new Error("Batch blacklisting a user without a reason should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "MissingStatusChangeReason");
+    }
+  });
+
+  it("Batch clearing a user without a record does not create it", async () => {
+    const batchUser = Keypair.generate();
+    const [userStatusAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), batchUser.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .batchSetUserStatus([batchUpdate(batchUser.publicKey, { none: {} })])
+      .remainingAccounts([{ pubkey: userStatusAddress, isSigner: false, isWritable: true }])
+      .rpc({ commitment: "confirmed" });
+
+    assert.isNull(await provider.connection.getAccountInfo(userStatusAddress));
+
+    // the user can still be whitelisted afterwards
+    await program.methods.addUserToWhitelist(batchUser.publicKey, noDepositLimits, null).rpc();
+  });
+
+  it("Non-agent batch setting user statuses fails", async () => {
+    const batchUser = Keypair.generate();
+    const [userStatusAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), batchUser.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .batchSetUserStatus([batchUpdate(batchUser.publicKey, { whitelisted: {} })])
+        .accounts({ signer: user.publicKey })
+        .remainingAccounts([{ pubkey: userStatusAddress, isSigner: false, isWritable: true }])
+        .signers([user])
+        .rpc();
+      throw new Error("Non-agent batch setting user statuses should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "AccountNotInitialized");
+    }
+  });
 });
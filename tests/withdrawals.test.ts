@@ -20,12 +20,16 @@ import {
   getStakePoolSharePrice,
   increaseAdditionalValidatorStake,
   moveEpochForwardAndUpdatePool,
+  getEvent,
+  queueAction,
+  PAUSE_WITHDRAWALS,
 } from "./helpers";
 import { CreateStakePoolResponse} from "./stake_pool/types";
 import {
   TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
   createAssociatedTokenAccountInstruction,
+  getAccount,
 } from "@solana/spl-token";
 
 import { assert } from "chai";
@@ -45,6 +49,18 @@ describe("withdrawals", () => {
   let validatorVoteAccount: PublicKey;
   let userPoolTokenATA: PublicKey;
 
+  function withdrawSolAccounts() {
+    return {
+      user: user.publicKey,
+      stakePool: stakePoolInfo.accounts.stakePoolAccount,
+      withdrawAuthority: stakePoolInfo.accounts.withdrawAuthorityAccount,
+      userPoolTokenAccount: userPoolTokenATA,
+      poolReserve: stakePoolInfo.accounts.reserveStakeAccount,
+      feeTokenAccount: stakePoolInfo.accounts.feesTokenAccount,
+      poolMint: stakePoolInfo.accounts.poolMintAccount,
+    };
+  }
+
   let stakePoolInfo: CreateStakePoolResponse;
 
   before(async () => {
@@ -65,10 +81,7 @@ describe("withdrawals", () => {
     stakeManager = Keypair.generate();
     staker = Keypair.generate();
 
-    program = await initStaker(
-      provider.wallet.publicKey,
-      stakeManager.publicKey,
-    );
+    program = anchor.workspace.Staker as anchor.Program<Staker>;
 
     // create the stake pool with the manager and staker authorities
     stakePoolInfo = await createStakePool(
@@ -77,6 +90,9 @@ describe("withdrawals", () => {
       staker // Staker authority
     );
 
+    // initialize the staker bound to the new stake pool
+    program = await initStaker(provider.wallet.publicKey, stakeManager.publicKey, stakePoolInfo.accounts.stakePoolAccount);
+
     // add the default validator to the stake pool
     await addValidatorToStakePool(
       validatorVoteAccount,
@@ -113,7 +129,7 @@ describe("withdrawals", () => {
     // "Deposit 20 SOL to the stake pool
     const depositAmount = new BN(20 * LAMPORTS_PER_SOL);
     const depositTx = await program.methods
-      .deposit(depositAmount)
+      .deposit(depositAmount, null, null)
       .accounts({
         user: user.publicKey,
         stakePool: stakePoolInfo.accounts.stakePoolAccount,
@@ -336,4 +352,73 @@ describe("withdrawals", () => {
     }
   });
 
+  it("Withdraw SOL when withdrawals are paused fails", async () => {
+    await program.methods.pause(PAUSE_WITHDRAWALS).rpc();
+    try {
+      await program.methods
+        .withdrawSol(new BN(1 * LAMPORTS_PER_SOL))
+        .accounts(withdrawSolAccounts())
+        .signers([user])
+        .rpc();
+
+      throw new Error("Withdraw SOL when withdrawals are paused should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "ContractPaused");
+    } finally {
+      const pendingAction = await queueAction(program, { unpause: { flags: PAUSE_WITHDRAWALS } });
+      await program.methods.unpause(PAUSE_WITHDRAWALS).accounts({ pendingAction }).rpc();
+    }
+  });
+
+  it("Withdraw SOL for a blacklisted user fails", async () => {
+    await program.methods.addUserToBlacklist(user.publicKey, { other: {} }, Array(32).fill(0)).rpc();
+    try {
+      await program.methods
+        .withdrawSol(new BN(1 * LAMPORTS_PER_SOL))
+        .accounts(withdrawSolAccounts())
+        .signers([user])
+        .rpc();
+
+      throw new Error("Withdraw SOL for a blacklisted user should fail");
+    } catch (e) {
+      assert.strictEqual(e.error.errorCode.code, "UserBlacklisted");
+    } finally {
+      await addUserToWhitelist(program, user.publicKey);
+    }
+  });
+
+  it("Withdraw SOL from the reserve", async () => {
+    const withdrawAmount = new BN(1 * LAMPORTS_PER_SOL);
+
+    const userTokenAccountPre = await getAccount(connection, userPoolTokenATA);
+    const userBalancePre = await connection.getBalance(user.publicKey);
+
+    const tx = await program.methods
+      .withdrawSol(withdrawAmount)
+      .accounts(withdrawSolAccounts())
+      .transaction();
+
+    const txHash = await provider.sendAndConfirm(tx, [user], {
+      commitment: "confirmed",
+    });
+
+    // verify the pool tokens were burned
+    const userTokenAccount = await getAccount(connection, userPoolTokenATA);
+    assert.equal(
+      Number(userTokenAccount.amount),
+      Number(userTokenAccountPre.amount) - withdrawAmount.toNumber()
+    );
+
+    // verify the Withdrawn event was emitted with the correct data
+    const event = await getEvent(program, txHash, "withdrawn");
+    assert.ok(event);
+    assert.strictEqual(event.data.user.toBase58(), user.publicKey.toBase58());
+    assert.strictEqual(event.data.poolTokens.toNumber(), withdrawAmount.toNumber());
+    assert(event.data.lamports.toNumber() > 0);
+
+    // verify the user received the lamports, net of the transaction fee
+    const userBalance = await connection.getBalance(user.publicKey);
+    assert(userBalance > userBalancePre);
+  });
+
 });